@@ -0,0 +1,13 @@
+#version 400 core
+layout (location = 0) in vec3 in_pos;
+layout (location = 1) in vec4 in_color;
+
+out vec4 color;
+
+uniform mat4 view_projection;
+
+void main()
+{
+    gl_Position = view_projection * vec4(in_pos, 1);
+    color = in_color;
+}
@@ -0,0 +1,169 @@
+#version 440 core
+
+in vec3 world_pos;
+in vec3 world_normal;
+in vec2 texcoord;
+in vec4 world_tangent;
+
+uniform sampler2D base_color_tex;
+uniform sampler2D metallic_roughness_tex;
+uniform sampler2D normal_tex;
+uniform sampler2D occlusion_tex;
+uniform sampler2D emissive_tex;
+uniform samplerCube environment_tex;
+
+uniform vec4 camera_pos;
+uniform vec4 light_direction;
+// rgb color, a intensity
+uniform vec4 light_color;
+
+uniform vec4 base_color_factor;
+uniform vec4 emissive_factor;
+// metallic, roughness, occlusion strength, normal scale
+uniform vec4 material_params;
+// enabled, intensity, mip levels of the cubemap
+uniform vec4 environment_params;
+// elapsed seconds, delta, real elapsed seconds, frame number. Stops while the game is paused
+uniform vec4 time;
+
+
+layout(location = 0) out vec4 diffuseColor;
+
+const float PI = 3.14159265359;
+
+vec3 sample_normal_map(vec2 uv)
+{
+    vec3 map = texture(normal_tex, uv).xyz * 2.0 - 1.0;
+    map.xy *= material_params.w;
+
+    return map;
+}
+
+// tangent space normal mapping with the mikktspace tangents of the mesh
+vec3 perturb_normal_tangent(vec3 N, vec4 tangent, vec2 uv)
+{
+    // re-orthogonalize, interpolation does not keep the two perpendicular
+    vec3 T = normalize(tangent.xyz - N * dot(N, tangent.xyz));
+    vec3 B = cross(N, T) * tangent.w;
+
+    return normalize(mat3(T, B, N) * sample_normal_map(uv));
+}
+
+// without vertex tangents the tangent frame is reconstructed from the screen space derivatives
+vec3 perturb_normal_derivatives(vec3 N, vec3 p, vec2 uv)
+{
+    vec3 dp1 = dFdx(p);
+    vec3 dp2 = dFdy(p);
+    vec2 duv1 = dFdx(uv);
+    vec2 duv2 = dFdy(uv);
+
+    vec3 dp2perp = cross(dp2, N);
+    vec3 dp1perp = cross(N, dp1);
+    vec3 T = dp2perp * duv1.x + dp1perp * duv2.x;
+    vec3 B = dp2perp * duv1.y + dp1perp * duv2.y;
+
+    float len = max(dot(T, T), dot(B, B));
+    if (len == 0.0) {
+        // no uvs, nothing to map
+        return N;
+    }
+
+    float invmax = inversesqrt(len);
+    mat3 TBN = mat3(T * invmax, B * invmax, N);
+
+    return normalize(TBN * sample_normal_map(uv));
+}
+
+float distribution_ggx(float NdotH, float roughness)
+{
+    float a = roughness * roughness;
+    float a2 = a * a;
+    float denom = NdotH * NdotH * (a2 - 1.0) + 1.0;
+
+    return a2 / (PI * denom * denom);
+}
+
+float geometry_schlick_ggx(float NdotX, float roughness)
+{
+    float r = roughness + 1.0;
+    float k = (r * r) / 8.0;
+
+    return NdotX / (NdotX * (1.0 - k) + k);
+}
+
+vec3 fresnel_schlick(float cos_theta, vec3 F0)
+{
+    return F0 + (1.0 - F0) * pow(clamp(1.0 - cos_theta, 0.0, 1.0), 5.0);
+}
+
+// analytic fit of the split sum brdf lut (Karis, "Physically Based Shading on Mobile")
+vec3 env_brdf_approx(vec3 F0, float roughness, float NdotV)
+{
+    const vec4 c0 = vec4(-1.0, -0.0275, -0.572, 0.022);
+    const vec4 c1 = vec4(1.0, 0.0425, 1.04, -0.04);
+    vec4 r = roughness * c0 + c1;
+    float a004 = min(r.x * r.x, exp2(-9.28 * NdotV)) * r.x + r.y;
+    vec2 AB = vec2(-1.04, 1.04) * a004 + r.zw;
+
+    return F0 * AB.x + AB.y;
+}
+
+void main()
+{
+    vec4 base_color = texture(base_color_tex, texcoord) * base_color_factor;
+    vec4 metallic_roughness = texture(metallic_roughness_tex, texcoord);
+
+    float metallic = clamp(metallic_roughness.b * material_params.x, 0.0, 1.0);
+    float roughness = clamp(metallic_roughness.g * material_params.y, 0.04, 1.0);
+    float occlusion = mix(1.0, texture(occlusion_tex, texcoord).r, material_params.z);
+    vec3 emissive = texture(emissive_tex, texcoord).rgb * emissive_factor.rgb;
+
+    vec3 N = normalize(world_normal);
+    if (dot(world_tangent.xyz, world_tangent.xyz) > 0.0) {
+        N = perturb_normal_tangent(N, world_tangent, texcoord);
+    } else {
+        N = perturb_normal_derivatives(N, world_pos, texcoord);
+    }
+    vec3 V = normalize(camera_pos.xyz - world_pos);
+    vec3 L = normalize(-light_direction.xyz);
+    vec3 H = normalize(V + L);
+
+    float NdotV = max(dot(N, V), 1e-4);
+    float NdotL = max(dot(N, L), 0.0);
+    float NdotH = max(dot(N, H), 0.0);
+    float HdotV = max(dot(H, V), 0.0);
+
+    vec3 F0 = mix(vec3(0.04), base_color.rgb, metallic);
+
+    // cook-torrance specular and lambert diffuse for the directional light
+    float D = distribution_ggx(NdotH, roughness);
+    float G = geometry_schlick_ggx(NdotV, roughness) * geometry_schlick_ggx(NdotL, roughness);
+    vec3 F = fresnel_schlick(HdotV, F0);
+
+    vec3 specular = D * G * F / (4.0 * NdotV * NdotL + 1e-4);
+    vec3 kd = (1.0 - F) * (1.0 - metallic);
+
+    vec3 radiance = light_color.rgb * light_color.a;
+    vec3 color = (kd * base_color.rgb / PI + specular) * radiance * NdotL;
+
+    // ambient, either from the environment cubemap or a dim constant
+    vec3 ambient;
+    if (environment_params.x > 0.5) {
+        float max_lod = environment_params.z - 1.0;
+        vec3 R = reflect(-V, N);
+
+        vec3 irradiance = textureLod(environment_tex, N, max_lod).rgb;
+        vec3 prefiltered = textureLod(environment_tex, R, roughness * max_lod).rgb;
+
+        vec3 diffuse_ibl = irradiance * base_color.rgb * (1.0 - metallic);
+        vec3 specular_ibl = prefiltered * env_brdf_approx(F0, roughness, NdotV);
+
+        ambient = (diffuse_ibl + specular_ibl) * environment_params.y;
+    } else {
+        ambient = vec3(0.03) * base_color.rgb;
+    }
+
+    color += ambient * occlusion + emissive;
+
+    diffuseColor = vec4(color, base_color.a);
+}
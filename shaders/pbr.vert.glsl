@@ -0,0 +1,27 @@
+#version 400 core
+layout (location = 0) in vec3 in_pos;
+layout (location = 1) in vec3 in_normal;
+layout (location = 2) in vec2 uv_pos;
+layout (location = 3) in vec4 in_tangent;
+
+out vec3 world_pos;
+out vec3 world_normal;
+out vec2 texcoord;
+out vec4 world_tangent;
+
+
+uniform mat4 model;
+uniform mat4 view;
+uniform mat4 projection;
+
+void main()
+{
+    vec4 world = model * vec4(in_pos, 1);
+
+    gl_Position = projection * view * world;
+
+    world_pos = world.xyz;
+    world_normal = mat3(transpose(inverse(model))) * in_normal;
+    world_tangent = vec4(mat3(model) * in_tangent.xyz, in_tangent.w);
+    texcoord = uv_pos;
+}
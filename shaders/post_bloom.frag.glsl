@@ -0,0 +1,40 @@
+#version 440 core
+
+// params: x brightness threshold, y bloom intensity, z blur radius in pixels
+
+in vec2 texcoord;
+
+uniform sampler2D source;
+uniform vec4 resolution;
+uniform vec4 params;
+
+layout(location = 0) out vec4 diffuseColor;
+
+vec3 bright(vec2 uv)
+{
+    vec3 color = texture(source, uv).rgb;
+    float luma = dot(color, vec3(0.2126, 0.7152, 0.0722));
+
+    return color * max(luma - params.x, 0.0) / max(luma, 1e-4);
+}
+
+void main()
+{
+    vec4 color = texture(source, texcoord);
+
+    // two rings of taps around the pixel, weighted by distance
+    vec3 glow = vec3(0.0);
+    float total = 0.0;
+    for (int ring = 1; ring <= 2; ring++) {
+        for (int i = 0; i < 12; i++) {
+            float angle = 6.2831853 * (float(i) + 0.5 * float(ring)) / 12.0;
+            vec2 offset = vec2(cos(angle), sin(angle)) * params.z * float(ring * ring);
+            float weight = 1.0 / float(ring);
+
+            glow += bright(texcoord + offset * resolution.zw) * weight;
+            total += weight;
+        }
+    }
+
+    diffuseColor = vec4(color.rgb + glow / total * params.y, color.a);
+}
@@ -0,0 +1,12 @@
+#version 440 core
+
+in vec2 texcoord;
+
+uniform sampler2D source;
+
+layout(location = 0) out vec4 diffuseColor;
+
+void main()
+{
+    diffuseColor = texture(source, texcoord);
+}
@@ -0,0 +1,57 @@
+#version 440 core
+
+// params: x maximum blur span in pixels, y reduce multiplier, z minimum reduce
+// works on gamma corrected colors, keep it after the gamma pass
+
+in vec2 texcoord;
+
+uniform sampler2D source;
+uniform vec4 resolution;
+uniform vec4 params;
+
+layout(location = 0) out vec4 diffuseColor;
+
+float luma(vec3 color)
+{
+    return dot(color, vec3(0.299, 0.587, 0.114));
+}
+
+void main()
+{
+    vec2 px = resolution.zw;
+
+    float luma_nw = luma(texture(source, texcoord + vec2(-1.0, -1.0) * px).rgb);
+    float luma_ne = luma(texture(source, texcoord + vec2( 1.0, -1.0) * px).rgb);
+    float luma_sw = luma(texture(source, texcoord + vec2(-1.0,  1.0) * px).rgb);
+    float luma_se = luma(texture(source, texcoord + vec2( 1.0,  1.0) * px).rgb);
+    vec4 center = texture(source, texcoord);
+    float luma_m = luma(center.rgb);
+
+    float luma_min = min(luma_m, min(min(luma_nw, luma_ne), min(luma_sw, luma_se)));
+    float luma_max = max(luma_m, max(max(luma_nw, luma_ne), max(luma_sw, luma_se)));
+
+    // blur along the edge, perpendicular to the luma gradient
+    vec2 dir = vec2(
+        -((luma_nw + luma_ne) - (luma_sw + luma_se)),
+        ((luma_nw + luma_sw) - (luma_ne + luma_se))
+    );
+
+    float reduce = max((luma_nw + luma_ne + luma_sw + luma_se) * 0.25 * params.y, params.z);
+    float scale = 1.0 / (min(abs(dir.x), abs(dir.y)) + reduce);
+    dir = clamp(dir * scale, vec2(-params.x), vec2(params.x)) * px;
+
+    vec3 a = 0.5 * (
+        texture(source, texcoord + dir * (1.0 / 3.0 - 0.5)).rgb +
+        texture(source, texcoord + dir * (2.0 / 3.0 - 0.5)).rgb);
+    vec3 b = a * 0.5 + 0.25 * (
+        texture(source, texcoord + dir * -0.5).rgb +
+        texture(source, texcoord + dir * 0.5).rgb);
+
+    // the wider blur overshot the edge, fall back to the narrow one
+    float luma_b = luma(b);
+    if (luma_b < luma_min || luma_b > luma_max) {
+        diffuseColor = vec4(a, center.a);
+    } else {
+        diffuseColor = vec4(b, center.a);
+    }
+}
@@ -0,0 +1,18 @@
+#version 440 core
+
+// params: x gamma
+
+in vec2 texcoord;
+
+uniform sampler2D source;
+uniform vec4 resolution;
+uniform vec4 params;
+
+layout(location = 0) out vec4 diffuseColor;
+
+void main()
+{
+    vec4 color = texture(source, texcoord);
+
+    diffuseColor = vec4(pow(max(color.rgb, 0.0), vec3(1.0 / params.x)), color.a);
+}
@@ -0,0 +1,30 @@
+#version 440 core
+
+// params: x exposure
+
+in vec2 texcoord;
+
+uniform sampler2D source;
+uniform vec4 resolution;
+uniform vec4 params;
+
+layout(location = 0) out vec4 diffuseColor;
+
+// filmic curve fitted to aces (Narkowicz)
+vec3 aces(vec3 x)
+{
+    const float a = 2.51;
+    const float b = 0.03;
+    const float c = 2.43;
+    const float d = 0.59;
+    const float e = 0.14;
+
+    return clamp((x * (a * x + b)) / (x * (c * x + d) + e), 0.0, 1.0);
+}
+
+void main()
+{
+    vec4 color = texture(source, texcoord);
+
+    diffuseColor = vec4(aces(color.rgb * params.x), color.a);
+}
@@ -0,0 +1,22 @@
+#version 440 core
+
+// params: x strength, y radius where the darkening starts, z softness of the edge
+
+in vec2 texcoord;
+
+uniform sampler2D source;
+uniform vec4 resolution;
+uniform vec4 params;
+
+layout(location = 0) out vec4 diffuseColor;
+
+void main()
+{
+    vec4 color = texture(source, texcoord);
+
+    // keep the vignette round on non square windows
+    vec2 centered = (texcoord - 0.5) * vec2(resolution.x / resolution.y, 1.0);
+    float vignette = smoothstep(params.y, params.y - params.z, length(centered));
+
+    diffuseColor = vec4(color.rgb * mix(1.0 - params.x, 1.0, vignette), color.a);
+}
@@ -0,0 +1,14 @@
+#version 440 core
+
+in vec2 texcoord;
+in vec4 color;
+
+layout(location = 0) out vec4 diffuseColor;
+
+// outline fonts are white with the coverage in alpha, bitmap fonts keep their own colors
+uniform sampler2D atlas;
+
+void main()
+{
+    diffuseColor = color * texture(atlas, texcoord);
+}
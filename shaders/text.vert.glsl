@@ -0,0 +1,17 @@
+#version 400 core
+layout (location = 0) in vec3 in_pos;
+layout (location = 1) in vec2 in_uv;
+layout (location = 2) in vec4 in_color;
+
+out vec2 texcoord;
+out vec4 color;
+
+// window pixels for screen space text, projection * view for world space text
+uniform mat4 view_projection;
+
+void main()
+{
+    gl_Position = view_projection * vec4(in_pos, 1);
+    texcoord = in_uv;
+    color = in_color;
+}
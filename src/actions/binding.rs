@@ -0,0 +1,228 @@
+use std::fmt;
+
+use miniquad::{KeyCode, KeyMods, MouseButton};
+
+/// the physical thing a binding reacts to
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Input {
+    Key(KeyCode),
+    Mouse(MouseButton),
+}
+
+/// A key or mouse button together with the modifiers that have to be held with it
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Binding {
+    pub input: Input,
+    pub mods: KeyMods,
+}
+
+impl Binding {
+    /// Parse the config file form: modifiers joined with `+` in front of a key or mouse button,
+    /// e.g. `W`, `Ctrl+Shift+S` or `MouseLeft`. Keys use the names of miniquad's KeyCode, the
+    /// modifiers are Shift, Ctrl, Alt and Super. Case does not matter
+    pub fn parse(text: &str) -> Result<Self, String> {
+        let mut parts: Vec<&str> = text.split('+').map(|p| p.trim()).collect();
+        let name = parts.pop().unwrap_or_default();
+
+        let mut mods = KeyMods::default();
+        for modifier in parts {
+            let flag = match modifier.to_ascii_lowercase().as_str() {
+                "shift" => &mut mods.shift,
+                "ctrl" => &mut mods.ctrl,
+                "alt" => &mut mods.alt,
+                "super" => &mut mods.logo,
+                _ => return Err(format!("unknown modifier {:?} in {:?}", modifier, text)),
+            };
+            *flag = true;
+        }
+
+        let input = if let Some(button) = MOUSE_BUTTONS
+            .iter()
+            .find(|(button_name, _)| button_name.eq_ignore_ascii_case(name))
+        {
+            Input::Mouse(button.1)
+        } else if let Some(key) = KEYS
+            .iter()
+            .find(|key| format!("{:?}", key).eq_ignore_ascii_case(name))
+        {
+            Input::Key(*key)
+        } else {
+            return Err(format!("unknown key or mouse button {:?}", name));
+        };
+
+        return Ok(Self { input, mods });
+    }
+
+    pub fn is_modifier_key(key: KeyCode) -> bool {
+        matches!(
+            key,
+            KeyCode::LeftShift
+                | KeyCode::RightShift
+                | KeyCode::LeftControl
+                | KeyCode::RightControl
+                | KeyCode::LeftAlt
+                | KeyCode::RightAlt
+                | KeyCode::LeftSuper
+                | KeyCode::RightSuper
+        )
+    }
+}
+
+/// the form parse reads
+impl fmt::Display for Binding {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let modifiers = [
+            (self.mods.ctrl, "Ctrl+"),
+            (self.mods.alt, "Alt+"),
+            (self.mods.shift, "Shift+"),
+            (self.mods.logo, "Super+"),
+        ];
+        for (held, name) in modifiers {
+            if held {
+                f.write_str(name)?;
+            }
+        }
+
+        match self.input {
+            Input::Key(key) => write!(f, "{:?}", key),
+            Input::Mouse(button) => {
+                let name = MOUSE_BUTTONS
+                    .iter()
+                    .find(|(_, b)| *b == button)
+                    .map_or("MouseUnknown", |(name, _)| name);
+                f.write_str(name)
+            }
+        }
+    }
+}
+
+const MOUSE_BUTTONS: [(&str, MouseButton); 3] = [
+    ("MouseLeft", MouseButton::Left),
+    ("MouseMiddle", MouseButton::Middle),
+    ("MouseRight", MouseButton::Right),
+];
+
+/// every key that can be bound, KeyCode has no way to list its variants
+const KEYS: &[KeyCode] = &[
+    KeyCode::Space,
+    KeyCode::Apostrophe,
+    KeyCode::Comma,
+    KeyCode::Minus,
+    KeyCode::Period,
+    KeyCode::Slash,
+    KeyCode::Key0,
+    KeyCode::Key1,
+    KeyCode::Key2,
+    KeyCode::Key3,
+    KeyCode::Key4,
+    KeyCode::Key5,
+    KeyCode::Key6,
+    KeyCode::Key7,
+    KeyCode::Key8,
+    KeyCode::Key9,
+    KeyCode::Semicolon,
+    KeyCode::Equal,
+    KeyCode::A,
+    KeyCode::B,
+    KeyCode::C,
+    KeyCode::D,
+    KeyCode::E,
+    KeyCode::F,
+    KeyCode::G,
+    KeyCode::H,
+    KeyCode::I,
+    KeyCode::J,
+    KeyCode::K,
+    KeyCode::L,
+    KeyCode::M,
+    KeyCode::N,
+    KeyCode::O,
+    KeyCode::P,
+    KeyCode::Q,
+    KeyCode::R,
+    KeyCode::S,
+    KeyCode::T,
+    KeyCode::U,
+    KeyCode::V,
+    KeyCode::W,
+    KeyCode::X,
+    KeyCode::Y,
+    KeyCode::Z,
+    KeyCode::LeftBracket,
+    KeyCode::Backslash,
+    KeyCode::RightBracket,
+    KeyCode::GraveAccent,
+    KeyCode::World1,
+    KeyCode::World2,
+    KeyCode::Escape,
+    KeyCode::Enter,
+    KeyCode::Tab,
+    KeyCode::Backspace,
+    KeyCode::Insert,
+    KeyCode::Delete,
+    KeyCode::Right,
+    KeyCode::Left,
+    KeyCode::Down,
+    KeyCode::Up,
+    KeyCode::PageUp,
+    KeyCode::PageDown,
+    KeyCode::Home,
+    KeyCode::End,
+    KeyCode::CapsLock,
+    KeyCode::ScrollLock,
+    KeyCode::NumLock,
+    KeyCode::PrintScreen,
+    KeyCode::Pause,
+    KeyCode::F1,
+    KeyCode::F2,
+    KeyCode::F3,
+    KeyCode::F4,
+    KeyCode::F5,
+    KeyCode::F6,
+    KeyCode::F7,
+    KeyCode::F8,
+    KeyCode::F9,
+    KeyCode::F10,
+    KeyCode::F11,
+    KeyCode::F12,
+    KeyCode::F13,
+    KeyCode::F14,
+    KeyCode::F15,
+    KeyCode::F16,
+    KeyCode::F17,
+    KeyCode::F18,
+    KeyCode::F19,
+    KeyCode::F20,
+    KeyCode::F21,
+    KeyCode::F22,
+    KeyCode::F23,
+    KeyCode::F24,
+    KeyCode::F25,
+    KeyCode::Kp0,
+    KeyCode::Kp1,
+    KeyCode::Kp2,
+    KeyCode::Kp3,
+    KeyCode::Kp4,
+    KeyCode::Kp5,
+    KeyCode::Kp6,
+    KeyCode::Kp7,
+    KeyCode::Kp8,
+    KeyCode::Kp9,
+    KeyCode::KpDecimal,
+    KeyCode::KpDivide,
+    KeyCode::KpMultiply,
+    KeyCode::KpSubtract,
+    KeyCode::KpAdd,
+    KeyCode::KpEnter,
+    KeyCode::KpEqual,
+    KeyCode::LeftShift,
+    KeyCode::LeftControl,
+    KeyCode::LeftAlt,
+    KeyCode::LeftSuper,
+    KeyCode::RightShift,
+    KeyCode::RightControl,
+    KeyCode::RightAlt,
+    KeyCode::RightSuper,
+    KeyCode::Menu,
+    KeyCode::Back,
+];
@@ -0,0 +1,446 @@
+// Named actions and axes on top of the raw key and mouse events. Game code asks whether
+// move_forward is active instead of whether W is down, which keys that means comes from a config
+// file and can be changed while running
+mod binding;
+
+pub use binding::{Binding, Input};
+
+use std::{
+    collections::HashSet,
+    path::{Path, PathBuf},
+};
+
+use miniquad::{KeyCode, KeyMods, MouseButton};
+
+/// where the bindings are loaded from and saved to
+pub const DEFAULT_BINDINGS_FILE: &str = "./input.cfg";
+
+/// every action and axis the game uses, in the format of the config file
+const DEFAULT_BINDINGS: &str = include_str!("default_bindings.cfg");
+
+/// active while any of its bindings is held
+#[derive(Debug, Clone, PartialEq)]
+pub struct Action {
+    pub name: String,
+    pub bindings: Vec<Binding>,
+}
+
+/// -1..1 from two groups of bindings, like W and S for moving forward and back
+#[derive(Debug, Clone, PartialEq)]
+pub struct Axis {
+    pub name: String,
+    pub positive: Vec<Binding>,
+    pub negative: Vec<Binding>,
+}
+
+/// which list the next pressed key or mouse button is added to
+#[derive(Debug, Clone, PartialEq)]
+pub enum RebindTarget {
+    Action(String),
+    AxisPositive(String),
+    AxisNegative(String),
+}
+
+/// The contents of a bindings file, in file order
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Bindings {
+    pub actions: Vec<Action>,
+    pub axes: Vec<Axis>,
+}
+
+impl Bindings {
+    pub fn defaults() -> Self {
+        let mut bindings = Self::default();
+        bindings
+            .apply(DEFAULT_BINDINGS, "default bindings", true)
+            .expect("the default bindings are valid");
+
+        return bindings;
+    }
+
+    /// The defaults with everything the file binds replaced. Names the defaults do not know are
+    /// an error, they are most likely typos
+    pub fn load(path: &Path) -> Result<Self, String> {
+        let source = std::fs::read_to_string(path)
+            .map_err(|e| format!("could not read {}: {}", path.display(), e))?;
+
+        let mut bindings = Self::defaults();
+        bindings.apply(&source, &path.display().to_string(), false)?;
+
+        return Ok(bindings);
+    }
+
+    pub fn save(&self, path: &Path) -> Result<(), String> {
+        let list = |bindings: &[Binding]| {
+            bindings
+                .iter()
+                .map(|b| b.to_string())
+                .collect::<Vec<_>>()
+                .join(", ")
+        };
+
+        let mut text = String::from("[actions]\n");
+        for action in self.actions.iter() {
+            text += &format!("{} = {}\n", action.name, list(&action.bindings));
+        }
+
+        text += "\n[axes]\n# positive bindings / negative bindings\n";
+        for axis in self.axes.iter() {
+            text += &format!(
+                "{} = {} / {}\n",
+                axis.name,
+                list(&axis.positive),
+                list(&axis.negative)
+            );
+        }
+
+        return std::fs::write(path, text)
+            .map_err(|e| format!("could not save {}: {}", path.display(), e));
+    }
+
+    /// Read `name = bindings` lines under an [actions] or [axes] header into self, replacing the
+    /// bindings of names that are already there. Empty lines and lines starting with # are
+    /// skipped, an empty right hand side unbinds
+    fn apply(&mut self, source: &str, origin: &str, allow_new: bool) -> Result<(), String> {
+        let mut in_axes = None;
+
+        for (number, line) in source.lines().enumerate() {
+            let error = |message: String| format!("{} line {}: {}", origin, number + 1, message);
+
+            let line = line.trim();
+            if line.is_empty() || line.starts_with('#') {
+                continue;
+            }
+
+            match line {
+                "[actions]" => in_axes = Some(false),
+                "[axes]" => in_axes = Some(true),
+                _ => {
+                    let Some(in_axes) = in_axes else {
+                        return Err(error("expected [actions] or [axes] first".to_owned()));
+                    };
+                    let Some((name, value)) = line.split_once('=') else {
+                        return Err(error(format!("expected name = bindings, found {:?}", line)));
+                    };
+                    let name = name.trim();
+
+                    if in_axes {
+                        let Some((positive, negative)) = value.split_once('/') else {
+                            return Err(error(format!(
+                                "axis {} needs positive / negative bindings",
+                                name
+                            )));
+                        };
+                        let axis = Axis {
+                            name: name.to_owned(),
+                            positive: parse_list(positive).map_err(error)?,
+                            negative: parse_list(negative).map_err(error)?,
+                        };
+
+                        match self.axes.iter_mut().find(|a| a.name == name) {
+                            Some(existing) => *existing = axis,
+                            None if allow_new => self.axes.push(axis),
+                            None => return Err(error(format!("unknown axis {}", name))),
+                        }
+                    } else {
+                        let action = Action {
+                            name: name.to_owned(),
+                            bindings: parse_list(value).map_err(error)?,
+                        };
+
+                        match self.actions.iter_mut().find(|a| a.name == name) {
+                            Some(existing) => *existing = action,
+                            None if allow_new => self.actions.push(action),
+                            None => return Err(error(format!("unknown action {}", name))),
+                        }
+                    }
+                }
+            }
+        }
+
+        return Ok(());
+    }
+
+    fn list_mut(&mut self, target: &RebindTarget) -> Option<&mut Vec<Binding>> {
+        match target {
+            RebindTarget::Action(name) => self
+                .actions
+                .iter_mut()
+                .find(|a| a.name == *name)
+                .map(|a| &mut a.bindings),
+            RebindTarget::AxisPositive(name) => self
+                .axes
+                .iter_mut()
+                .find(|a| a.name == *name)
+                .map(|a| &mut a.positive),
+            RebindTarget::AxisNegative(name) => self
+                .axes
+                .iter_mut()
+                .find(|a| a.name == *name)
+                .map(|a| &mut a.negative),
+        }
+    }
+}
+
+fn parse_list(text: &str) -> Result<Vec<Binding>, String> {
+    text.split(',')
+        .map(|b| b.trim())
+        .filter(|b| !b.is_empty())
+        .map(Binding::parse)
+        .collect()
+}
+
+/// Turns key and mouse events into action states. Edges are kept until end_frame, so a key that
+/// is pressed and released between two updates still shows up as just pressed
+pub struct ActionMap {
+    pub bindings: Bindings,
+    pub file: PathBuf,
+
+    /// set while waiting for the key or mouse button to add to the target
+    pub rebinding: Option<RebindTarget>,
+
+    keys_down: HashSet<KeyCode>,
+    buttons_down: HashSet<MouseButton>,
+    mods: KeyMods,
+
+    active: HashSet<String>,
+    /// in the order they were pressed, commands run in that order
+    pressed: Vec<String>,
+    released: HashSet<String>,
+}
+
+impl ActionMap {
+    /// Bindings from `file`, or the defaults if it does not exist or cannot be used
+    pub fn load(file: PathBuf) -> Self {
+        let bindings = if file.exists() {
+            Bindings::load(&file).unwrap_or_else(|e| {
+                log::warn!("{}, using the default bindings", e);
+                Bindings::defaults()
+            })
+        } else {
+            Bindings::defaults()
+        };
+
+        Self {
+            bindings,
+            file,
+            rebinding: None,
+            keys_down: HashSet::new(),
+            buttons_down: HashSet::new(),
+            mods: KeyMods::default(),
+            active: HashSet::new(),
+            pressed: vec![],
+            released: HashSet::new(),
+        }
+    }
+
+    /// held right now
+    pub fn is_active(&self, action: &str) -> bool {
+        self.active.contains(action)
+    }
+
+    /// became active since the last end_frame
+    pub fn just_pressed(&self, action: &str) -> bool {
+        self.pressed.iter().any(|a| a == action)
+    }
+
+    /// stopped being active since the last end_frame
+    pub fn just_released(&self, action: &str) -> bool {
+        self.released.contains(action)
+    }
+
+    /// every action that became active since the last end_frame
+    pub fn pressed_actions(&self) -> Vec<String> {
+        self.pressed.clone()
+    }
+
+    /// -1, 0 or 1, 0 when both sides are held
+    pub fn axis(&self, axis: &str) -> f32 {
+        let Some(axis) = self.bindings.axes.iter().find(|a| a.name == axis) else {
+            return 0.0;
+        };
+
+        let side = |bindings: &[Binding]| {
+            if bindings.iter().any(|b| self.held(b)) {
+                1.0
+            } else {
+                0.0
+            }
+        };
+
+        return side(&axis.positive) - side(&axis.negative);
+    }
+
+    /// The first binding of an action for hints on screen
+    pub fn label(&self, action: &str) -> String {
+        self.bindings
+            .actions
+            .iter()
+            .find(|a| a.name == action)
+            .and_then(|a| a.bindings.first())
+            .map_or("unbound".to_owned(), |b| b.to_string())
+    }
+
+    /// Whether pressing `key` with `mods` triggers `action`, for the few keys that have to work
+    /// while a text field takes the keyboard
+    pub fn is_bound_to(&self, action: &str, key: KeyCode, mods: KeyMods) -> bool {
+        self.bindings
+            .actions
+            .iter()
+            .filter(|a| a.name == action)
+            .flat_map(|a| a.bindings.iter())
+            .any(|b| {
+                b.input == Input::Key(key)
+                    && (!b.mods.shift || mods.shift)
+                    && b.mods.ctrl == mods.ctrl
+                    && b.mods.alt == mods.alt
+                    && b.mods.logo == mods.logo
+            })
+    }
+
+    /// forget this frame's edges, called at the end of every update
+    pub fn end_frame(&mut self) {
+        self.pressed.clear();
+        self.released.clear();
+    }
+
+    /// a key went down, repeats are not passed in
+    pub fn key_down(&mut self, key: KeyCode, mods: KeyMods) {
+        self.keys_down.insert(key);
+        self.update_mods(key, mods);
+
+        if self.rebinding.is_some() && !Binding::is_modifier_key(key) {
+            // escape cancels instead of binding itself
+            if key != KeyCode::Escape {
+                self.finish_rebind(Binding {
+                    input: Input::Key(key),
+                    mods: self.mods,
+                });
+            }
+            self.rebinding = None;
+            self.active = self.currently_active();
+            return;
+        }
+
+        self.refresh();
+    }
+
+    /// Forget every held key and button without reporting them as released, so input that comes
+    /// in afterwards starts from nothing held
+    pub fn release_all(&mut self) {
+        self.keys_down.clear();
+        self.buttons_down.clear();
+        self.mods = KeyMods::default();
+        self.active.clear();
+    }
+
+    pub fn key_up(&mut self, key: KeyCode, mods: KeyMods) {
+        self.keys_down.remove(&key);
+        self.update_mods(key, mods);
+        self.refresh();
+    }
+
+    pub fn mouse_down(&mut self, button: MouseButton) {
+        self.buttons_down.insert(button);
+
+        if self.rebinding.is_some() {
+            self.finish_rebind(Binding {
+                input: Input::Mouse(button),
+                mods: self.mods,
+            });
+            self.rebinding = None;
+            self.active = self.currently_active();
+            return;
+        }
+
+        self.refresh();
+    }
+
+    pub fn mouse_up(&mut self, button: MouseButton) {
+        self.buttons_down.remove(&button);
+        self.refresh();
+    }
+
+    fn finish_rebind(&mut self, binding: Binding) {
+        let Some(target) = self.rebinding.clone() else {
+            return;
+        };
+
+        if let Some(list) = self.bindings.list_mut(&target) {
+            if !list.contains(&binding) {
+                list.push(binding);
+            }
+            log::info!("bound {} to {:?}", binding, target);
+        }
+    }
+
+    /// Some platforms report the modifiers from before the event, for the modifier keys
+    /// themselves the held keys are more reliable
+    fn update_mods(&mut self, key: KeyCode, mods: KeyMods) {
+        let held = |left, right| self.keys_down.contains(&left) || self.keys_down.contains(&right);
+
+        self.mods = match key {
+            KeyCode::LeftShift | KeyCode::RightShift => KeyMods {
+                shift: held(KeyCode::LeftShift, KeyCode::RightShift),
+                ..mods
+            },
+            KeyCode::LeftControl | KeyCode::RightControl => KeyMods {
+                ctrl: held(KeyCode::LeftControl, KeyCode::RightControl),
+                ..mods
+            },
+            KeyCode::LeftAlt | KeyCode::RightAlt => KeyMods {
+                alt: held(KeyCode::LeftAlt, KeyCode::RightAlt),
+                ..mods
+            },
+            KeyCode::LeftSuper | KeyCode::RightSuper => KeyMods {
+                logo: held(KeyCode::LeftSuper, KeyCode::RightSuper),
+                ..mods
+            },
+            _ => mods,
+        };
+    }
+
+    /// Ctrl, Alt and Super have to match exactly so F6 and Ctrl+F6 can do different things.
+    /// Shift only has to be held if the binding asks for it
+    fn held(&self, binding: &Binding) -> bool {
+        let down = match binding.input {
+            Input::Key(key) => self.keys_down.contains(&key),
+            Input::Mouse(button) => self.buttons_down.contains(&button),
+        };
+
+        let mods = binding.mods;
+        down && (!mods.shift || self.mods.shift)
+            && mods.ctrl == self.mods.ctrl
+            && mods.alt == self.mods.alt
+            && mods.logo == self.mods.logo
+    }
+
+    fn currently_active(&self) -> HashSet<String> {
+        self.bindings
+            .actions
+            .iter()
+            .filter(|a| a.bindings.iter().any(|b| self.held(b)))
+            .map(|a| a.name.clone())
+            .collect()
+    }
+
+    /// record the edges between the last known and the current state
+    fn refresh(&mut self) {
+        let active = self.currently_active();
+
+        for action in self.bindings.actions.iter() {
+            let (was, is) = (
+                self.active.contains(&action.name),
+                active.contains(&action.name),
+            );
+            if is && !was {
+                self.pressed.push(action.name.clone());
+            }
+            if was && !is {
+                self.released.insert(action.name.clone());
+            }
+        }
+
+        self.active = active;
+    }
+}
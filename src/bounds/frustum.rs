@@ -0,0 +1,69 @@
+use glam::*;
+
+use super::{Aabb, BoundingSphere, BoundingVolume};
+
+/// The six planes enclosing everything a camera can see. The plane normals point inwards and are
+/// normalized, so a positive distance means "inside"
+pub struct Frustum {
+    planes: [Vec4; 6],
+}
+
+impl Frustum {
+    /// Extract the planes from a combined projection * view matrix (Gribb/Hartmann).
+    /// Expects the depth range 0..1 of `Mat4::perspective_rh` which every camera projection uses.
+    /// Reversed depth only swaps near and far, an infinite far plane never culls anything
+    pub fn from_matrix(view_projection: Mat4) -> Self {
+        let row = |i| view_projection.row(i);
+
+        let mut planes = [
+            row(3) + row(0), // left
+            row(3) - row(0), // right
+            row(3) + row(1), // bottom
+            row(3) - row(1), // top
+            row(2),          // near
+            row(3) - row(2), // far
+        ];
+
+        for plane in planes.iter_mut() {
+            let length = plane.truncate().length();
+
+            // the far plane of an infinite projection has no normal, everything is in front of it
+            if length < f32::EPSILON {
+                *plane = Vec4::W;
+                continue;
+            }
+
+            *plane /= length;
+        }
+
+        return Self { planes };
+    }
+
+    fn distance(plane: Vec4, point: Vec3) -> f32 {
+        plane.truncate().dot(point) + plane.w
+    }
+
+    /// false only if the sphere is fully outside of the frustum
+    pub fn intersects_sphere(&self, sphere: &BoundingSphere) -> bool {
+        self.planes
+            .iter()
+            .all(|plane| Self::distance(*plane, sphere.center) >= -sphere.radius)
+    }
+
+    /// false only if the box is fully outside of the frustum.
+    /// Boxes near the frustum corners may be reported as visible even though they are not
+    pub fn intersects_aabb(&self, aabb: &Aabb) -> bool {
+        self.planes.iter().all(|plane| {
+            // the corner furthest along the plane normal
+            let positive = Vec3::select(plane.truncate().cmpge(Vec3::ZERO), aabb.max, aabb.min);
+            Self::distance(*plane, positive) >= 0.0
+        })
+    }
+
+    pub fn intersects(&self, volume: &BoundingVolume) -> bool {
+        match volume {
+            BoundingVolume::Aabb(aabb) => self.intersects_aabb(aabb),
+            BoundingVolume::Sphere(sphere) => self.intersects_sphere(sphere),
+        }
+    }
+}
@@ -0,0 +1,100 @@
+mod frustum;
+mod ray;
+pub use frustum::Frustum;
+pub use ray::Ray;
+
+use glam::*;
+
+/// Axis aligned bounding box
+#[derive(Debug, Clone, Copy)]
+pub struct Aabb {
+    pub min: Vec3,
+    pub max: Vec3,
+}
+
+impl Aabb {
+    /// smallest box around all points, an empty list results in a box of size 0 at the origin
+    pub fn from_points(points: impl Iterator<Item = Vec3>) -> Self {
+        let mut min = Vec3::splat(f32::INFINITY);
+        let mut max = Vec3::splat(f32::NEG_INFINITY);
+
+        for point in points {
+            min = min.min(point);
+            max = max.max(point);
+        }
+
+        if min.x > max.x {
+            return Self {
+                min: Vec3::ZERO,
+                max: Vec3::ZERO,
+            };
+        }
+
+        return Self { min, max };
+    }
+
+    pub fn center(&self) -> Vec3 {
+        (self.min + self.max) * 0.5
+    }
+
+    pub fn half_extents(&self) -> Vec3 {
+        (self.max - self.min) * 0.5
+    }
+
+    /// Box around the transformed box, which is usually a bit larger than the transformed box
+    /// itself (Arvo's method)
+    pub fn transformed(&self, transform: Mat4) -> Self {
+        let center = transform.transform_point3(self.center());
+
+        let abs = Mat3::from_cols(
+            transform.x_axis.truncate().abs(),
+            transform.y_axis.truncate().abs(),
+            transform.z_axis.truncate().abs(),
+        );
+        let half_extents = abs * self.half_extents();
+
+        return Self {
+            min: center - half_extents,
+            max: center + half_extents,
+        };
+    }
+}
+
+#[derive(Debug, Clone, Copy)]
+pub struct BoundingSphere {
+    pub center: Vec3,
+    pub radius: f32,
+}
+
+impl BoundingSphere {
+    /// non uniform scaling grows the sphere by the largest axis scale
+    pub fn transformed(&self, transform: Mat4) -> Self {
+        let scale = transform
+            .x_axis
+            .truncate()
+            .length()
+            .max(transform.y_axis.truncate().length())
+            .max(transform.z_axis.truncate().length());
+
+        return Self {
+            center: transform.transform_point3(self.center),
+            radius: self.radius * scale,
+        };
+    }
+}
+
+/// Volume enclosing all vertices of an object, used to skip work for objects that are not visible
+#[derive(Debug, Clone, Copy)]
+pub enum BoundingVolume {
+    Aabb(Aabb),
+    Sphere(BoundingSphere),
+}
+
+impl BoundingVolume {
+    pub fn transformed(&self, transform: Mat4) -> Self {
+        match self {
+            BoundingVolume::Aabb(aabb) => BoundingVolume::Aabb(aabb.transformed(transform)),
+            BoundingVolume::Sphere(sphere) => BoundingVolume::Sphere(sphere.transformed(transform)),
+        }
+    }
+}
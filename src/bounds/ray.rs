@@ -0,0 +1,96 @@
+use glam::*;
+
+use super::{Aabb, BoundingSphere, BoundingVolume};
+
+/// Half line starting at `origin`. Distances returned by the intersection tests are multiples of
+/// `direction`, which only are world units if the direction has a length of 1
+#[derive(Debug, Clone, Copy)]
+pub struct Ray {
+    pub origin: Vec3,
+    pub direction: Vec3,
+}
+
+impl Ray {
+    pub fn at(&self, distance: f32) -> Vec3 {
+        self.origin + self.direction * distance
+    }
+
+    /// The same ray in another space. The direction is not normalized again, so distances along
+    /// the transformed ray match distances along this one
+    pub fn transformed(&self, transform: Mat4) -> Self {
+        Self {
+            origin: transform.transform_point3(self.origin),
+            direction: transform.transform_vector3(self.direction),
+        }
+    }
+
+    /// distance to where the ray enters the box, 0 if it starts inside (slab test)
+    pub fn intersect_aabb(&self, aabb: &Aabb) -> Option<f32> {
+        let inverse = self.direction.recip();
+        let t1 = (aabb.min - self.origin) * inverse;
+        let t2 = (aabb.max - self.origin) * inverse;
+
+        let near = t1.min(t2).max_element().max(0.0);
+        let far = t1.max(t2).min_element();
+
+        return (near <= far).then_some(near);
+    }
+
+    /// distance to where the ray enters the sphere, 0 if it starts inside
+    pub fn intersect_sphere(&self, sphere: &BoundingSphere) -> Option<f32> {
+        let to_center = sphere.center - self.origin;
+        let a = self.direction.length_squared();
+        let b = to_center.dot(self.direction);
+        let c = to_center.length_squared() - sphere.radius * sphere.radius;
+
+        let discriminant = b * b - a * c;
+        if discriminant < 0.0 {
+            return None;
+        }
+
+        let root = discriminant.sqrt();
+        let far = (b + root) / a;
+        if far < 0.0 {
+            return None;
+        }
+
+        return Some(((b - root) / a).max(0.0));
+    }
+
+    pub fn intersect_bounds(&self, bounds: &BoundingVolume) -> Option<f32> {
+        match bounds {
+            BoundingVolume::Aabb(aabb) => self.intersect_aabb(aabb),
+            BoundingVolume::Sphere(sphere) => self.intersect_sphere(sphere),
+        }
+    }
+
+    /// Distance to the triangle, hits from both sides count (Möller-Trumbore)
+    pub fn intersect_triangle(&self, [a, b, c]: [Vec3; 3]) -> Option<f32> {
+        let edge1 = b - a;
+        let edge2 = c - a;
+
+        let p = self.direction.cross(edge2);
+        let determinant = edge1.dot(p);
+        if determinant.abs() < 1e-8 {
+            // parallel to the triangle
+            return None;
+        }
+
+        let inverse = 1.0 / determinant;
+        let to_origin = self.origin - a;
+
+        let u = to_origin.dot(p) * inverse;
+        if !(0.0..=1.0).contains(&u) {
+            return None;
+        }
+
+        let q = to_origin.cross(edge1);
+        let v = self.direction.dot(q) * inverse;
+        if v < 0.0 || u + v > 1.0 {
+            return None;
+        }
+
+        let distance = edge2.dot(q) * inverse;
+        return (distance >= 0.0).then_some(distance);
+    }
+}
@@ -0,0 +1,266 @@
+// Camera fly-throughs: record the camera into keyframes, edit them, save them to a text file and
+// play them back along a spline. Playback only depends on the time it is advanced by, with the
+// fixed delta of an image sequence the same path renders the same frames every time
+mod spline;
+
+use std::path::{Path, PathBuf};
+
+use glam::*;
+
+use crate::stage::Camera;
+
+/// where R, F6 and F7 keep the path
+pub const DEFAULT_PATH_FILE: &str = "./camera_path.txt";
+
+/// seconds between two keyframes while recording
+const RECORD_INTERVAL: f32 = 0.25;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Keyframe {
+    /// seconds since the start of the path
+    pub time: f32,
+    pub position: Vec3,
+    pub orientation: Quat,
+}
+
+/// keyframes sorted by time
+#[derive(Debug, Clone, Default)]
+pub struct CameraPath {
+    pub keyframes: Vec<Keyframe>,
+}
+
+impl CameraPath {
+    /// Read a path written by save. One keyframe per line: time, position xyz and orientation
+    /// xyzw separated by whitespace. Empty lines and lines starting with # are skipped
+    pub fn load(path: &Path) -> Result<Self, String> {
+        let source = std::fs::read_to_string(path)
+            .map_err(|e| format!("could not read {}: {}", path.display(), e))?;
+
+        let mut keyframes = vec![];
+        for (number, line) in source.lines().enumerate() {
+            let line = line.trim();
+            if line.is_empty() || line.starts_with('#') {
+                continue;
+            }
+
+            let values: Vec<f32> = line
+                .split_whitespace()
+                .map(|v| v.parse::<f32>())
+                .collect::<Result<_, _>>()
+                .map_err(|e| format!("{} line {}: {}", path.display(), number + 1, e))?;
+
+            let [time, px, py, pz, qx, qy, qz, qw] = values[..] else {
+                return Err(format!(
+                    "{} line {}: expected 8 numbers, found {}",
+                    path.display(),
+                    number + 1,
+                    values.len()
+                ));
+            };
+
+            keyframes.push(Keyframe {
+                time,
+                position: vec3(px, py, pz),
+                orientation: Quat::from_xyzw(qx, qy, qz, qw).normalize(),
+            });
+        }
+
+        let mut camera_path = Self { keyframes };
+        camera_path.sort();
+
+        return Ok(camera_path);
+    }
+
+    pub fn save(&self, path: &Path) -> Result<(), String> {
+        let mut text = String::from("# time  position x y z  orientation x y z w\n");
+        for k in self.keyframes.iter() {
+            text += &format!(
+                "{} {} {} {} {} {} {} {}\n",
+                k.time,
+                k.position.x,
+                k.position.y,
+                k.position.z,
+                k.orientation.x,
+                k.orientation.y,
+                k.orientation.z,
+                k.orientation.w
+            );
+        }
+
+        return std::fs::write(path, text)
+            .map_err(|e| format!("could not save {}: {}", path.display(), e));
+    }
+
+    /// restore the time order after keyframe times were edited
+    pub fn sort(&mut self) {
+        self.keyframes.sort_by(|a, b| a.time.total_cmp(&b.time));
+    }
+
+    /// add the current camera as a keyframe `time` seconds into the path
+    pub fn insert(&mut self, time: f32, cam: &Camera) {
+        self.keyframes.push(Keyframe {
+            time,
+            position: cam.camera_pos,
+            orientation: cam.orientation,
+        });
+        self.sort();
+    }
+
+    /// time of the last keyframe
+    pub fn duration(&self) -> f32 {
+        self.keyframes.last().map_or(0.0, |k| k.time)
+    }
+
+    /// Position and orientation at `time`, clamped to the ends of the path. None without
+    /// keyframes
+    pub fn sample(&self, time: f32) -> Option<(Vec3, Quat)> {
+        let keys = &self.keyframes;
+        let last = keys.len().checked_sub(1)?;
+
+        // first keyframe at or after time, the segment runs from the one before it
+        let next = keys.partition_point(|k| k.time < time);
+        if next == 0 {
+            return Some((keys[0].position, keys[0].orientation));
+        }
+        if next > last {
+            return Some((keys[last].position, keys[last].orientation));
+        }
+
+        // the ends are repeated so the first and last segment have neighbours
+        let index = |i: isize| i.clamp(0, last as isize) as usize;
+        let i1 = next as isize - 1;
+        let [k0, k1, k2, k3] = [i1 - 1, i1, i1 + 1, i1 + 2].map(|i| keys[index(i)]);
+
+        let span = k2.time - k1.time;
+        let t = if span > 0.0 {
+            (time - k1.time) / span
+        } else {
+            1.0
+        };
+
+        let position = spline::catmull_rom(k0.position, k1.position, k2.position, k3.position, t);
+
+        let q1 = k1.orientation;
+        let q0 = spline::same_hemisphere(q1, k0.orientation);
+        let q2 = spline::same_hemisphere(q1, k2.orientation);
+        let q3 = spline::same_hemisphere(q2, k3.orientation);
+        let orientation = spline::squad(
+            q1,
+            q2,
+            spline::squad_control(q0, q1, q2),
+            spline::squad_control(q1, q2, q3),
+            t,
+        );
+
+        return Some((position, orientation));
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum PathState {
+    Idle,
+    /// seconds since the recording started and the time of the next keyframe
+    Recording {
+        elapsed: f32,
+        next_keyframe: f32,
+    },
+    /// position on the path in path seconds
+    Playing {
+        time: f32,
+    },
+}
+
+/// Records the camera into a CameraPath and plays it back
+pub struct PathPlayer {
+    pub path: CameraPath,
+    pub state: PathState,
+
+    /// path seconds per real second while playing
+    pub speed: f32,
+    pub file: PathBuf,
+
+    /// whether the last update moved the camera
+    was_playing: bool,
+}
+
+impl PathPlayer {
+    pub fn new() -> Self {
+        Self {
+            path: CameraPath::default(),
+            state: PathState::Idle,
+            speed: 1.0,
+            file: PathBuf::from(DEFAULT_PATH_FILE),
+            was_playing: false,
+        }
+    }
+
+    /// replace the path with a new recording of the camera
+    pub fn start_recording(&mut self) {
+        self.path.keyframes.clear();
+        self.state = PathState::Recording {
+            elapsed: 0.0,
+            next_keyframe: 0.0,
+        };
+    }
+
+    /// play from the start, false if there is nothing to play
+    pub fn play(&mut self) -> bool {
+        if self.path.keyframes.is_empty() {
+            return false;
+        }
+
+        self.state = PathState::Playing { time: 0.0 };
+        return true;
+    }
+
+    pub fn stop(&mut self) {
+        self.state = PathState::Idle;
+    }
+
+    pub fn is_playing(&self) -> bool {
+        matches!(self.state, PathState::Playing { .. })
+    }
+
+    /// real seconds a playback at the current speed takes
+    pub fn playback_duration(&self) -> f32 {
+        self.path.duration() / self.speed.max(0.01)
+    }
+
+    /// Record or move the camera, `delta` in seconds. Returns true when the camera is given back,
+    /// because playback reached the end of the path or was stopped since the last update
+    pub fn update(&mut self, cam: &mut Camera, delta: f32) -> bool {
+        let released = self.was_playing && !self.is_playing();
+        self.was_playing = self.is_playing();
+
+        match &mut self.state {
+            PathState::Idle => {}
+
+            PathState::Recording {
+                elapsed,
+                next_keyframe,
+            } => {
+                if *elapsed >= *next_keyframe {
+                    self.path.insert(*elapsed, cam);
+                    *next_keyframe += RECORD_INTERVAL;
+                }
+                *elapsed += delta;
+            }
+
+            PathState::Playing { time } => {
+                if let Some((position, orientation)) = self.path.sample(*time) {
+                    cam.camera_pos = position;
+                    cam.orientation = orientation;
+                }
+
+                if *time >= self.path.duration() {
+                    self.state = PathState::Idle;
+                    self.was_playing = false;
+                    return true;
+                }
+                *time = (*time + delta * self.speed).min(self.path.duration());
+            }
+        }
+
+        return released;
+    }
+}
@@ -0,0 +1,79 @@
+// Interpolation through keyframes: Catmull-Rom for positions and squad (spherical quadrangle)
+// for orientations. Both pass exactly through every keyframe and keep the first derivative
+// continuous, so a path has no visible kinks at the keyframes
+use glam::*;
+
+/// Point between `p1` and `p2` at `t` in 0..1, `p0` and `p3` are the keyframes around them
+pub fn catmull_rom(p0: Vec3, p1: Vec3, p2: Vec3, p3: Vec3, t: f32) -> Vec3 {
+    let t2 = t * t;
+    let t3 = t2 * t;
+
+    return 0.5
+        * (2.0 * p1
+            + (p2 - p0) * t
+            + (2.0 * p0 - 5.0 * p1 + 4.0 * p2 - p3) * t2
+            + (3.0 * p1 - p0 - 3.0 * p2 + p3) * t3);
+}
+
+/// Inner control point of `q1` for squad, from its neighbours. All three must be in the same
+/// hemisphere, see same_hemisphere
+pub fn squad_control(q0: Quat, q1: Quat, q2: Quat) -> Quat {
+    let inverse = q1.inverse();
+    let tangent = (log(inverse * q2) + log(inverse * q0)) * -0.25;
+
+    return (q1 * exp(tangent)).normalize();
+}
+
+/// Rotation between `q1` and `q2` at `t` in 0..1 with the control points of squad_control
+pub fn squad(q1: Quat, q2: Quat, s1: Quat, s2: Quat, t: f32) -> Quat {
+    let outer = slerp_no_flip(q1, q2, t);
+    let inner = slerp_no_flip(s1, s2, t);
+
+    return slerp_no_flip(outer, inner, 2.0 * t * (1.0 - t)).normalize();
+}
+
+/// `q` or its negation, whichever is closer to `reference`. Both describe the same rotation,
+/// interpolating between quaternions of opposite sign would take the long way around
+pub fn same_hemisphere(reference: Quat, q: Quat) -> Quat {
+    if reference.dot(q) < 0.0 { -q } else { q }
+}
+
+/// slerp that keeps the sign of its inputs, squad relies on the control points not being
+/// flipped independently of the keyframes
+fn slerp_no_flip(a: Quat, b: Quat, t: f32) -> Quat {
+    let cos_angle = a.dot(b).clamp(-1.0, 1.0);
+
+    // nearly the same rotation, a plain lerp avoids dividing by a tiny sine
+    if cos_angle.abs() > 0.9995 {
+        return Quat::from_vec4(Vec4::from(a).lerp(Vec4::from(b), t)).normalize();
+    }
+
+    let angle = cos_angle.acos();
+    let sin_angle = angle.sin();
+    let wa = ((1.0 - t) * angle).sin() / sin_angle;
+    let wb = (t * angle).sin() / sin_angle;
+
+    return Quat::from_vec4(Vec4::from(a) * wa + Vec4::from(b) * wb);
+}
+
+/// logarithm of a unit quaternion, the rotation axis scaled by half the angle
+fn log(q: Quat) -> Vec3 {
+    let v = q.xyz();
+    let sin_half = v.length();
+    if sin_half < 1e-6 {
+        return v;
+    }
+
+    return v / sin_half * sin_half.atan2(q.w);
+}
+
+/// inverse of log
+fn exp(v: Vec3) -> Quat {
+    let half_angle = v.length();
+    if half_angle < 1e-6 {
+        return Quat::from_xyzw(v.x, v.y, v.z, 1.0).normalize();
+    }
+
+    let axis = v / half_angle * half_angle.sin();
+    return Quat::from_xyzw(axis.x, axis.y, axis.z, half_angle.cos());
+}
@@ -0,0 +1,160 @@
+use std::{fs::File, io::BufWriter, path::PathBuf};
+
+use miniquad::{RenderPass, RenderingBackend, TextureFormat, TextureId, date};
+
+use crate::render_targets::RenderTarget;
+
+type BackendArg = Box<dyn RenderingBackend>;
+
+/// where screenshots and image sequences are written to
+const CAPTURE_FOLDER: &str = "./captures";
+
+/// a run of frames written to numbered files
+struct Sequence {
+    folder: PathBuf,
+    frame_rate: f64,
+    next_frame: usize,
+    frame_count: usize,
+}
+
+/// Saves rendered frames as PNG files. A frame that is captured is rendered into an offscreen
+/// target instead of the window, read back and then copied to the window
+pub struct FrameCapture {
+    target: RenderTarget,
+
+    screenshot_requested: bool,
+    sequence: Option<Sequence>,
+}
+
+impl FrameCapture {
+    pub fn new() -> Self {
+        Self {
+            target: RenderTarget::new(TextureFormat::RGBA8, false),
+            screenshot_requested: false,
+            sequence: None,
+        }
+    }
+
+    /// save the next frame as ./captures/screenshot_<time>.png
+    pub fn request_screenshot(&mut self) {
+        self.screenshot_requested = true;
+    }
+
+    /// Save the next `frames` frames as ./captures/sequence_<time>/frame_00000.png and so on.
+    /// While recording the simulation steps exactly 1 / `frame_rate` seconds per frame, no matter
+    /// how long rendering and saving takes
+    pub fn start_sequence(&mut self, frames: usize, frame_rate: f64) {
+        let folder = PathBuf::from(CAPTURE_FOLDER).join(format!("sequence_{}", timestamp()));
+
+        log::info!(
+            "recording {} frames at {} fps to {}",
+            frames,
+            frame_rate,
+            folder.display()
+        );
+
+        self.sequence = Some(Sequence {
+            folder,
+            frame_rate,
+            next_frame: 0,
+            frame_count: frames,
+        });
+    }
+
+    /// the simulated time step while a sequence is recorded
+    pub fn fixed_delta(&self) -> Option<f64> {
+        self.sequence.as_ref().map(|s| 1.0 / s.frame_rate)
+    }
+
+    /// The pass the finished frame has to be rendered into, None if this frame is not captured
+    /// and goes straight to the window
+    pub fn output(&mut self, ctx: &mut BackendArg, width: u32, height: u32) -> Option<RenderPass> {
+        if !self.screenshot_requested && self.sequence.is_none() {
+            return None;
+        }
+
+        self.target.ensure_size(ctx, width, height);
+        return Some(self.target.pass());
+    }
+
+    /// Save the frame rendered into `output` and return its texture so it can still be shown
+    pub fn finish_frame(&mut self, ctx: &mut BackendArg) -> TextureId {
+        let texture = self.target.color_texture();
+        let (width, height) = self.target.size();
+        let pixels = read_pixels(ctx, texture, width, height);
+
+        if std::mem::take(&mut self.screenshot_requested) {
+            let path =
+                PathBuf::from(CAPTURE_FOLDER).join(format!("screenshot_{}.png", timestamp()));
+
+            match save_png(&path, width, height, &pixels) {
+                Ok(()) => log::info!("saved screenshot {}", path.display()),
+                Err(e) => log::error!("{}", e),
+            }
+        }
+
+        if let Some(sequence) = self.sequence.as_mut() {
+            let path = sequence
+                .folder
+                .join(format!("frame_{:05}.png", sequence.next_frame));
+
+            if let Err(e) = save_png(&path, width, height, &pixels) {
+                log::error!("{}, recording stopped", e);
+                self.sequence = None;
+            } else {
+                sequence.next_frame += 1;
+
+                if sequence.next_frame == sequence.frame_count {
+                    log::info!("recorded {} frames", sequence.frame_count);
+                    self.sequence = None;
+                }
+            }
+        }
+
+        return texture;
+    }
+
+    /// deallocate any resources that are allocated on opengl
+    pub fn drop_gl_resources(&mut self, ctx: &mut BackendArg) {
+        self.target.drop_gl_resources(ctx);
+    }
+}
+
+/// Read an RGBA8 texture back into memory, rows ordered top to bottom like image files expect
+pub fn read_pixels(ctx: &mut BackendArg, texture: TextureId, width: u32, height: u32) -> Vec<u8> {
+    let mut pixels = vec![0; (width * height * 4) as usize];
+    ctx.texture_read_pixels(texture, &mut pixels);
+
+    // opengl hands out the bottom row first
+    let row = (width * 4) as usize;
+    let mut flipped = Vec::with_capacity(pixels.len());
+    for line in pixels.chunks_exact(row).rev() {
+        flipped.extend_from_slice(line);
+    }
+
+    return flipped;
+}
+
+/// write tightly packed RGBA8 pixels as a png, creating missing folders on the way
+pub fn save_png(path: &PathBuf, width: u32, height: u32, rgba: &[u8]) -> Result<(), String> {
+    let error = |e: &dyn std::fmt::Display| format!("could not save {}: {}", path.display(), e);
+
+    if let Some(folder) = path.parent() {
+        std::fs::create_dir_all(folder).map_err(|e| error(&e))?;
+    }
+
+    let file = File::create(path).map_err(|e| error(&e))?;
+    let mut encoder = png::Encoder::new(BufWriter::new(file), width, height);
+    encoder.set_color(png::ColorType::Rgba);
+    encoder.set_depth(png::BitDepth::Eight);
+
+    let mut writer = encoder.write_header().map_err(|e| error(&e))?;
+    writer.write_image_data(rgba).map_err(|e| error(&e))?;
+
+    return Ok(());
+}
+
+/// milliseconds since the epoch, unique enough for file names
+fn timestamp() -> u64 {
+    (date::now() * 1000.0) as u64
+}
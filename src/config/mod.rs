@@ -0,0 +1,571 @@
+// Startup and runtime settings from the [window], [input] and [camera] tables of ./config.toml. A
+// missing file or setting is the default, a file that is not valid TOML or a value out of range is
+// reported and keeps what was there before. The file is watched, edits to the settings that can
+// change while running apply right away, and changes made in the app are written back into it
+// without touching comments, key order or the settings that did not change
+use std::{
+    fmt::Display,
+    ops::RangeInclusive,
+    path::{Path, PathBuf},
+};
+
+use miniquad::conf::AppleGfxApi;
+use serde::{Deserialize, Serialize};
+use toml_edit::{DocumentMut, Item, Table};
+
+use crate::{
+    file_watch::FolderWatch,
+    stage::{Camera, Projection, Settings},
+};
+
+pub const DEFAULT_CONFIG_FILE: &str = "./config.toml";
+
+/// seconds an in-app change has to stay unchanged before it is written, so dragging a slider
+/// does not write the file every frame
+const SAVE_DELAY: f64 = 0.5;
+
+/// what the file starts as when the app writes it for the first time
+const TEMPLATE: &str = r#"# Changes to [input] and [camera] apply while running, [window] after a restart.
+# Settings changed in the debug panel are written back to this file.
+
+[window]
+# in logical pixels, --window-size overrides them
+width = 1280
+height = 720
+fullscreen = false
+high_dpi = true
+# "opengl" or "metal", only used on macOS and iOS
+apple_gfx_api = "opengl"
+
+[input]
+mouse_sensitivity = 0.2
+
+[camera]
+# units per second
+speed = 5.2
+# vertical, in degrees
+fov = 45.0
+z_near = 0.1
+z_far = 100.0
+"#;
+
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Config {
+    pub window: WindowConfig,
+    pub input: InputConfig,
+    pub camera: CameraConfig,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct WindowConfig {
+    /// in logical pixels
+    pub width: i32,
+    pub height: i32,
+    pub fullscreen: bool,
+    pub high_dpi: bool,
+    /// only used on macOS and iOS
+    #[serde(with = "AppleGfxApiName")]
+    pub apple_gfx_api: AppleGfxApi,
+}
+
+/// apple_gfx_api as it is written in the file
+#[derive(Serialize, Deserialize)]
+#[serde(remote = "AppleGfxApi", rename_all = "lowercase")]
+enum AppleGfxApiName {
+    OpenGl,
+    Metal,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct InputConfig {
+    pub mouse_sensitivity: f32,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct CameraConfig {
+    pub speed: f32,
+    #[serde(rename = "fov")]
+    pub fov_y_deg: f32,
+    pub z_near: f32,
+    pub z_far: f32,
+}
+
+impl Default for WindowConfig {
+    fn default() -> Self {
+        Self {
+            width: 1280,
+            height: 720,
+            fullscreen: false,
+            high_dpi: true,
+            apple_gfx_api: AppleGfxApi::OpenGl,
+        }
+    }
+}
+
+impl Default for InputConfig {
+    fn default() -> Self {
+        Self {
+            mouse_sensitivity: 0.2,
+        }
+    }
+}
+
+impl Default for CameraConfig {
+    fn default() -> Self {
+        Self {
+            speed: 5.2,
+            fov_y_deg: 45.0,
+            z_near: 0.1,
+            z_far: 100.0,
+        }
+    }
+}
+
+impl Config {
+    /// The defaults with `path` applied on top, and what was wrong with the file. A missing file
+    /// is not an error
+    pub fn load(path: &Path) -> (Self, Vec<String>) {
+        match std::fs::read_to_string(path) {
+            Ok(source) => Self::parse(&source, &path.display().to_string(), &Self::default()),
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => (Self::default(), vec![]),
+            Err(e) => (
+                Self::default(),
+                vec![format!("could not read {}: {}", path.display(), e)],
+            ),
+        }
+    }
+
+    /// The settings of `source`, the defaults for the ones it does not have. `base` is kept when
+    /// `source` can not be read and so is every value of it that is out of range
+    pub fn parse(source: &str, origin: &str, base: &Config) -> (Self, Vec<String>) {
+        let mut config: Config = match toml::from_str(source) {
+            Ok(config) => config,
+            Err(e) => {
+                let line = e.span().map_or(1, |span| line_of(source, span.start));
+                let error = format!("{} line {}: {}", origin, line, e.message().trim_end());
+                return (base.clone(), vec![error]);
+            }
+        };
+
+        let mut errors = unknown_settings(source, origin);
+        let mut check = |name: &str, value: &mut f32, base: f32, range: RangeInclusive<f32>| {
+            keep_in_range(&mut errors, origin, name, value, base, range)
+        };
+        let (input, camera) = (&mut config.input, &mut config.camera);
+        check(
+            "mouse_sensitivity",
+            &mut input.mouse_sensitivity,
+            base.input.mouse_sensitivity,
+            0.01..=1.0,
+        );
+        check("speed", &mut camera.speed, base.camera.speed, 0.1..=1000.0);
+        check(
+            "fov",
+            &mut camera.fov_y_deg,
+            base.camera.fov_y_deg,
+            1.0..=179.0,
+        );
+        check(
+            "z_near",
+            &mut camera.z_near,
+            base.camera.z_near,
+            0.001..=1000.0,
+        );
+        check(
+            "z_far",
+            &mut camera.z_far,
+            base.camera.z_far,
+            0.01..=1_000_000.0,
+        );
+
+        let window = &mut config.window;
+        keep_in_range(
+            &mut errors,
+            origin,
+            "width",
+            &mut window.width,
+            base.window.width,
+            1..=16384,
+        );
+        keep_in_range(
+            &mut errors,
+            origin,
+            "height",
+            &mut window.height,
+            base.window.height,
+            1..=16384,
+        );
+
+        if config.camera.z_near >= config.camera.z_far {
+            errors.push(format!(
+                "{}: z_near {} has to be less than z_far {}",
+                origin, config.camera.z_near, config.camera.z_far
+            ));
+            config.camera.z_near = base.camera.z_near;
+            config.camera.z_far = base.camera.z_far;
+        }
+
+        return (config, errors);
+    }
+
+    /// Set the settings that can change while running. The projection keeps its mode, only the
+    /// fov and clip planes it has are replaced
+    pub fn apply(&self, settings: &mut Settings, cam: &mut Camera) {
+        settings.mouse_sensitivity = self.input.mouse_sensitivity;
+        cam.camera_speed = self.camera.speed;
+
+        match &mut cam.projection {
+            Projection::Perspective {
+                fov_y_deg,
+                z_near,
+                z_far,
+            } => {
+                *fov_y_deg = self.camera.fov_y_deg;
+                *z_near = self.camera.z_near;
+                *z_far = self.camera.z_far;
+            }
+            Projection::Orthographic { z_near, z_far, .. }
+            | Projection::OrthographicBounds { z_near, z_far, .. } => {
+                *z_near = self.camera.z_near;
+                *z_far = self.camera.z_far;
+            }
+            Projection::InfinitePerspective { fov_y_deg, z_near } => {
+                *fov_y_deg = self.camera.fov_y_deg;
+                *z_near = self.camera.z_near;
+            }
+        }
+    }
+
+    /// self with the runtime settings as they are now, the opposite of apply
+    pub fn with_runtime(&self, settings: &Settings, cam: &Camera) -> Config {
+        let mut config = self.clone();
+        config.input.mouse_sensitivity = settings.mouse_sensitivity;
+        config.camera.speed = cam.camera_speed;
+
+        match cam.projection {
+            Projection::Perspective {
+                fov_y_deg,
+                z_near,
+                z_far,
+            } => {
+                config.camera.fov_y_deg = fov_y_deg;
+                config.camera.z_near = z_near;
+                config.camera.z_far = z_far;
+            }
+            Projection::Orthographic { z_near, z_far, .. }
+            | Projection::OrthographicBounds { z_near, z_far, .. } => {
+                config.camera.z_near = z_near;
+                config.camera.z_far = z_far;
+            }
+            Projection::InfinitePerspective { fov_y_deg, z_near } => {
+                config.camera.fov_y_deg = fov_y_deg;
+                config.camera.z_near = z_near;
+            }
+        }
+        return config;
+    }
+
+    /// whether going from self to `other` changes something only a restart applies
+    pub fn needs_restart(&self, other: &Config) -> bool {
+        self.window != other.window
+    }
+}
+
+/// The config together with its file, reloaded when the file changes and saved when the app
+/// changes it
+pub struct ConfigFile {
+    pub config: Config,
+    pub file: PathBuf,
+    /// what was wrong the last time the file was read, for the debug panel
+    pub errors: Vec<String>,
+
+    /// None when not live
+    watch: Option<FolderWatch>,
+    /// whether in-app changes are written, not when headless
+    write_back: bool,
+    /// the file content last read or written, so saving does not count as an edit
+    last_text: Option<String>,
+    /// real time at which an in-app change is written
+    save_at: Option<f64>,
+}
+
+impl ConfigFile {
+    /// Load `file`. When `live` it is watched and changes made in the app are written back,
+    /// headless runs do neither
+    pub fn load(file: PathBuf, live: bool) -> Self {
+        let (config, errors) = Config::load(&file);
+        for e in errors.iter() {
+            log::warn!("{}", e);
+        }
+
+        let watch = live
+            .then(|| FolderWatch::containing(&file))
+            .and_then(|watch| {
+                watch
+                    .inspect_err(|e| log::warn!("not watching {}: {}", file.display(), e))
+                    .ok()
+            });
+
+        Self {
+            last_text: std::fs::read_to_string(&file).ok(),
+            config,
+            file,
+            errors,
+            watch,
+            write_back: live,
+            save_at: None,
+        }
+    }
+
+    /// Read the file again if it was changed since the last call. Returns the config from before
+    /// when it did, the errors are in self.errors
+    pub fn poll(&mut self) -> Option<Config> {
+        let name = self.file.file_name();
+        if !self
+            .watch
+            .as_ref()?
+            .changed(|path| path.file_name() == name)
+        {
+            return None;
+        }
+
+        let text = std::fs::read_to_string(&self.file).ok()?;
+        if self.last_text.as_ref() == Some(&text) {
+            return None;
+        }
+
+        let (config, errors) = Config::parse(&text, &self.file.display().to_string(), &self.config);
+        self.last_text = Some(text);
+        self.errors = errors;
+        // whatever was not written yet is replaced by the edit
+        self.save_at = None;
+        return Some(std::mem::replace(&mut self.config, config));
+    }
+
+    /// Take over `config` as changed in the app, it is written once it stops changing
+    pub fn changed_in_app(&mut self, config: Config, now: f64) {
+        if config != self.config {
+            self.config = config;
+            if self.write_back {
+                self.save_at = Some(now + SAVE_DELAY);
+            }
+        }
+    }
+
+    /// write the config if an in-app change is due
+    pub fn save_if_due(&mut self, now: f64) -> Result<(), String> {
+        match self.save_at {
+            Some(at) if now >= at => self.save(),
+            _ => Ok(()),
+        }
+    }
+
+    /// Write the settings that differ from the file into it, a missing file starts as TEMPLATE
+    pub fn save(&mut self) -> Result<(), String> {
+        self.save_at = None;
+        let error = |e: String| format!("could not save {}: {}", self.file.display(), e);
+
+        let text = self.last_text.as_deref().unwrap_or(TEMPLATE);
+        let (on_disk, _) = Config::parse(text, "", &Config::default());
+        let text = write_back(text, &on_disk, &self.config).map_err(error)?;
+
+        std::fs::write(&self.file, &text).map_err(|e| error(e.to_string()))?;
+        self.last_text = Some(text);
+        return Ok(());
+    }
+}
+
+/// `text` with every setting that differs between `old` and `new` set to the value of `new`.
+/// Comments, the order of the settings and the ones that did not change stay as they are
+fn write_back(text: &str, old: &Config, new: &Config) -> Result<String, String> {
+    let mut document: DocumentMut = text.parse().map_err(|e| format!("{}", e))?;
+    let old = to_document(old)?;
+    let new = to_document(new)?;
+
+    for (section, settings) in new.iter() {
+        let Some(settings) = settings.as_table() else {
+            continue;
+        };
+
+        for (key, value) in settings.iter() {
+            let Some(value) = value.as_value() else {
+                continue;
+            };
+            let before = old.get(section).and_then(|s| s.get(key));
+            if before.map(|v| v.to_string()) == Some(value.to_string()) {
+                continue;
+            }
+
+            if !document.contains_key(section) {
+                document.insert(section, Item::Table(Table::new()));
+            }
+            let Some(table) = document[section].as_table_mut() else {
+                return Err(format!("{} is not a table", section));
+            };
+
+            match table.get_mut(key).and_then(Item::as_value_mut) {
+                // the comment behind the value stays
+                Some(existing) => {
+                    let decor = existing.decor().clone();
+                    *existing = value.clone();
+                    *existing.decor_mut() = decor;
+                }
+                None => {
+                    table.insert(key, Item::Value(value.clone()));
+                }
+            }
+        }
+    }
+
+    return Ok(document.to_string());
+}
+
+fn to_document(config: &Config) -> Result<DocumentMut, String> {
+    toml::to_string(config)
+        .map_err(|e| e.to_string())?
+        .parse()
+        .map_err(|e: toml_edit::TomlError| e.to_string())
+}
+
+/// the settings and tables of `source` that Config does not have
+fn unknown_settings(source: &str, origin: &str) -> Vec<String> {
+    let (Ok(found), Ok(known)) = (
+        toml::from_str::<toml::Table>(source),
+        toml::Table::try_from(Config::default()),
+    ) else {
+        return vec![];
+    };
+
+    let mut errors = vec![];
+    for (section, settings) in found.iter() {
+        let Some(known) = known.get(section).and_then(|s| s.as_table()) else {
+            errors.push(format!("{}: unknown section [{}]", origin, section));
+            continue;
+        };
+        let Some(settings) = settings.as_table() else {
+            continue;
+        };
+        for key in settings.keys().filter(|key| !known.contains_key(*key)) {
+            errors.push(format!(
+                "{}: unknown setting {} in [{}]",
+                origin, key, section
+            ));
+        }
+    }
+    return errors;
+}
+
+/// Report `value` and set it back to `base` when it is not in `range`
+fn keep_in_range<T: PartialOrd + Display + Copy>(
+    errors: &mut Vec<String>,
+    origin: &str,
+    name: &str,
+    value: &mut T,
+    base: T,
+    range: RangeInclusive<T>,
+) {
+    if !range.contains(value) {
+        errors.push(format!(
+            "{}: {} has to be between {} and {}, found {}",
+            origin,
+            name,
+            range.start(),
+            range.end(),
+            value
+        ));
+        *value = base;
+    }
+}
+
+/// 1 based line number of the byte at `offset`
+fn line_of(source: &str, offset: usize) -> usize {
+    source[..offset.min(source.len())].matches('\n').count() + 1
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn template_is_the_default() {
+        assert_eq!(
+            Config::parse(TEMPLATE, "template", &Config::default()),
+            (Config::default(), vec![])
+        );
+    }
+
+    #[test]
+    fn removed_setting_is_the_default_again() {
+        let (edited, errors) = Config::parse(
+            "[camera]\nfov = 60.0\nspeed = 2.0\n",
+            "test",
+            &Config::default(),
+        );
+        assert!(errors.is_empty());
+        assert_eq!(edited.camera.fov_y_deg, 60.0);
+
+        let (reverted, errors) = Config::parse("[camera]\nspeed = 2.0\n", "test", &edited);
+        assert!(errors.is_empty());
+        assert_eq!(reverted.camera.fov_y_deg, CameraConfig::default().fov_y_deg);
+        assert_eq!(reverted.camera.speed, 2.0);
+    }
+
+    #[test]
+    fn invalid_values_keep_the_previous_ones() {
+        let mut base = Config::default();
+        base.camera.fov_y_deg = 70.0;
+
+        let (config, errors) = Config::parse("[camera]\nfov = 500.0\nspeed = 3.0\n", "test", &base);
+        assert_eq!(errors.len(), 1);
+        assert_eq!(config.camera.fov_y_deg, 70.0);
+        assert_eq!(config.camera.speed, 3.0);
+
+        let (config, errors) = Config::parse("[camera\nfov = 50.0\n", "test", &base);
+        assert_eq!(errors.len(), 1);
+        assert!(errors[0].starts_with("test line 1:"), "{}", errors[0]);
+        assert_eq!(config, base);
+
+        let (_, errors) = Config::parse("[camera]\nfox = 50.0\n[sound]\n", "test", &base);
+        assert_eq!(
+            errors,
+            vec![
+                "test: unknown setting fox in [camera]",
+                "test: unknown section [sound]"
+            ]
+        );
+    }
+
+    #[test]
+    fn write_back_keeps_comments_and_order() {
+        let text = "# mine\n[camera]\nz_far = 50.0 # far enough\n\n# wider\nfov = 60.0\n\n[window]\nwidth = 800\n";
+        let (old, errors) = Config::parse(text, "test", &Config::default());
+        assert!(errors.is_empty());
+
+        let mut new = old.clone();
+        new.camera.fov_y_deg = 75.0;
+        new.input.mouse_sensitivity = 0.5;
+
+        assert_eq!(
+            write_back(text, &old, &new).unwrap(),
+            "# mine\n[camera]\nz_far = 50.0 # far enough\n\n# wider\nfov = 75.0\n\n[window]\nwidth = 800\n\n[input]\nmouse_sensitivity = 0.5\n"
+        );
+    }
+
+    #[test]
+    fn nothing_is_written_when_not_live() {
+        let file = std::env::temp_dir().join("rustic_test_not_live.toml");
+        let _ = std::fs::remove_file(&file);
+
+        let mut config_file = ConfigFile::load(file.clone(), false);
+        let mut changed = config_file.config.clone();
+        changed.camera.speed = 9.0;
+        config_file.changed_in_app(changed, 0.0);
+        assert_eq!(config_file.save_if_due(10.0), Ok(()));
+
+        assert!(!file.exists());
+    }
+}
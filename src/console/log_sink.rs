@@ -0,0 +1,54 @@
+use std::sync::Mutex;
+
+use log::{Level, LevelFilter, Log, Metadata, Record};
+
+/// Records waiting for the console. Logging can happen on any thread, the console reads them on
+/// the main one
+static RECORDS: Mutex<Vec<(Level, String)>> = Mutex::new(vec![]);
+
+/// Prints every record like println did before and keeps it for the console
+struct ConsoleLogger;
+
+static LOGGER: ConsoleLogger = ConsoleLogger;
+
+impl Log for ConsoleLogger {
+    fn enabled(&self, metadata: &Metadata) -> bool {
+        // dependencies only get through with problems
+        metadata.level() <= Level::Warn || metadata.target().starts_with(env!("CARGO_PKG_NAME"))
+    }
+
+    fn log(&self, record: &Record) {
+        if !self.enabled(record.metadata()) {
+            return;
+        }
+
+        let message = match record.level() {
+            Level::Info => record.args().to_string(),
+            level => format!("{}: {}", level.as_str().to_lowercase(), record.args()),
+        };
+        println!("{}", message);
+
+        if let Ok(mut records) = RECORDS.lock() {
+            // nobody reads them without a console, e.g. headless
+            if records.len() < 1000 {
+                records.push((record.level(), message));
+            }
+        }
+    }
+
+    fn flush(&self) {}
+}
+
+/// Send the `log` macros to stdout and the console, once at startup
+pub fn init_logging() {
+    if log::set_logger(&LOGGER).is_ok() {
+        log::set_max_level(LevelFilter::Info);
+    }
+}
+
+pub fn take_records() -> Vec<(Level, String)> {
+    RECORDS
+        .lock()
+        .map(|mut records| std::mem::take(&mut *records))
+        .unwrap_or_default()
+}
@@ -0,0 +1,331 @@
+// Drop-down developer console. Code registers commands and typed variables by name, a typed line
+// runs the command or reads and sets the variable, e.g. `wireframe 1` or `cam.fov 70`. Names are
+// completed with tab, earlier lines come back with up and down, and everything logged through
+// the `log` facade shows up in the output
+mod log_sink;
+pub use log_sink::init_logging;
+
+use std::collections::VecDeque;
+
+use glam::Vec3;
+
+/// output lines kept, older ones are dropped
+const MAX_LINES: usize = 500;
+
+/// a console command, `args` are the words after its name
+pub struct Command<T> {
+    pub name: &'static str,
+    /// the arguments as shown by help, e.g. `cube|sphere x y z`
+    pub usage: &'static str,
+    pub help: &'static str,
+    /// returns what to print, an empty string prints nothing
+    pub run: fn(&mut T, &[&str]) -> Result<String, String>,
+}
+
+/// A value stored somewhere in T, read and written through the two functions. The type of what
+/// `get` returns decides how typed arguments are parsed
+pub struct Variable<T> {
+    pub name: &'static str,
+    pub help: &'static str,
+    pub get: fn(&T) -> Value,
+    pub set: fn(&mut T, Value) -> Result<(), String>,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum Value {
+    Bool(bool),
+    Float(f32),
+    Vec3(Vec3),
+}
+
+impl Value {
+    /// Parse `args` as a value of the same type as self
+    pub fn parse_like(&self, args: &[&str]) -> Result<Value, String> {
+        // f32 parsing also accepts nan and inf, which no variable can do anything sensible with
+        let number = |arg: &str| match arg.parse::<f32>() {
+            Ok(x) if x.is_finite() => Ok(x),
+            Ok(_) => Err(format!("expected a finite number, found {}", arg)),
+            Err(_) => Err(format!("expected a number, found {}", arg)),
+        };
+
+        match (self, args) {
+            (Value::Bool(_), [arg]) => match arg.to_ascii_lowercase().as_str() {
+                "1" | "true" | "on" => Ok(Value::Bool(true)),
+                "0" | "false" | "off" => Ok(Value::Bool(false)),
+                _ => Err(format!("expected 1 or 0, found {}", arg)),
+            },
+            (Value::Float(_), [arg]) => number(arg).map(Value::Float),
+            (Value::Vec3(_), [x, y, z]) => {
+                Ok(Value::Vec3(Vec3::new(number(x)?, number(y)?, number(z)?)))
+            }
+            (Value::Vec3(_), _) => Err("expected three numbers, x y z".to_owned()),
+            _ => Err("expected one value".to_owned()),
+        }
+    }
+
+    pub fn as_bool(&self) -> bool {
+        matches!(self, Value::Bool(true))
+    }
+
+    pub fn as_f32(&self) -> f32 {
+        match *self {
+            Value::Float(f) => f,
+            _ => 0.0,
+        }
+    }
+
+    pub fn as_vec3(&self) -> Vec3 {
+        match *self {
+            Value::Vec3(v) => v,
+            _ => Vec3::ZERO,
+        }
+    }
+}
+
+impl std::fmt::Display for Value {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Value::Bool(b) => write!(f, "{}", *b as u8),
+            Value::Float(x) => write!(f, "{}", x),
+            Value::Vec3(v) => write!(f, "{} {} {}", v.x, v.y, v.z),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum LineKind {
+    /// a line that was entered, echoed
+    Input,
+    Output,
+    Error,
+    Log(log::Level),
+}
+
+/// Something that owns a console, so commands can get at both
+pub trait HasConsole: Sized {
+    fn console(self: &mut Self) -> &mut Console<Self>;
+}
+
+/// The registered names, the line being typed, history and output. `T` is what commands and
+/// variables work on
+pub struct Console<T> {
+    commands: Vec<Command<T>>,
+    variables: Vec<Variable<T>>,
+
+    /// the line being typed
+    pub input: String,
+    history: Vec<String>,
+    /// position while going through the history with up and down, None while typing a new line
+    history_index: Option<usize>,
+    output: VecDeque<(LineKind, String)>,
+    /// entered lines that were not run yet, the owner runs them from its update
+    submitted: Vec<String>,
+}
+
+impl<T: HasConsole> Console<T> {
+    pub fn new() -> Self {
+        Self {
+            commands: vec![],
+            variables: vec![],
+            input: String::new(),
+            history: vec![],
+            history_index: None,
+            output: VecDeque::new(),
+            submitted: vec![],
+        }
+    }
+
+    pub fn command(&mut self, command: Command<T>) {
+        self.commands.push(command);
+    }
+
+    pub fn variable(&mut self, variable: Variable<T>) {
+        self.variables.push(variable);
+    }
+
+    pub fn print(&mut self, kind: LineKind, text: &str) {
+        for line in text.lines() {
+            if self.output.len() == MAX_LINES {
+                self.output.pop_front();
+            }
+            self.output.push_back((kind, line.to_owned()));
+        }
+    }
+
+    pub fn lines(&self) -> impl Iterator<Item = &(LineKind, String)> {
+        self.output.iter()
+    }
+
+    /// Move the logged records into the output, called once per frame
+    pub fn collect_log(&mut self) {
+        for (level, message) in log_sink::take_records() {
+            self.print(LineKind::Log(level), &message);
+        }
+    }
+
+    /// enter the typed line, it runs with the next run_submitted
+    pub fn submit(&mut self) {
+        let line = std::mem::take(&mut self.input);
+        let line = line.trim();
+        self.history_index = None;
+        if line.is_empty() {
+            return;
+        }
+
+        if self.history.last().map(|l| l.as_str()) != Some(line) {
+            self.history.push(line.to_owned());
+        }
+        self.submitted.push(line.to_owned());
+    }
+
+    /// Step through the history, `older` is up
+    pub fn browse_history(&mut self, older: bool) {
+        if self.history.is_empty() {
+            return;
+        }
+
+        self.history_index = match (self.history_index, older) {
+            (None, true) => Some(self.history.len() - 1),
+            (None, false) => None,
+            (Some(i), true) => Some(i.saturating_sub(1)),
+            (Some(i), false) if i + 1 < self.history.len() => Some(i + 1),
+            (Some(_), false) => None,
+        };
+        self.input = self
+            .history_index
+            .map_or(String::new(), |i| self.history[i].clone());
+    }
+
+    /// Complete the name being typed. A single match is filled in, several are filled in as far as
+    /// they agree and listed
+    pub fn complete(&mut self) {
+        let prefix = self.input.trim_start();
+        if prefix.contains(' ') {
+            return;
+        }
+
+        let mut matches: Vec<&str> = BUILTINS
+            .iter()
+            .map(|(name, _)| *name)
+            .chain(self.commands.iter().map(|c| c.name))
+            .chain(self.variables.iter().map(|v| v.name))
+            .filter(|name| name.starts_with(prefix))
+            .collect();
+        matches.sort();
+
+        match matches.as_slice() {
+            [] => {}
+            [name] => self.input = format!("{} ", name),
+            [first, rest @ ..] => {
+                let common = rest.iter().fold(first.len(), |length, name| {
+                    first
+                        .bytes()
+                        .zip(name.bytes())
+                        .take(length)
+                        .take_while(|(a, b)| a == b)
+                        .count()
+                });
+                let listing = matches.join("  ");
+                self.input = first[..common].to_owned();
+                self.print(LineKind::Output, &listing);
+            }
+        }
+    }
+
+    /// Run the lines entered since the last call on `target`
+    pub fn run_submitted(target: &mut T) {
+        let lines = std::mem::take(&mut target.console().submitted);
+        for line in lines {
+            Self::execute(target, &line);
+        }
+    }
+
+    /// Run one line as if it was typed, printing the result
+    pub fn execute(target: &mut T, line: &str) {
+        let console = target.console();
+        console.print(LineKind::Input, &format!("> {}", line));
+
+        let words: Vec<&str> = line.split_whitespace().collect();
+        let Some((&name, args)) = words.split_first() else {
+            return;
+        };
+
+        let result = if let Some(builtin) = console.builtin(name, args) {
+            builtin
+        } else if let Some(command) = console.commands.iter().find(|c| c.name == name) {
+            let run = command.run;
+            run(target, args)
+        } else if let Some(variable) = console.variables.iter().find(|v| v.name == name) {
+            let (get, set) = (variable.get, variable.set);
+            let current = get(target);
+            if args.is_empty() {
+                Ok(format!("{} = {}", name, current))
+            } else {
+                current
+                    .parse_like(args)
+                    .and_then(|value| set(target, value))
+                    .map(|()| format!("{} = {}", name, get(target)))
+                    .map_err(|e| format!("{}: {}", name, e))
+            }
+        } else {
+            Err(format!("unknown command {}, help lists them", name))
+        };
+
+        // what the command logged comes before its result
+        let console = target.console();
+        console.collect_log();
+        match result {
+            Ok(text) => console.print(LineKind::Output, &text),
+            Err(e) => console.print(LineKind::Error, &e),
+        }
+    }
+
+    /// the commands every console has, None if `name` is not one of them
+    fn builtin(&mut self, name: &str, args: &[&str]) -> Option<Result<String, String>> {
+        let result = match name {
+            "help" => self.help(args.first().copied()),
+            "clear" => {
+                self.output.clear();
+                Ok(String::new())
+            }
+            _ => return None,
+        };
+        return Some(result);
+    }
+
+    fn help(&self, name: Option<&str>) -> Result<String, String> {
+        let mut lines: Vec<(String, &str)> = vec![];
+        for (builtin, help) in BUILTINS {
+            lines.push((builtin.to_owned(), help));
+        }
+        for command in self.commands.iter() {
+            lines.push((format!("{} {}", command.name, command.usage), command.help));
+        }
+        for variable in self.variables.iter() {
+            lines.push((format!("{} [value]", variable.name), variable.help));
+        }
+
+        if let Some(name) = name {
+            lines.retain(|(usage, _)| usage.split(' ').next() == Some(name));
+            if lines.is_empty() {
+                return Err(format!("unknown command {}", name));
+            }
+        }
+
+        let width = lines
+            .iter()
+            .map(|(usage, _)| usage.len())
+            .max()
+            .unwrap_or(0);
+        return Ok(lines
+            .iter()
+            .map(|(usage, help)| format!("{:width$}  {}", usage, help))
+            .collect::<Vec<_>>()
+            .join("\n"));
+    }
+}
+
+const BUILTINS: [(&str, &str); 2] = [
+    ("help", "list the commands and variables, or describe one"),
+    ("clear", "empty the output"),
+];
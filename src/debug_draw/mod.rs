@@ -0,0 +1,212 @@
+// Immediate mode debug drawing, callable from anywhere on the main thread.
+// Every shape is queued as colored lines and drawn on top of the finished frame. A lifetime of
+// 0 draws the shape for the current frame only, anything larger keeps it for that many seconds
+mod renderer;
+pub use renderer::DebugDrawRenderer;
+
+use std::cell::RefCell;
+
+use glam::*;
+
+use crate::bounds::Aabb;
+
+pub const RED: Vec4 = vec4(1.0, 0.0, 0.0, 1.0);
+pub const GREEN: Vec4 = vec4(0.0, 1.0, 0.0, 1.0);
+pub const BLUE: Vec4 = vec4(0.0, 0.0, 1.0, 1.0);
+pub const YELLOW: Vec4 = vec4(1.0, 1.0, 0.0, 1.0);
+
+#[repr(C)]
+#[derive(Clone, Copy)]
+pub struct DebugVertex {
+    pub pos: Vec3,
+    // not a Vec4, its 16 byte alignment would add padding the vertex layout does not know about
+    pub color: [f32; 4],
+}
+
+/// the lines of one call, they share a lifetime
+struct Shape {
+    vertices: Vec<DebugVertex>,
+    remaining: f32,
+}
+
+thread_local! {
+    static SHAPES: RefCell<Vec<Shape>> = const { RefCell::new(vec![]) };
+}
+
+fn push(lines: &[(Vec3, Vec3)], color: Vec4, lifetime: f32) {
+    let color = color.to_array();
+    let vertices = lines
+        .iter()
+        .flat_map(|(a, b)| {
+            [
+                DebugVertex { pos: *a, color },
+                DebugVertex { pos: *b, color },
+            ]
+        })
+        .collect();
+
+    SHAPES.with_borrow_mut(|shapes| {
+        shapes.push(Shape {
+            vertices,
+            remaining: lifetime,
+        })
+    });
+}
+
+/// Age all shapes by `delta` seconds, dropping those that were drawn and ran out.
+/// Called once per frame before the update code queues new shapes
+pub fn tick(delta: f32) {
+    SHAPES.with_borrow_mut(|shapes| {
+        shapes.retain_mut(|shape| {
+            shape.remaining -= delta;
+            shape.remaining > 0.0
+        })
+    });
+}
+
+/// all lines that are currently alive, as line list vertices
+fn collect_vertices() -> Vec<DebugVertex> {
+    SHAPES.with_borrow(|shapes| {
+        shapes
+            .iter()
+            .flat_map(|s| s.vertices.iter().copied())
+            .collect()
+    })
+}
+
+pub fn line(from: Vec3, to: Vec3, color: Vec4, lifetime: f32) {
+    push(&[(from, to)], color, lifetime);
+}
+
+/// line with a small head at `to`
+pub fn arrow(from: Vec3, to: Vec3, color: Vec4, lifetime: f32) {
+    let direction = to - from;
+    let length = direction.length();
+    if length == 0.0 {
+        return;
+    }
+
+    let forward = direction / length;
+    let side = forward.any_orthonormal_vector();
+    let up = forward.cross(side);
+    let head = length.min(1.0) * 0.2;
+    let base = to - forward * head;
+
+    push(
+        &[
+            (from, to),
+            (to, base + side * head * 0.5),
+            (to, base - side * head * 0.5),
+            (to, base + up * head * 0.5),
+            (to, base - up * head * 0.5),
+        ],
+        color,
+        lifetime,
+    );
+}
+
+/// the 8 corners connected into a box
+fn push_box(corners: [Vec3; 8], color: Vec4, lifetime: f32) {
+    // corner index bits: x is bit 0, y bit 1, z bit 2
+    #[rustfmt::skip]
+    const EDGES: [(usize, usize); 12] = [
+        (0, 1), (2, 3), (4, 5), (6, 7), // along x
+        (0, 2), (1, 3), (4, 6), (5, 7), // along y
+        (0, 4), (1, 5), (2, 6), (3, 7), // along z
+    ];
+
+    let lines: Vec<(Vec3, Vec3)> = EDGES
+        .iter()
+        .map(|(a, b)| (corners[*a], corners[*b]))
+        .collect();
+
+    push(&lines, color, lifetime);
+}
+
+fn corner(index: usize, min: Vec3, max: Vec3) -> Vec3 {
+    vec3(
+        if index & 1 == 0 { min.x } else { max.x },
+        if index & 2 == 0 { min.y } else { max.y },
+        if index & 4 == 0 { min.z } else { max.z },
+    )
+}
+
+pub fn wire_aabb(aabb: &Aabb, color: Vec4, lifetime: f32) {
+    push_box(
+        std::array::from_fn(|i| corner(i, aabb.min, aabb.max)),
+        color,
+        lifetime,
+    );
+}
+
+/// the unit cube from -0.5 to 0.5 put through `transform`, for rotated boxes
+pub fn wire_box(transform: Mat4, color: Vec4, lifetime: f32) {
+    push_box(
+        std::array::from_fn(|i| {
+            transform.transform_point3(corner(i, Vec3::splat(-0.5), Vec3::splat(0.5)))
+        }),
+        color,
+        lifetime,
+    );
+}
+
+/// three circles around the x, y and z axis
+pub fn wire_sphere(center: Vec3, radius: f32, color: Vec4, lifetime: f32) {
+    const SEGMENTS: usize = 24;
+
+    let mut lines = Vec::with_capacity(SEGMENTS * 3);
+    for (u, v) in [(Vec3::X, Vec3::Y), (Vec3::Y, Vec3::Z), (Vec3::Z, Vec3::X)] {
+        let point = |i: usize| {
+            let angle = i as f32 / SEGMENTS as f32 * std::f32::consts::TAU;
+            center + (u * angle.cos() + v * angle.sin()) * radius
+        };
+
+        for i in 0..SEGMENTS {
+            lines.push((point(i), point(i + 1)));
+        }
+    }
+
+    push(&lines, color, lifetime);
+}
+
+/// x, y and z axis of `transform` in red, green and blue
+pub fn axes(transform: Mat4, size: f32, lifetime: f32) {
+    let origin = transform.transform_point3(Vec3::ZERO);
+
+    for (axis, color) in [(Vec3::X, RED), (Vec3::Y, GREEN), (Vec3::Z, BLUE)] {
+        let end = transform.transform_point3(axis * size);
+        arrow(origin, end, color, lifetime);
+    }
+}
+
+/// outline of everything visible through a projection * view matrix, expects the 0..1 depth range
+/// of `Mat4::perspective_rh`. Sides going to infinity are cut off `INFINITE_FRUSTUM_LENGTH` behind
+/// the other end
+pub fn frustum(view_projection: Mat4, color: Vec4, lifetime: f32) {
+    const INFINITE_FRUSTUM_LENGTH: f32 = 100.0;
+
+    let inverse = view_projection.inverse();
+    let unproject = |i: usize, depth_min: f32, depth_max: f32| {
+        inverse.project_point3(corner(
+            i,
+            vec3(-1.0, -1.0, depth_min),
+            vec3(1.0, 1.0, depth_max),
+        ))
+    };
+
+    push_box(
+        std::array::from_fn(|i| {
+            let point = unproject(i, 0.0, 1.0);
+            if point.is_finite() {
+                return point;
+            }
+
+            // the same corner at the other depth and halfway to it give the direction
+            let other_end = unproject(i ^ 4, 0.0, 1.0);
+            let halfway = unproject(i, 0.5, 0.5);
+            other_end + (halfway - other_end).normalize() * INFINITE_FRUSTUM_LENGTH
+        }),
+        color,
+        lifetime,
+    );
+}
@@ -0,0 +1,179 @@
+use miniquad::{
+    Bindings, BufferId, BufferLayout, BufferSource, BufferType, BufferUsage, Comparison,
+    PassAction, Pipeline, PipelineParams, PrimitiveType, RenderPass, RenderingBackend, ShaderId,
+    UniformsSource, VertexAttribute, VertexFormat,
+};
+
+use super::{DebugVertex, collect_vertices};
+use crate::{profiler::profile_scope, shaders::ShaderFile};
+
+type BackendArg = Box<dyn RenderingBackend>;
+
+/// Uploads all queued debug lines into one streaming buffer each frame and draws them
+pub struct DebugDrawRenderer {
+    shader_file: ShaderFile,
+
+    /// in vertices, the buffers grow when more lines are queued
+    capacity: usize,
+
+    /// opengl vars
+    shader: Option<ShaderId>,
+    pipeline: Option<Pipeline>,
+    vertex_buffer: Option<BufferId>,
+    index_buffer: Option<BufferId>,
+}
+
+impl DebugDrawRenderer {
+    pub fn new() -> Self {
+        Self {
+            shader_file: ShaderFile::new("debug_lines".to_owned()),
+            capacity: 0,
+            shader: None,
+            pipeline: None,
+            vertex_buffer: None,
+            index_buffer: None,
+        }
+    }
+
+    fn ensure_pipeline(&mut self, ctx: &mut BackendArg) {
+        if !self.shader_file.reload_if_needed() && self.pipeline.is_some() {
+            return;
+        }
+
+        let compiled = ctx.new_shader(self.shader_file.get_shadersource(), shader::meta());
+        self.shader_file.report_compile(&compiled);
+
+        let Ok(shader) = compiled else {
+            return;
+        };
+
+        if let Some(val) = self.pipeline.take() {
+            ctx.delete_pipeline(val);
+        }
+        if let Some(val) = self.shader.take() {
+            ctx.delete_shader(val);
+        }
+
+        self.pipeline = Some(ctx.new_pipeline(
+            &[BufferLayout::default()],
+            &[
+                VertexAttribute::new("in_pos", VertexFormat::Float3),
+                VertexAttribute::new("in_color", VertexFormat::Float4),
+            ],
+            shader,
+            PipelineParams {
+                primitive_type: PrimitiveType::Lines,
+                // debug output is always visible, even behind geometry
+                depth_test: Comparison::Always,
+                ..Default::default()
+            },
+        ));
+        self.shader = Some(shader);
+    }
+
+    /// make sure the buffers can hold `vertex_count` vertices
+    fn ensure_capacity(&mut self, ctx: &mut BackendArg, vertex_count: usize) {
+        if self.vertex_buffer.is_some() && vertex_count <= self.capacity {
+            return;
+        }
+
+        self.drop_buffers(ctx);
+
+        self.capacity = vertex_count.next_power_of_two().max(1024);
+
+        self.vertex_buffer = Some(ctx.new_buffer(
+            BufferType::VertexBuffer,
+            BufferUsage::Stream,
+            BufferSource::empty::<DebugVertex>(self.capacity),
+        ));
+
+        // lines are never shared, so the indices never change
+        let indices: Vec<u32> = (0..self.capacity as u32).collect();
+        self.index_buffer = Some(ctx.new_buffer(
+            BufferType::IndexBuffer,
+            BufferUsage::Immutable,
+            BufferSource::slice(&indices),
+        ));
+    }
+
+    /// Draw every live debug shape into `output` (None being the window), on top of whatever is
+    /// there. Must be called outside of a render pass
+    pub fn draw(
+        &mut self,
+        ctx: &mut BackendArg,
+        view_projection: glam::Mat4,
+        output: Option<RenderPass>,
+    ) {
+        profile_scope!("debug draw");
+        let vertices = collect_vertices();
+        if vertices.is_empty() {
+            return;
+        }
+
+        self.ensure_pipeline(ctx);
+        let Some(pipeline) = self.pipeline else {
+            return;
+        };
+
+        self.ensure_capacity(ctx, vertices.len());
+        ctx.buffer_update(self.vertex_buffer.unwrap(), BufferSource::slice(&vertices));
+
+        ctx.begin_pass(output, PassAction::Nothing);
+        ctx.apply_pipeline(&pipeline);
+        ctx.apply_bindings(&Bindings {
+            vertex_buffers: vec![self.vertex_buffer.unwrap()],
+            index_buffer: self.index_buffer.unwrap(),
+            images: vec![],
+        });
+        ctx.apply_uniforms(UniformsSource::table(&shader::Uniforms { view_projection }));
+        ctx.draw(0, vertices.len() as i32, 1);
+        ctx.end_render_pass();
+    }
+
+    fn drop_buffers(&mut self, ctx: &mut BackendArg) {
+        if let Some(val) = self.vertex_buffer.take() {
+            ctx.delete_buffer(val);
+        }
+
+        if let Some(val) = self.index_buffer.take() {
+            ctx.delete_buffer(val);
+        }
+    }
+
+    /// deallocate any resources that are allocated on opengl
+    pub fn drop_gl_resources(&mut self, ctx: &mut BackendArg) {
+        self.drop_buffers(ctx);
+
+        if let Some(val) = self.pipeline.take() {
+            ctx.delete_pipeline(val);
+        }
+
+        if let Some(val) = self.shader.take() {
+            ctx.delete_shader(val);
+        }
+    }
+}
+
+impl Drop for DebugDrawRenderer {
+    fn drop(&mut self) {
+        if self.vertex_buffer.is_some() || self.pipeline.is_some() {
+            panic!("Debug draw renderer was not cleared properly");
+        }
+    }
+}
+
+mod shader {
+    use miniquad::*;
+    pub fn meta() -> ShaderMeta {
+        ShaderMeta {
+            images: vec![],
+            uniforms: UniformBlockLayout {
+                uniforms: vec![UniformDesc::new("view_projection", UniformType::Mat4)],
+            },
+        }
+    }
+    #[repr(C)]
+    pub struct Uniforms {
+        pub view_projection: glam::f32::Mat4,
+    }
+}
@@ -0,0 +1,74 @@
+use egui::{Color32, Key, Modifiers, RichText};
+
+use crate::{
+    console::{Console, LineKind},
+    stage::Stage,
+};
+
+/// The console as a panel over the top part of the window, output above the input line
+pub fn console_panel(ctx: &egui::Context, console: &mut Console<Stage>) {
+    let height = ctx.screen_rect().height() * 0.4;
+
+    egui::TopBottomPanel::top("console")
+        .exact_height(height)
+        .frame(
+            egui::Frame::default()
+                .fill(Color32::from_black_alpha(220))
+                .inner_margin(6.0),
+        )
+        .show(ctx, |ui| {
+            egui::ScrollArea::vertical()
+                .max_height(height - 36.0)
+                .auto_shrink([false, false])
+                .stick_to_bottom(true)
+                .show(ui, |ui| {
+                    for (kind, text) in console.lines() {
+                        let color = match kind {
+                            LineKind::Input => Color32::GRAY,
+                            LineKind::Output => Color32::WHITE,
+                            LineKind::Error | LineKind::Log(log::Level::Error) => Color32::RED,
+                            LineKind::Log(log::Level::Warn) => Color32::YELLOW,
+                            LineKind::Log(_) => Color32::LIGHT_BLUE,
+                        };
+                        ui.label(RichText::new(text).monospace().color(color));
+                    }
+                });
+
+            // taken before the text field sees them, tab would move the focus away
+            let (tab, up, down) = ui.input_mut(|input| {
+                (
+                    input.consume_key(Modifiers::NONE, Key::Tab),
+                    input.consume_key(Modifiers::NONE, Key::ArrowUp),
+                    input.consume_key(Modifiers::NONE, Key::ArrowDown),
+                )
+            });
+            if tab {
+                console.complete();
+            }
+            if up || down {
+                console.browse_history(up);
+            }
+
+            let mut output = egui::TextEdit::singleline(&mut console.input)
+                .font(egui::TextStyle::Monospace)
+                .desired_width(f32::INFINITY)
+                .hint_text("help lists the commands, tab completes")
+                .show(ui);
+
+            if output.response.lost_focus() && ui.input(|input| input.key_pressed(Key::Enter)) {
+                console.submit();
+            }
+            // typing goes here as long as the console is open
+            output.response.request_focus();
+
+            // a completed or recalled line continues at its end
+            if tab || up || down {
+                let end = egui::text::CCursor::new(console.input.chars().count());
+                output
+                    .state
+                    .cursor
+                    .set_char_range(Some(egui::text::CCursorRange::one(end)));
+                output.state.store(ui.ctx(), output.response.id);
+            }
+        });
+}
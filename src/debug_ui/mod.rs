@@ -0,0 +1,688 @@
+mod console;
+
+use std::collections::VecDeque;
+
+use egui_miniquad::EguiMq;
+use miniquad::{KeyCode, KeyMods, MouseButton, RenderingBackend};
+
+use crate::{
+    actions::{ActionMap, Binding, Bindings, RebindTarget},
+    camera_path::{PathPlayer, PathState},
+    config::ConfigFile,
+    console::Console,
+    debug_draw,
+    postprocess::PostProcessChain,
+    profiler::{self, profile_scope},
+    shaders,
+    stage::{Camera, Projection, Settings, Stage, StageMetadata, WorldState},
+    time::Time,
+};
+
+type BackendArg = Box<dyn RenderingBackend>;
+
+/// Everything the panel shows and edits, borrowed from the stage for one frame
+pub struct DebugUiState<'a> {
+    pub settings: &'a mut Settings,
+    pub world: &'a mut WorldState,
+    pub post: &'a mut PostProcessChain,
+    pub camera_path: &'a mut PathPlayer,
+    pub actions: &'a mut ActionMap,
+    pub time: &'a mut Time,
+    pub config: &'a mut ConfigFile,
+    pub console: &'a mut Console<Stage>,
+    pub meta: &'a StageMetadata,
+}
+
+/// Immediate mode overlay to inspect and change settings, the camera and shaders while running.
+/// Events only reach egui while the panel or the console is visible
+pub struct DebugUi {
+    pub visible: bool,
+    /// the drop-down console at the top of the window
+    pub console_open: bool,
+
+    /// created the first time the panel is shown, it needs a window
+    egui_mq: Option<EguiMq>,
+}
+
+impl DebugUi {
+    pub fn new() -> Self {
+        Self {
+            visible: false,
+            console_open: false,
+            egui_mq: None,
+        }
+    }
+
+    fn shown(&self) -> bool {
+        self.visible || self.console_open
+    }
+
+    /// the egui state while the panel or the console is visible
+    fn active(&mut self) -> Option<&mut EguiMq> {
+        if !self.shown() {
+            return None;
+        }
+
+        return self.egui_mq.as_mut();
+    }
+
+    /// the pointer is over the panel, clicks and drags should not move the camera
+    pub fn wants_pointer(&self) -> bool {
+        self.shown()
+            && self
+                .egui_mq
+                .as_ref()
+                .is_some_and(|mq| mq.egui_ctx().wants_pointer_input())
+    }
+
+    /// a text field has focus, key presses should not reach the game
+    pub fn wants_keyboard(&self) -> bool {
+        self.shown()
+            && self
+                .egui_mq
+                .as_ref()
+                .is_some_and(|mq| mq.egui_ctx().wants_keyboard_input())
+    }
+
+    /// Build and draw the panel on top of the frame.
+    /// Must be called outside of a render pass
+    pub fn draw(&mut self, ctx: &mut BackendArg, state: DebugUiState) {
+        if !self.shown() {
+            return;
+        }
+        profile_scope!("debug ui");
+
+        let visible = self.visible;
+        let console_open = self.console_open;
+        let egui_mq = self.egui_mq.get_or_insert_with(|| EguiMq::new(&mut **ctx));
+
+        egui_mq.run(&mut **ctx, |_, egui_ctx| {
+            if console_open {
+                console::console_panel(egui_ctx, state.console);
+            }
+            if !visible {
+                return;
+            }
+
+            egui::Window::new("Debug")
+                .default_pos((10.0, 60.0))
+                .show(egui_ctx, |ui| {
+                    frame_section(ui, state.meta);
+                    time_section(ui, state.time);
+                    profiler_section(ui);
+                    settings_section(ui, state.settings, state.world, state.config);
+                    camera_section(ui, state.world);
+                    camera_path_section(ui, state.camera_path, &mut state.world.cam);
+                    lighting_section(ui, state.world);
+                    post_section(ui, state.post);
+                    input_section(ui, state.actions);
+                    shader_section(ui);
+                });
+        });
+
+        egui_mq.draw(&mut **ctx);
+    }
+
+    pub fn mouse_motion_event(&mut self, x: f32, y: f32) {
+        if let Some(egui_mq) = self.active() {
+            egui_mq.mouse_motion_event(x, y);
+        }
+    }
+
+    pub fn mouse_wheel_event(&mut self, dx: f32, dy: f32) {
+        if let Some(egui_mq) = self.active() {
+            egui_mq.mouse_wheel_event(dx, dy);
+        }
+    }
+
+    pub fn mouse_button_down_event(&mut self, button: MouseButton, x: f32, y: f32) {
+        if let Some(egui_mq) = self.active() {
+            egui_mq.mouse_button_down_event(button, x, y);
+        }
+    }
+
+    pub fn mouse_button_up_event(&mut self, button: MouseButton, x: f32, y: f32) {
+        if let Some(egui_mq) = self.active() {
+            egui_mq.mouse_button_up_event(button, x, y);
+        }
+    }
+
+    pub fn char_event(&mut self, character: char) {
+        if let Some(egui_mq) = self.active() {
+            egui_mq.char_event(character);
+        }
+    }
+
+    pub fn key_down_event(&mut self, keycode: KeyCode, keymods: KeyMods) {
+        if let Some(egui_mq) = self.active() {
+            egui_mq.key_down_event(keycode, keymods);
+        }
+    }
+
+    pub fn key_up_event(&mut self, keycode: KeyCode, keymods: KeyMods) {
+        if let Some(egui_mq) = self.active() {
+            egui_mq.key_up_event(keycode, keymods);
+        }
+    }
+}
+
+fn frame_section(ui: &mut egui::Ui, meta: &StageMetadata) {
+    ui.collapsing("Frame", |ui| {
+        ui.label(format!(
+            "{:.0} fps, {:.2} ms",
+            meta.fps.fps, meta.fps.frame_time_ms
+        ));
+        ui.label(format!(
+            "objects drawn {}, culled {}",
+            meta.render_stats.drawn, meta.render_stats.culled
+        ));
+
+        let (logical_width, logical_height) = meta.logical_screen_size();
+        ui.label(format!(
+            "window {}x{} px, {}x{} logical at dpi scale {:.2}",
+            meta.screen_size.0, meta.screen_size.1, logical_width, logical_height, meta.dpi_scale
+        ));
+        ui.label("F11 toggles fullscreen");
+    });
+}
+
+fn time_section(ui: &mut egui::Ui, time: &mut Time) {
+    ui.collapsing("Time", |ui| {
+        ui.label(format!(
+            "frame {}, {:.2}s elapsed, {:.2}s real",
+            time.frame, time.elapsed, time.real_elapsed
+        ));
+
+        ui.horizontal(|ui| {
+            ui.checkbox(&mut time.paused, "paused");
+            if ui.button("step").clicked() {
+                time.step_one_frame();
+            }
+        });
+        ui.add(
+            egui::Slider::new(&mut time.time_scale, 0.0..=4.0)
+                .text("time scale")
+                .logarithmic(true),
+        );
+
+        let sim_clock = &mut time.simulation;
+        let mut rate = sim_clock.rate();
+        if ui
+            .add(egui::Slider::new(&mut rate, 10.0..=240.0).text("simulation rate hz"))
+            .changed()
+        {
+            sim_clock.set_rate(rate);
+        }
+        ui.add(egui::Slider::new(&mut sim_clock.max_steps, 1..=20).text("max steps per frame"));
+        ui.label(format!(
+            "{} steps last frame, {:.2}s dropped",
+            sim_clock.last_steps, sim_clock.dropped
+        ));
+    });
+}
+
+fn profiler_section(ui: &mut egui::Ui) {
+    ui.collapsing("Profiler", |ui| {
+        ui.horizontal(|ui| {
+            let mut paused = profiler::is_paused();
+            if ui.checkbox(&mut paused, "paused").changed() {
+                profiler::set_paused(paused);
+            }
+            if ui.button("save chrome trace").clicked() {
+                match profiler::save_chrome_trace(profiler::DEFAULT_TRACE_FILE) {
+                    Ok(()) => log::info!("saved {}", profiler::DEFAULT_TRACE_FILE),
+                    Err(e) => log::error!("{}", e),
+                }
+            }
+        });
+
+        profiler::with_frames(|frames| {
+            let Some(last) = frames.back() else {
+                return;
+            };
+            frame_time_graph(ui, frames);
+
+            // the first frame also timed the loading before the window opened
+            let averaged: Vec<&profiler::FrameProfile> = frames.iter().skip(1).collect();
+            let average_ms = |name: &str| {
+                let total: f64 = averaged
+                    .iter()
+                    .flat_map(|f| f.samples.iter())
+                    .filter(|s| s.name == name)
+                    .map(|s| s.duration.as_secs_f64())
+                    .sum();
+                total * 1000.0 / averaged.len().max(1) as f64
+            };
+
+            ui.label(format!(
+                "frame {}: {:.2} ms",
+                last.number,
+                last.duration.as_secs_f64() * 1000.0
+            ));
+            egui::Grid::new("profile").striped(true).show(ui, |ui| {
+                ui.label("scope");
+                ui.label("calls");
+                ui.label("last ms");
+                ui.label("average ms");
+                ui.end_row();
+
+                for (name, depth, duration, calls) in last.breakdown() {
+                    ui.label(format!("{}{}", "  ".repeat(depth as usize), name));
+                    ui.label(calls.to_string());
+                    ui.label(format!("{:.3}", duration.as_secs_f64() * 1000.0));
+                    ui.label(format!("{:.3}", average_ms(name)));
+                    ui.end_row();
+                }
+            });
+        });
+    });
+}
+
+/// One bar per kept frame, newest on the right, with lines at 60 and 30 fps
+fn frame_time_graph(ui: &mut egui::Ui, frames: &VecDeque<profiler::FrameProfile>) {
+    const MAX_MS: f32 = 50.0;
+    let size = egui::vec2(ui.available_width().max(240.0), 80.0);
+    let (rect, _) = ui.allocate_exact_size(size, egui::Sense::hover());
+    let painter = ui.painter_at(rect);
+    painter.rect_filled(rect, 0.0, egui::Color32::from_black_alpha(160));
+
+    let bar_width = rect.width() / profiler::HISTORY as f32;
+    let offset = profiler::HISTORY - frames.len();
+    for (i, frame) in frames.iter().enumerate() {
+        let ms = frame.duration.as_secs_f32() * 1000.0;
+        let color = if ms <= 1000.0 / 60.0 {
+            egui::Color32::GREEN
+        } else if ms <= 1000.0 / 30.0 {
+            egui::Color32::YELLOW
+        } else {
+            egui::Color32::RED
+        };
+        let x = rect.left() + (offset + i) as f32 * bar_width;
+        let top = rect.bottom() - rect.height() * (ms / MAX_MS).min(1.0);
+        painter.rect_filled(
+            egui::Rect::from_min_max(egui::pos2(x, top), egui::pos2(x + bar_width, rect.bottom())),
+            0.0,
+            color,
+        );
+    }
+
+    for ms in [1000.0 / 60.0, 1000.0 / 30.0] {
+        let y = rect.bottom() - rect.height() * ms / MAX_MS;
+        painter.hline(
+            rect.x_range(),
+            y,
+            egui::Stroke::new(1.0, egui::Color32::GRAY),
+        );
+    }
+}
+
+fn settings_section(
+    ui: &mut egui::Ui,
+    settings: &mut Settings,
+    world: &WorldState,
+    config: &mut ConfigFile,
+) {
+    egui::CollapsingHeader::new("Settings")
+        .default_open(true)
+        .show(ui, |ui| {
+            // mouse sensitivity, camera speed, fov and clip planes go back into the file
+            ui.horizontal(|ui| {
+                ui.label(format!("saved to {}", config.file.display()));
+                if ui.button("save now").clicked()
+                    && let Err(e) = config.save()
+                {
+                    log::error!("{}", e);
+                }
+            });
+            for e in config.errors.iter() {
+                ui.colored_label(egui::Color32::RED, e);
+            }
+
+            ui.add(
+                egui::Slider::new(&mut settings.mouse_sensitivity, 0.01..=1.0)
+                    .text("mouse sensitivity"),
+            );
+
+            ui.checkbox(&mut settings.render_wireframe, "wireframe [1]");
+            ui.checkbox(&mut settings.show_bounds, "object bounds and labels [2]");
+            ui.checkbox(
+                &mut settings.show_reference_lines,
+                "axes, grid and sun direction [3]",
+            );
+
+            if ui.button("capture camera frustum [4]").clicked() {
+                debug_draw::frustum(
+                    world.cam.projection_matrix() * world.cam.get_view_matrix(),
+                    debug_draw::YELLOW,
+                    10.0,
+                );
+            }
+        });
+}
+
+fn camera_section(ui: &mut egui::Ui, world: &mut WorldState) {
+    let cam = &mut world.cam;
+
+    ui.collapsing("Camera", |ui| {
+        ui.horizontal(|ui| {
+            ui.label("position");
+            ui.add(egui::DragValue::new(&mut cam.camera_pos.x).speed(0.1));
+            ui.add(egui::DragValue::new(&mut cam.camera_pos.y).speed(0.1));
+            ui.add(egui::DragValue::new(&mut cam.camera_pos.z).speed(0.1));
+        });
+        let (yaw, pitch, roll) = cam.yaw_pitch_roll();
+        ui.label(format!(
+            "yaw {:.1}, pitch {:.1}, roll {:.1}",
+            yaw, pitch, roll
+        ));
+        ui.label("V switches between free fly, 6dof flight, orbit and follow");
+        if ui.button("look at origin").clicked() {
+            cam.look_at(glam::Vec3::ZERO, glam::Vec3::Y);
+        }
+
+        ui.add(egui::Slider::new(&mut cam.camera_speed, 0.5..=50.0).text("speed"));
+
+        projection_controls(ui, cam);
+    });
+}
+
+fn projection_controls(ui: &mut egui::Ui, cam: &mut Camera) {
+    ui.horizontal(|ui| {
+        ui.label(format!("projection {} [P]", cam.projection.name()));
+        if ui.button("next").clicked() {
+            let focus_distance = cam.camera_pos.length().max(1.0);
+            let projection = cam.projection.next(focus_distance);
+            cam.set_projection(projection, 0.5);
+        }
+    });
+
+    let near_slider = |ui: &mut egui::Ui, z_near: &mut f32| {
+        ui.add(
+            egui::Slider::new(z_near, 0.01..=10.0)
+                .logarithmic(true)
+                .text("near"),
+        );
+    };
+    let far_slider = |ui: &mut egui::Ui, z_far: &mut f32| {
+        ui.add(
+            egui::Slider::new(z_far, 10.0..=1000.0)
+                .logarithmic(true)
+                .text("far"),
+        );
+    };
+
+    match &mut cam.projection {
+        Projection::Perspective {
+            fov_y_deg,
+            z_near,
+            z_far,
+        } => {
+            ui.add(egui::Slider::new(fov_y_deg, 10.0..=120.0).text("fov"));
+            near_slider(ui, z_near);
+            far_slider(ui, z_far);
+        }
+        Projection::Orthographic {
+            height,
+            z_near,
+            z_far,
+        } => {
+            ui.add(
+                egui::Slider::new(height, 0.1..=200.0)
+                    .logarithmic(true)
+                    .text("height"),
+            );
+            near_slider(ui, z_near);
+            far_slider(ui, z_far);
+        }
+        Projection::OrthographicBounds {
+            left,
+            right,
+            bottom,
+            top,
+            z_near,
+            z_far,
+        } => {
+            ui.horizontal(|ui| {
+                ui.label("left right");
+                ui.add(egui::DragValue::new(left).speed(0.1));
+                ui.add(egui::DragValue::new(right).speed(0.1));
+            });
+            ui.horizontal(|ui| {
+                ui.label("bottom top");
+                ui.add(egui::DragValue::new(bottom).speed(0.1));
+                ui.add(egui::DragValue::new(top).speed(0.1));
+            });
+            near_slider(ui, z_near);
+            far_slider(ui, z_far);
+        }
+        Projection::InfinitePerspective { fov_y_deg, z_near } => {
+            ui.add(egui::Slider::new(fov_y_deg, 10.0..=120.0).text("fov"));
+            near_slider(ui, z_near);
+        }
+    }
+}
+
+fn camera_path_section(ui: &mut egui::Ui, player: &mut PathPlayer, cam: &mut Camera) {
+    ui.collapsing("Camera path", |ui| {
+        let status = match player.state {
+            PathState::Idle => "idle".to_owned(),
+            PathState::Recording { elapsed, .. } => format!("recording {:.1}s", elapsed),
+            PathState::Playing { time } => {
+                format!("playing {:.1} / {:.1}s", time, player.path.duration())
+            }
+        };
+        ui.label(format!("{} [R record, K keyframe, L play]", status));
+
+        ui.horizontal(|ui| {
+            if ui.button("record").clicked() {
+                player.start_recording();
+            }
+            if ui.button("play").clicked() {
+                player.play();
+            }
+            if ui.button("stop").clicked() {
+                player.stop();
+            }
+        });
+        ui.add(egui::Slider::new(&mut player.speed, 0.1..=4.0).text("speed"));
+        ui.label(format!(
+            "{} [F6 save, F7 load, F8 record frames]",
+            player.file.display()
+        ));
+
+        let mut resort = false;
+        let mut remove = None;
+
+        egui::Grid::new("keyframes").striped(true).show(ui, |ui| {
+            for (index, keyframe) in player.path.keyframes.iter_mut().enumerate() {
+                resort |= ui
+                    .add(
+                        egui::DragValue::new(&mut keyframe.time)
+                            .speed(0.05)
+                            .range(0.0..=f32::MAX)
+                            .suffix("s"),
+                    )
+                    .changed();
+
+                if ui.button("view").clicked() {
+                    cam.camera_pos = keyframe.position;
+                    cam.orientation = keyframe.orientation;
+                }
+                if ui
+                    .button("set")
+                    .on_hover_text("replace with the camera")
+                    .clicked()
+                {
+                    keyframe.position = cam.camera_pos;
+                    keyframe.orientation = cam.orientation;
+                }
+                if ui.button("delete").clicked() {
+                    remove = Some(index);
+                }
+                ui.end_row();
+            }
+        });
+
+        if let Some(index) = remove {
+            player.path.keyframes.remove(index);
+        }
+        if resort {
+            player.path.sort();
+        }
+    });
+}
+
+fn lighting_section(ui: &mut egui::Ui, world: &mut WorldState) {
+    ui.collapsing("Lighting", |ui| {
+        ui.horizontal(|ui| {
+            ui.label("sun direction");
+            ui.add(egui::DragValue::new(&mut world.light.direction.x).speed(0.02));
+            ui.add(egui::DragValue::new(&mut world.light.direction.y).speed(0.02));
+            ui.add(egui::DragValue::new(&mut world.light.direction.z).speed(0.02));
+        });
+        ui.add(egui::Slider::new(&mut world.light.intensity, 0.0..=10.0).text("sun intensity"));
+
+        ui.checkbox(&mut world.environment.enabled, "image based lighting");
+        ui.add(
+            egui::Slider::new(&mut world.environment.intensity, 0.0..=4.0)
+                .text("environment intensity"),
+        );
+    });
+}
+
+fn post_section(ui: &mut egui::Ui, post: &mut PostProcessChain) {
+    ui.collapsing("Post processing", |ui| {
+        for (index, effect) in post.effects.iter_mut().enumerate() {
+            ui.checkbox(
+                &mut effect.enabled,
+                format!("{} [F{}]", effect.name, index + 1),
+            );
+        }
+    });
+}
+
+fn input_section(ui: &mut egui::Ui, actions: &mut ActionMap) {
+    ui.collapsing("Input", |ui| {
+        let ActionMap {
+            bindings,
+            file,
+            rebinding,
+            ..
+        } = actions;
+
+        match rebinding {
+            Some(target) => ui.colored_label(
+                egui::Color32::YELLOW,
+                format!(
+                    "press a key or mouse button for {:?}, Escape cancels",
+                    target
+                ),
+            ),
+            None => ui.label("click a binding to remove it, + adds the next key or button pressed"),
+        };
+
+        egui::Grid::new("actions").striped(true).show(ui, |ui| {
+            for action in bindings.actions.iter_mut() {
+                ui.label(&action.name);
+                bindings_row(
+                    ui,
+                    &mut action.bindings,
+                    RebindTarget::Action(action.name.clone()),
+                    rebinding,
+                );
+                ui.end_row();
+            }
+
+            for axis in bindings.axes.iter_mut() {
+                ui.label(format!("{} +", axis.name));
+                bindings_row(
+                    ui,
+                    &mut axis.positive,
+                    RebindTarget::AxisPositive(axis.name.clone()),
+                    rebinding,
+                );
+                ui.end_row();
+
+                ui.label(format!("{} -", axis.name));
+                bindings_row(
+                    ui,
+                    &mut axis.negative,
+                    RebindTarget::AxisNegative(axis.name.clone()),
+                    rebinding,
+                );
+                ui.end_row();
+            }
+        });
+
+        ui.label(file.display().to_string());
+        ui.horizontal(|ui| {
+            if ui.button("save").clicked()
+                && let Err(e) = bindings.save(file)
+            {
+                log::error!("{}", e);
+            }
+            if ui.button("reload").clicked() {
+                match Bindings::load(file) {
+                    Ok(loaded) => *bindings = loaded,
+                    Err(e) => log::error!("{}", e),
+                }
+            }
+            if ui.button("defaults").clicked() {
+                *bindings = Bindings::defaults();
+            }
+        });
+    });
+}
+
+fn bindings_row(
+    ui: &mut egui::Ui,
+    bindings: &mut Vec<Binding>,
+    target: RebindTarget,
+    rebinding: &mut Option<RebindTarget>,
+) {
+    ui.horizontal(|ui| {
+        let mut remove = None;
+        for (index, binding) in bindings.iter().enumerate() {
+            if ui.small_button(binding.to_string()).clicked() {
+                remove = Some(index);
+            }
+        }
+        if let Some(index) = remove {
+            bindings.remove(index);
+        }
+
+        if ui.small_button("+").clicked() {
+            *rebinding = Some(target);
+        }
+    });
+}
+
+fn shader_section(ui: &mut egui::Ui) {
+    ui.collapsing("Shaders", |ui| {
+        egui::Grid::new("shaders").striped(true).show(ui, |ui| {
+            for status in shaders::loaded_shaders() {
+                let mut status = status.borrow_mut();
+
+                ui.label(&status.name)
+                    .on_hover_text(format!("{}\n{}", status.vert_path, status.frag_path));
+
+                match &status.error {
+                    Some(error) => {
+                        ui.colored_label(egui::Color32::RED, "error")
+                            .on_hover_text(error);
+                    }
+                    None => {
+                        ui.colored_label(egui::Color32::GREEN, "ok");
+                    }
+                }
+
+                ui.label(format!("{} reloads", status.reloads));
+
+                if ui.button("reload").clicked() {
+                    status.reload_requested = true;
+                }
+                ui.end_row();
+            }
+        });
+    });
+}
@@ -0,0 +1,48 @@
+// Polled file system watching for files that are edited while the app runs. A folder is watched
+// instead of the file, the file may not exist yet and editors replace it on save
+use std::{
+    path::Path,
+    sync::mpsc::{Receiver, channel},
+};
+
+use notify::{EventKind, RecommendedWatcher, RecursiveMode, Watcher};
+
+pub struct FolderWatch {
+    /// stops watching when dropped
+    _watcher: RecommendedWatcher,
+    events: Receiver<notify::Result<notify::Event>>,
+}
+
+impl FolderWatch {
+    pub fn new(folder: &Path) -> notify::Result<Self> {
+        let (tx, events) = channel();
+        let mut watcher = notify::recommended_watcher(tx)?;
+        watcher.watch(folder, RecursiveMode::NonRecursive)?;
+        return Ok(Self {
+            _watcher: watcher,
+            events,
+        });
+    }
+
+    /// Watch the folder `file` is in
+    pub fn containing(file: &Path) -> notify::Result<Self> {
+        match file.parent() {
+            Some(folder) if !folder.as_os_str().is_empty() => Self::new(folder),
+            _ => Self::new(Path::new(".")),
+        }
+    }
+
+    /// Whether a file `matches` accepts was created or written since the last call. Editors
+    /// write a file in several steps, they all count as one change
+    pub fn changed(&self, matches: impl Fn(&Path) -> bool) -> bool {
+        let mut changed = false;
+        while let Ok(event) = self.events.try_recv() {
+            let Ok(event) = event else {
+                continue;
+            };
+            changed |= matches!(event.kind, EventKind::Create(..) | EventKind::Modify(..))
+                && event.paths.iter().any(|path| matches(path));
+        }
+        return changed;
+    }
+}
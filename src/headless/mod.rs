@@ -0,0 +1,291 @@
+mod recording;
+pub use recording::{Call, RecordingBackend};
+
+use miniquad::EventHandler;
+
+use crate::{config::ConfigFile, replay::InputRecording, stage::Stage};
+
+/// Run `frames` frames of the stage against a RecordingBackend and print what the gpu would have
+/// been asked to do, followed by every resource that was not freed on quit. With a `replay` the
+/// stage gets its input, automated runs can compare where the camera ends up
+pub fn run(
+    frames: usize,
+    model_path: Option<String>,
+    screen_size: (f32, f32),
+    replay: Option<InputRecording>,
+    config: ConfigFile,
+) {
+    let backend = RecordingBackend::new();
+    let log = backend.log();
+
+    let mut stage = Stage::with_backend(Box::new(backend), model_path, screen_size, true, config);
+    if let Some(recording) = replay {
+        stage.start_replay(recording);
+    }
+
+    for _ in 0..frames {
+        stage.update();
+        EventHandler::draw(&mut stage);
+    }
+    stage.quit_requested_event();
+
+    let log = log.borrow();
+    for (number, frame) in log.frames().iter().enumerate() {
+        if frame.last() != Some(&Call::CommitFrame) {
+            println!("after the last frame: {} calls", frame.len());
+            continue;
+        }
+
+        let count = |f: fn(&Call) -> bool| frame.iter().filter(|c| f(c)).count();
+
+        println!(
+            "frame {}: {} calls, {} passes, {} draws, {} bindings, {} uniform uploads, {} new resources",
+            number,
+            frame.len(),
+            count(|c| matches!(c, Call::BeginPass { .. })),
+            count(|c| matches!(c, Call::Draw { .. })),
+            count(|c| matches!(c, Call::ApplyBindings { .. })),
+            count(|c| matches!(c, Call::ApplyUniforms { .. })),
+            count(|c| matches!(
+                c,
+                Call::NewShader { .. }
+                    | Call::NewTexture { .. }
+                    | Call::NewBuffer { .. }
+                    | Call::NewPipeline { .. }
+                    | Call::NewRenderPass { .. }
+            )),
+        );
+    }
+
+    let live = log.live_resources();
+    if live.is_empty() {
+        println!("every resource was freed");
+    } else {
+        for (kind, index) in live {
+            println!("leaked {} {}", kind, index);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{cell::RefCell, path::PathBuf, rc::Rc};
+
+    use miniquad::{EventHandler, KeyCode, KeyMods, RenderingBackend};
+
+    use super::{Call, RecordingBackend, recording::RecordingLog};
+    use crate::{
+        config::ConfigFile,
+        materials::{MATERIAL_TEXTURE_SLOTS, Material},
+        meshes::Mesh,
+        objects::{MeshObject, RenderableObject},
+        scene::Scene,
+        stage::Stage,
+    };
+
+    /// a stage on a recording backend with the demo scene and the default settings
+    fn stage() -> (Stage, Rc<RefCell<RecordingLog>>) {
+        let backend = RecordingBackend::new();
+        let log = backend.log();
+        let config = ConfigFile::load(PathBuf::from("./no_config_in_tests.toml"), false);
+        let stage = Stage::with_backend(Box::new(backend), None, (640.0, 480.0), true, config);
+        return (stage, log);
+    }
+
+    /// the calls of the next frame the stage draws
+    fn draw_frame(stage: &mut Stage, log: &Rc<RefCell<RecordingLog>>) -> Vec<Call> {
+        let before = log.borrow().calls.len();
+        EventHandler::draw(stage);
+        return log.borrow().calls[before..].to_vec();
+    }
+
+    /// everything from the first begin_pass to its end_pass, where the scene is drawn
+    fn scene_pass(frame: &[Call]) -> Vec<Call> {
+        return frame
+            .iter()
+            .skip_while(|call| !matches!(call, Call::BeginPass { .. }))
+            .take_while(|call| **call != Call::EndPass)
+            .cloned()
+            .collect();
+    }
+
+    fn is_file_texture(call: &Call) -> bool {
+        return matches!(
+            call,
+            Call::NewTexture { width, height, render_target: false, .. } if *width > 1 || *height > 1
+        );
+    }
+
+    /// test.png is uploaded once no matter how often the cube is bound, every other slot is a
+    /// 1x1 default
+    #[test]
+    fn textured_cube_uploads_one_texture() {
+        let recording = RecordingBackend::new();
+        let log = recording.log();
+        let mut backend: Box<dyn RenderingBackend> = Box::new(recording);
+        let mut material = Material::new("test cube".to_owned());
+        material.base_color_texture = Some("test.png".to_owned());
+        let mut cube = MeshObject::new(Mesh::cube(1.0), material, glam::Mat4::IDENTITY);
+
+        cube.get_bindings(&mut backend);
+        cube.get_bindings(&mut backend);
+        cube.get_wireframe_bindings(&mut backend);
+        assert_eq!(
+            log.borrow()
+                .calls
+                .iter()
+                .filter(|call| is_file_texture(call))
+                .count(),
+            1
+        );
+
+        cube.drop_gl_resources(&mut backend);
+        assert_eq!(log.borrow().live_resources(), vec![]);
+    }
+
+    #[test]
+    fn quit_frees_everything() {
+        let (mut stage, log) = stage();
+        for _ in 0..3 {
+            stage.update();
+            EventHandler::draw(&mut stage);
+        }
+        stage.quit_requested_event();
+
+        assert_eq!(log.borrow().live_resources(), vec![]);
+    }
+
+    /// one cube at the origin in front of the camera
+    const CUBE_SCENE: &str = r#"(
+        version: 1,
+        camera: (position: (0.0, 0.0, 5.0), yaw_pitch_roll: (0.0, 0.0, 0.0)),
+        light: (direction: (0.0, -1.0, 0.0), color: (1.0, 1.0, 1.0), intensity: 1.0),
+        environment: (cubemap: None, enabled: true, intensity: 1.0),
+        materials: [(name: "plain")],
+        objects: [(name: "cube", shape: Cube(size: 1.0), material: Some("plain"))],
+    )"#;
+
+    #[test]
+    fn draw_issues_one_pass_binding_and_draw_per_object() {
+        let (mut stage, log) = stage();
+        let file = std::env::temp_dir().join("rustic_test_cube_scene.ron");
+        std::fs::write(&file, CUBE_SCENE).unwrap();
+        assert_eq!(stage.load_scene(file), Ok(0));
+
+        let frame = draw_frame(&mut stage, &log);
+        let calls: Vec<Call> = scene_pass(&frame)
+            .into_iter()
+            .filter(|call| {
+                !matches!(
+                    call,
+                    Call::NewTexture { .. } | Call::NewBuffer { .. } | Call::NewRenderPass { .. }
+                )
+            })
+            .collect();
+
+        match calls.as_slice() {
+            [
+                Call::BeginPass { pass: Some(_) },
+                Call::Clear,
+                Call::ApplyPipeline { .. },
+                Call::ApplyBindings {
+                    vertex_buffers,
+                    textures,
+                    ..
+                },
+                Call::ApplyUniforms { .. },
+                Call::Draw {
+                    base_element: 0,
+                    num_elements: 36,
+                    num_instances: 1,
+                },
+            ] => {
+                assert_eq!(vertex_buffers.len(), 1);
+                // the material slots and the environment
+                assert_eq!(textures.len(), MATERIAL_TEXTURE_SLOTS + 1);
+            }
+            other => panic!("unexpected scene pass {:#?}", other),
+        }
+        assert_eq!(frame.last(), Some(&Call::CommitFrame));
+
+        stage.quit_requested_event();
+    }
+
+    #[test]
+    fn saving_writes_moved_objects_back() {
+        let (mut stage, _) = stage();
+        let file = std::env::temp_dir().join("rustic_test_saved_scene.ron");
+        std::fs::write(&file, CUBE_SCENE).unwrap();
+        assert_eq!(stage.load_scene(file.clone()), Ok(0));
+
+        stage.renderable_objects[0]
+            .set_model_matrix(glam::Mat4::from_translation(glam::vec3(1.0, 2.0, 3.0)));
+        assert_eq!(stage.save_scene(file.clone()), Ok(()));
+
+        let saved = Scene::load(&file).unwrap();
+        assert_eq!(saved.objects[0].position, [1.0, 2.0, 3.0]);
+        assert_eq!(saved.objects[0].scale, [1.0; 3]);
+
+        stage.quit_requested_event();
+    }
+
+    #[test]
+    fn backtick_toggles_the_console() {
+        let (mut stage, _) = stage();
+        let press = |stage: &mut Stage| {
+            stage.key_down_event(KeyCode::GraveAccent, KeyMods::default(), false);
+            stage.key_up_event(KeyCode::GraveAccent, KeyMods::default());
+            stage.update();
+        };
+
+        press(&mut stage);
+        assert!(stage.debug_ui.console_open);
+
+        press(&mut stage);
+        assert!(!stage.debug_ui.console_open);
+
+        stage.quit_requested_event();
+    }
+
+    #[test]
+    fn reloading_scripts_replaces_their_objects() {
+        let (mut stage, _) = stage();
+        let count = |stage: &Stage| {
+            let scripted = stage
+                .renderable_objects
+                .iter()
+                .filter(|object| object.get_script_id().is_some())
+                .count();
+            (scripted, stage.renderable_objects.len())
+        };
+
+        stage.update();
+        let before = count(&stage);
+        assert!(before.0 > 0);
+
+        stage.scripts.request_reload();
+        stage.update();
+        assert_eq!(count(&stage), before);
+
+        stage.quit_requested_event();
+    }
+
+    #[test]
+    fn unreadable_textures_fall_back_to_the_default() {
+        let recording = RecordingBackend::new();
+        let log = recording.log();
+        let mut backend: Box<dyn RenderingBackend> = Box::new(recording);
+
+        let corrupt = std::env::temp_dir().join("rustic_test_corrupt.png");
+        std::fs::write(&corrupt, b"not a png").unwrap();
+
+        let mut material = Material::new("broken".to_owned());
+        material.base_color_texture = Some("does_not_exist.png".to_owned());
+        material.normal_texture = Some(corrupt.to_string_lossy().into_owned());
+        material.get_images(&mut backend);
+
+        assert!(!log.borrow().calls.iter().any(is_file_texture));
+        material.drop_gl_resources(&mut backend);
+        assert_eq!(log.borrow().live_resources(), vec![]);
+    }
+}
@@ -0,0 +1,469 @@
+use std::{
+    cell::RefCell,
+    collections::{HashMap, HashSet},
+    rc::Rc,
+};
+
+use miniquad::{
+    Backend, BufferId, BufferLayout, BufferSource, BufferType, BufferUsage, ContextInfo,
+    FilterMode, MipmapFilterMode, PassAction, Pipeline, PipelineParams, RawId, RenderPass,
+    RenderingBackend, ShaderError, ShaderId, ShaderMeta, ShaderSource, TextureAccess,
+    TextureFormat, TextureId, TextureParams, TextureSource, TextureWrap, VertexAttribute,
+};
+
+/// One call into the backend. Resources are named by the index the recording backend handed out,
+/// indices are shared between all kinds of resources so every one is unique
+#[derive(Debug, Clone, PartialEq)]
+pub enum Call {
+    NewShader {
+        shader: usize,
+        images: Vec<String>,
+    },
+    DeleteShader {
+        shader: usize,
+    },
+    NewTexture {
+        texture: usize,
+        width: u32,
+        height: u32,
+        format: TextureFormat,
+        render_target: bool,
+    },
+    UpdateTexture {
+        texture: usize,
+    },
+    DeleteTexture {
+        texture: usize,
+    },
+    NewBuffer {
+        buffer: usize,
+        buffer_type: BufferType,
+        usage: BufferUsage,
+        /// only known for empty buffers, miniquad keeps the size of slices to itself
+        size: Option<usize>,
+    },
+    UpdateBuffer {
+        buffer: usize,
+    },
+    DeleteBuffer {
+        buffer: usize,
+    },
+    NewPipeline {
+        pipeline: usize,
+        shader: usize,
+        attributes: Vec<&'static str>,
+    },
+    ApplyPipeline {
+        pipeline: usize,
+    },
+    DeletePipeline {
+        pipeline: usize,
+    },
+    NewRenderPass {
+        pass: usize,
+        color: Vec<usize>,
+        depth: Option<usize>,
+    },
+    DeleteRenderPass {
+        pass: usize,
+    },
+    /// None is the window
+    BeginPass {
+        pass: Option<usize>,
+    },
+    EndPass,
+    Clear,
+    ApplyBindings {
+        vertex_buffers: Vec<usize>,
+        index_buffer: usize,
+        textures: Vec<usize>,
+    },
+    ApplyUniforms {
+        bytes: Vec<u8>,
+    },
+    Draw {
+        base_element: i32,
+        num_elements: i32,
+        num_instances: i32,
+    },
+    CommitFrame,
+}
+
+/// Everything a RecordingBackend was asked to do, shared with whoever wants to inspect it
+#[derive(Default)]
+pub struct RecordingLog {
+    pub calls: Vec<Call>,
+
+    next_index: usize,
+
+    /// resources that were created and not deleted yet
+    textures: HashMap<usize, TextureParams>,
+    buffers: HashMap<usize, Option<usize>>,
+    shaders: HashSet<usize>,
+    pipelines: HashSet<usize>,
+    passes: HashMap<usize, (Vec<usize>, Option<usize>)>,
+}
+
+impl RecordingLog {
+    /// the calls split at every commit_frame, a trailing unfinished frame is included
+    pub fn frames(&self) -> Vec<&[Call]> {
+        self.calls
+            .split_inclusive(|call| *call == Call::CommitFrame)
+            .collect()
+    }
+
+    /// (kind, index) of every resource that is still alive
+    pub fn live_resources(&self) -> Vec<(&'static str, usize)> {
+        let mut live: Vec<(&'static str, usize)> = self
+            .textures
+            .keys()
+            .map(|i| ("texture", *i))
+            .chain(self.buffers.keys().map(|i| ("buffer", *i)))
+            .chain(self.shaders.iter().map(|i| ("shader", *i)))
+            .chain(self.pipelines.iter().map(|i| ("pipeline", *i)))
+            .chain(self.passes.keys().map(|i| ("render pass", *i)))
+            .collect();
+
+        live.sort_by_key(|(_, index)| *index);
+        return live;
+    }
+
+    fn next(&mut self) -> usize {
+        self.next_index += 1;
+        return self.next_index;
+    }
+}
+
+/// RenderingBackend that draws nothing and only writes down what it was asked to do, so a Stage
+/// can run without a window or gpu. Shaders always compile
+pub struct RecordingBackend {
+    log: Rc<RefCell<RecordingLog>>,
+
+    /// needed to hand out slices from `render_pass_color_attachments`
+    attachments: HashMap<usize, Vec<TextureId>>,
+    texture_indices: HashMap<TextureId, usize>,
+}
+
+impl RecordingBackend {
+    pub fn new() -> Self {
+        Self {
+            log: Rc::new(RefCell::new(RecordingLog::default())),
+            attachments: HashMap::new(),
+            texture_indices: HashMap::new(),
+        }
+    }
+
+    /// handle to the log that stays valid after the backend was boxed up and given away
+    pub fn log(&self) -> Rc<RefCell<RecordingLog>> {
+        self.log.clone()
+    }
+
+    fn record(&self, call: Call) {
+        self.log.borrow_mut().calls.push(call);
+    }
+
+    fn texture_index(&self, texture: TextureId) -> usize {
+        *self.texture_indices.get(&texture).unwrap_or(&usize::MAX)
+    }
+}
+
+/// miniquad does not let anyone outside of it create resource ids, they all wrap a plain usize
+fn handle<T: Copy>(index: usize) -> T {
+    assert_eq!(std::mem::size_of::<T>(), std::mem::size_of::<usize>());
+    unsafe { std::mem::transmute_copy(&index) }
+}
+
+fn index<T: Copy>(handle: T) -> usize {
+    assert_eq!(std::mem::size_of::<T>(), std::mem::size_of::<usize>());
+    unsafe { std::mem::transmute_copy(&handle) }
+}
+
+impl RenderingBackend for RecordingBackend {
+    fn info(&self) -> ContextInfo {
+        ContextInfo {
+            backend: Backend::OpenGl,
+            gl_version_string: "recording".to_owned(),
+            glsl_support: Default::default(),
+            features: Default::default(),
+        }
+    }
+
+    fn new_shader(
+        &mut self,
+        _shader: ShaderSource,
+        meta: ShaderMeta,
+    ) -> Result<ShaderId, ShaderError> {
+        let mut log = self.log.borrow_mut();
+        let shader = log.next();
+        log.shaders.insert(shader);
+        log.calls.push(Call::NewShader {
+            shader,
+            images: meta.images,
+        });
+
+        return Ok(handle(shader));
+    }
+
+    fn new_texture(
+        &mut self,
+        access: TextureAccess,
+        _data: TextureSource,
+        params: TextureParams,
+    ) -> TextureId {
+        let mut log = self.log.borrow_mut();
+        let texture = log.next();
+        log.textures.insert(texture, params);
+        log.calls.push(Call::NewTexture {
+            texture,
+            width: params.width,
+            height: params.height,
+            format: params.format,
+            render_target: access == TextureAccess::RenderTarget,
+        });
+
+        let id = TextureId::from_raw_id(RawId::OpenGl(texture as u32));
+        self.texture_indices.insert(id, texture);
+        return id;
+    }
+
+    fn texture_params(&self, texture: TextureId) -> TextureParams {
+        self.log.borrow().textures[&self.texture_index(texture)]
+    }
+
+    unsafe fn texture_raw_id(&self, texture: TextureId) -> RawId {
+        RawId::OpenGl(self.texture_index(texture) as u32)
+    }
+
+    fn texture_set_min_filter(
+        &mut self,
+        _texture: TextureId,
+        _filter: FilterMode,
+        _mipmap_filter: MipmapFilterMode,
+    ) {
+    }
+
+    fn texture_set_mag_filter(&mut self, _texture: TextureId, _filter: FilterMode) {}
+
+    fn texture_set_wrap(
+        &mut self,
+        _texture: TextureId,
+        _wrap_x: TextureWrap,
+        _wrap_y: TextureWrap,
+    ) {
+    }
+
+    fn texture_generate_mipmaps(&mut self, _texture: TextureId) {}
+
+    fn texture_resize(
+        &mut self,
+        texture: TextureId,
+        width: u32,
+        height: u32,
+        _bytes: Option<&[u8]>,
+    ) {
+        let index = self.texture_index(texture);
+        if let Some(params) = self.log.borrow_mut().textures.get_mut(&index) {
+            params.width = width;
+            params.height = height;
+        }
+        self.record(Call::UpdateTexture { texture: index });
+    }
+
+    fn texture_read_pixels(&mut self, _texture: TextureId, bytes: &mut [u8]) {
+        bytes.fill(0);
+    }
+
+    fn texture_update_part(
+        &mut self,
+        texture: TextureId,
+        _x_offset: i32,
+        _y_offset: i32,
+        _width: i32,
+        _height: i32,
+        _bytes: &[u8],
+    ) {
+        self.record(Call::UpdateTexture {
+            texture: self.texture_index(texture),
+        });
+    }
+
+    fn new_render_pass_mrt(
+        &mut self,
+        color_img: &[TextureId],
+        _resolve_img: Option<&[TextureId]>,
+        depth_img: Option<TextureId>,
+    ) -> RenderPass {
+        let color: Vec<usize> = color_img.iter().map(|t| self.texture_index(*t)).collect();
+        let depth = depth_img.map(|t| self.texture_index(t));
+
+        let mut log = self.log.borrow_mut();
+        let pass = log.next();
+        log.passes.insert(pass, (color.clone(), depth));
+        log.calls.push(Call::NewRenderPass { pass, color, depth });
+
+        self.attachments.insert(pass, color_img.to_vec());
+        return handle(pass);
+    }
+
+    fn render_pass_color_attachments(&self, render_pass: RenderPass) -> &[TextureId] {
+        &self.attachments[&index(render_pass)]
+    }
+
+    /// like the gl backend this also deletes the attachments
+    fn delete_render_pass(&mut self, render_pass: RenderPass) {
+        let pass = index(render_pass);
+        self.attachments.remove(&pass);
+
+        let mut log = self.log.borrow_mut();
+        if let Some((color, depth)) = log.passes.remove(&pass) {
+            for texture in color.into_iter().chain(depth) {
+                log.textures.remove(&texture);
+            }
+        }
+        log.calls.push(Call::DeleteRenderPass { pass });
+    }
+
+    fn new_pipeline(
+        &mut self,
+        _buffer_layout: &[BufferLayout],
+        attributes: &[VertexAttribute],
+        shader: ShaderId,
+        _params: PipelineParams,
+    ) -> Pipeline {
+        let mut log = self.log.borrow_mut();
+        let pipeline = log.next();
+        log.pipelines.insert(pipeline);
+        log.calls.push(Call::NewPipeline {
+            pipeline,
+            shader: index(shader),
+            attributes: attributes.iter().map(|a| a.name).collect(),
+        });
+
+        return handle(pipeline);
+    }
+
+    fn apply_pipeline(&mut self, pipeline: &Pipeline) {
+        self.record(Call::ApplyPipeline {
+            pipeline: index(*pipeline),
+        });
+    }
+
+    fn delete_pipeline(&mut self, pipeline: Pipeline) {
+        let pipeline = index(pipeline);
+        let mut log = self.log.borrow_mut();
+        log.pipelines.remove(&pipeline);
+        log.calls.push(Call::DeletePipeline { pipeline });
+    }
+
+    fn new_buffer(
+        &mut self,
+        type_: BufferType,
+        usage: BufferUsage,
+        data: BufferSource,
+    ) -> BufferId {
+        let size = match data {
+            BufferSource::Empty { size, .. } => Some(size),
+            BufferSource::Slice(_) => None,
+        };
+
+        let mut log = self.log.borrow_mut();
+        let buffer = log.next();
+        log.buffers.insert(buffer, size);
+        log.calls.push(Call::NewBuffer {
+            buffer,
+            buffer_type: type_,
+            usage,
+            size,
+        });
+
+        return handle(buffer);
+    }
+
+    fn buffer_update(&mut self, buffer: BufferId, _data: BufferSource) {
+        self.record(Call::UpdateBuffer {
+            buffer: index(buffer),
+        });
+    }
+
+    fn buffer_size(&mut self, buffer: BufferId) -> usize {
+        self.log.borrow().buffers[&index(buffer)].unwrap_or(0)
+    }
+
+    fn delete_buffer(&mut self, buffer: BufferId) {
+        let buffer = index(buffer);
+        let mut log = self.log.borrow_mut();
+        log.buffers.remove(&buffer);
+        log.calls.push(Call::DeleteBuffer { buffer });
+    }
+
+    fn delete_texture(&mut self, texture: TextureId) {
+        let texture = self.texture_index(texture);
+        let mut log = self.log.borrow_mut();
+        log.textures.remove(&texture);
+        log.calls.push(Call::DeleteTexture { texture });
+    }
+
+    fn delete_shader(&mut self, program: ShaderId) {
+        let shader = index(program);
+        let mut log = self.log.borrow_mut();
+        log.shaders.remove(&shader);
+        log.calls.push(Call::DeleteShader { shader });
+    }
+
+    fn apply_viewport(&mut self, _x: i32, _y: i32, _w: i32, _h: i32) {}
+
+    fn apply_scissor_rect(&mut self, _x: i32, _y: i32, _w: i32, _h: i32) {}
+
+    fn apply_bindings_from_slice(
+        &mut self,
+        vertex_buffers: &[BufferId],
+        index_buffer: BufferId,
+        textures: &[TextureId],
+    ) {
+        self.record(Call::ApplyBindings {
+            vertex_buffers: vertex_buffers.iter().map(|b| index(*b)).collect(),
+            index_buffer: index(index_buffer),
+            textures: textures.iter().map(|t| self.texture_index(*t)).collect(),
+        });
+    }
+
+    fn apply_uniforms_from_bytes(&mut self, uniform_ptr: *const u8, size: usize) {
+        let bytes = unsafe { std::slice::from_raw_parts(uniform_ptr, size) }.to_vec();
+        self.record(Call::ApplyUniforms { bytes });
+    }
+
+    fn clear(
+        &mut self,
+        _color: Option<(f32, f32, f32, f32)>,
+        _depth: Option<f32>,
+        _stencil: Option<i32>,
+    ) {
+        self.record(Call::Clear);
+    }
+
+    fn begin_default_pass(&mut self, action: PassAction) {
+        self.begin_pass(None, action);
+    }
+
+    fn begin_pass(&mut self, pass: Option<RenderPass>, _action: PassAction) {
+        self.record(Call::BeginPass {
+            pass: pass.map(index),
+        });
+    }
+
+    fn end_render_pass(&mut self) {
+        self.record(Call::EndPass);
+    }
+
+    fn commit_frame(&mut self) {
+        self.record(Call::CommitFrame);
+    }
+
+    fn draw(&self, base_element: i32, num_elements: i32, num_instances: i32) {
+        self.record(Call::Draw {
+            base_element,
+            num_elements,
+            num_instances,
+        });
+    }
+}
@@ -1,101 +1,305 @@
-use miniquad::{
-    gl::{GL_DEPTH_BUFFER_BIT, GL_FILL, GL_FRONT_AND_BACK, GL_LINE, GL_TRIANGLES},
+// the code base prefers explicit returns and spelled out self types
+#![allow(clippy::needless_return, clippy::needless_arbitrary_self_type)]
+
+use actions::ActionMap;
+use bounds::{BoundingVolume, Frustum};
+use camera_path::PathPlayer;
+use capture::FrameCapture;
+use config::ConfigFile;
+use console::Console;
+use debug_draw::DebugDrawRenderer;
+use debug_ui::{DebugUi, DebugUiState};
+use materials::Environment;
+use miniquad::*;
+use postprocess::PostProcessChain;
+use profiler::profile_scope;
+use render_targets::RenderTarget;
+use replay::{InputRecording, InputReplay};
+use scene::Scene;
+use scripting::ScriptHost;
+use stage::{
+    controllers::{FollowController, FreeFlyController, OrbitController},
+    input::InputData,
     *,
 };
-use objects::{RenderableObject, TestTexturedCube};
-use stage::{input::InputData, *};
-
+use text::{Font, TextRenderer, TextStyle};
+use time::Time;
+
+mod actions;
+mod bounds;
+mod camera_path;
+mod capture;
+mod config;
+mod console;
+mod debug_draw;
+mod debug_ui;
+mod file_watch;
+mod headless;
+mod materials;
+mod meshes;
 mod objects;
+mod picking;
+mod postprocess;
+mod profiler;
+mod render_targets;
+mod replay;
+mod scene;
+mod scripting;
 /**
 * General Notes:
 * - Not sure if mipmaps work correctly
 */
 mod shaders;
 mod stage;
+mod text;
 mod textures;
+mod time;
 
 impl Stage {
-    pub fn new() -> Stage {
-        let mut ctx: Box<dyn RenderingBackend> = window::new_rendering_backend();
-
+    /// `model_path` is an optional .obj, .gltf or .glb file shown next to the test scene,
+    /// `fullscreen` has to match what the window was created with, `config` is what it was
+    /// created from
+    pub fn new(model_path: Option<String>, fullscreen: bool, config: ConfigFile) -> Stage {
         miniquad::window::set_cursor_grab(true);
-        let mut test_textured_cube = TestTexturedCube::new();
 
-        let myshader = shaders::ShaderFile::new("basic".to_owned());
-
-        let shader = ctx
-            .new_shader(myshader.get_shadersource(), shader::meta())
-            .unwrap();
+        let mut stage = Self::with_backend(
+            window::new_rendering_backend(),
+            model_path,
+            window::screen_size(),
+            false,
+            config,
+        );
+        stage.meta.dpi_scale = window::dpi_scale();
+        stage.meta.fullscreen = fullscreen;
 
-        let mut pipelineparams = PipelineParams::default();
-        pipelineparams.depth_test = Comparison::Less;
-        pipelineparams.depth_write = true;
+        return stage;
+    }
 
-        let pipeline = ctx.new_pipeline(
-            &[BufferLayout::default()],
-            &[
-                VertexAttribute::new("in_pos", VertexFormat::Float3),
-                VertexAttribute::new("uv_pos", VertexFormat::Float2),
-            ],
-            shader,
-            pipelineparams,
-        );
+    /// Build the stage on any backend without touching the window, so it can also run against a
+    /// recording backend. `screen_size` is in physical pixels, afterwards it only changes through
+    /// resize_event
+    pub fn with_backend(
+        mut ctx: Box<dyn RenderingBackend>,
+        model_path: Option<String>,
+        screen_size: (f32, f32),
+        headless: bool,
+        config: ConfigFile,
+    ) -> Stage {
+        let myshader = shaders::ShaderFile::new("pbr".to_owned());
+
+        let compiled = ctx.new_shader(myshader.get_shadersource(), shader::meta());
+        myshader.report_compile(&compiled);
+        let shader = compiled.unwrap();
+
+        let scene_depth_test = Comparison::Less;
+        let pipeline =
+            new_scene_pipeline(&mut ctx, shader, PrimitiveType::Triangles, scene_depth_test);
+        let wireframe_pipeline =
+            new_scene_pipeline(&mut ctx, shader, PrimitiveType::Lines, scene_depth_test);
 
         let settings = Settings {
-            mouse_sensitivity: 0.2,
+            mouse_sensitivity: config.config.input.mouse_sensitivity,
 
             render_wireframe: false,
-            debug_toggle_1: false,
-            debug_toggle_2: false,
-            debug_toggle_3: false,
-            debug_toggle_4: false,
+            show_bounds: false,
+            show_reference_lines: false,
         };
 
-        Stage {
+        let cam = Camera {
+            camera_pos: glam::Vec3 {
+                x: 0.0,
+                y: 0.0,
+                z: 10.0,
+            },
+            // looking along -z towards the cubes
+            orientation: glam::Quat::IDENTITY,
+
+            camera_speed: config.config.camera.speed,
+
+            projection: Projection::Perspective {
+                fov_y_deg: config.config.camera.fov_y_deg,
+                z_near: config.config.camera.z_near,
+                z_far: config.config.camera.z_far,
+            },
+            aspect_ratio: screen_size.0 / screen_size.1,
+            transition: None,
+        };
+
+        let mut console = Console::new();
+        stage::register_commands(&mut console);
+
+        let font = Font::load("./fonts/DejaVuSansMono.ttf", 16.0)
+            .inspect_err(|e| log::error!("no text will be shown: {}", e))
+            .ok();
+
+        let mut stage = Stage {
             pipeline,
+            wireframe_pipeline,
+            scene_shader: shader,
+            scene_depth_test,
+            scene_target: RenderTarget::new(TextureFormat::RGBA16F, true),
+            post: PostProcessChain::new(),
+            debug_draw: DebugDrawRenderer::new(),
+            text: TextRenderer::new(font),
+            debug_ui: DebugUi::new(),
+            capture: FrameCapture::new(),
+            camera_path: PathPlayer::new(),
+            selection: None,
             ctx,
             settings,
+            config,
+            console,
+            scripts: ScriptHost::new(scripting::DEFAULT_SCRIPT.into(), !headless),
             shaders: vec![myshader],
             input: InputData::new(),
+            actions: ActionMap::load(actions::DEFAULT_BINDINGS_FILE.into()),
+            replay: InputReplay::new(),
+            time: Time::new(date::now()),
+            previous_camera_pose: cam.pose(),
+            camera_controllers: vec![
+                Box::new(FreeFlyController::new()),
+                Box::new(FreeFlyController::six_dof()),
+                Box::new(OrbitController::new()),
+                Box::new(FollowController::new()),
+            ],
+            active_camera_controller: 0,
             world: WorldState {
-                cam: Camera {
-                    camera_pos: glam::Vec3 {
-                        x: 0.0,
-                        y: 0.0,
-                        z: 10.0,
-                    },
-                    camera_front: glam::Vec3 {
-                        x: 0.0,
-                        y: 0.0,
-                        z: -1.0,
-                    },
-                    camera_up: glam::Vec3 {
-                        x: 0.0,
-                        y: 1.0,
-                        z: 0.0,
-                    },
-
-                    pitch: 0.0,
-                    yaw: -90.0,
-                    camera_speed: 5.2,
-
-                    // perspective
-                    fov_y_deg: 45.0,
-                    aspect_ratio: 1.0,
-                    z_near: 0.1,
-                    z_far: 100.0,
-                },
+                cam,
+
+                // until load_scene replaces them
+                light: Scene::default().light.build(),
+                environment: Environment::new(None),
             },
             meta: StageMetadata {
-                last_time_update_fn_run: date::now(),
-                _time_stage_started: date::now(),
                 exited: false,
+                headless,
+                screen_size,
+                dpi_scale: 1.0,
+                fullscreen: false,
+                render_stats: RenderStats::default(),
+                fps: FpsCounter::default(),
+                notice: Some(("F12 opens the debug panel".to_owned(), 5.0)),
             },
-            renderable_objects: vec![Box::new(test_textured_cube)],
+            scene: Scene::default(),
+            scene_file: scene::DEFAULT_SCENE.into(),
+            renderable_objects: vec![],
+        };
+
+        if let Err(e) = stage.load_scene(scene::DEFAULT_SCENE.into()) {
+            log::error!("{}, starting with an empty scene", e);
+        }
+
+        if let Some(path) = model_path {
+            match meshes::load_model(&path) {
+                Ok(objects) => {
+                    for object in objects {
+                        stage.renderable_objects.push(Box::new(object));
+                    }
+                }
+                Err(e) => log::error!("{}", e),
+            }
         }
+
+        return stage;
     }
 }
 
+impl Stage {
+    /// Recompile the scene shader after its code changed, a shader that does not compile keeps
+    /// the last working pipeline
+    pub fn reload_scene_shader(&mut self) {
+        let compiled = self
+            .ctx
+            .new_shader(self.shaders[0].get_shadersource(), shader::meta());
+        self.shaders[0].report_compile(&compiled);
+
+        if let Ok(shader) = compiled {
+            self.ctx.delete_shader(self.scene_shader);
+            self.scene_shader = shader;
+            self.rebuild_scene_pipelines();
+        }
+    }
+
+    /// Select the closest object under the cursor, given in pixels from the top left. Clicking
+    /// on nothing clears the selection
+    pub fn select_at(&mut self, cursor: glam::Vec2) {
+        let (width, height) = self.meta.screen_size;
+        let ray = self.world.cam.screen_ray(cursor, glam::vec2(width, height));
+
+        let candidates = self
+            .renderable_objects
+            .iter()
+            .enumerate()
+            .flat_map(|(index, object)| {
+                object
+                    .get_instance_transforms()
+                    .into_iter()
+                    .enumerate()
+                    .map(move |(instance, model)| (index, instance, model, object.as_ref()))
+            });
+
+        self.selection = picking::pick(&ray, candidates);
+
+        if let Some(hit) = self.selection {
+            let message = format!(
+                "selected {} #{} at distance {:.2}",
+                self.renderable_objects[hit.object].get_material().name,
+                hit.instance,
+                hit.distance
+            );
+            self.notify(message);
+        }
+    }
+
+    /// recreate both scene pipelines from scene_shader and scene_depth_test
+    fn rebuild_scene_pipelines(&mut self) {
+        self.ctx.delete_pipeline(self.pipeline);
+        self.ctx.delete_pipeline(self.wireframe_pipeline);
+
+        self.pipeline = new_scene_pipeline(
+            &mut self.ctx,
+            self.scene_shader,
+            PrimitiveType::Triangles,
+            self.scene_depth_test,
+        );
+        self.wireframe_pipeline = new_scene_pipeline(
+            &mut self.ctx,
+            self.scene_shader,
+            PrimitiveType::Lines,
+            self.scene_depth_test,
+        );
+    }
+}
+
+/// vertex layout and depth state every scene object is drawn with, `Lines` is the wireframe
+/// variant that expects the line list index buffers of get_wireframe_bindings.
+/// `depth_test` is Greater for reversed depth projections, Less otherwise
+fn new_scene_pipeline(
+    ctx: &mut Box<dyn RenderingBackend>,
+    shader: ShaderId,
+    primitive_type: PrimitiveType,
+    depth_test: Comparison,
+) -> Pipeline {
+    let pipelineparams = PipelineParams {
+        depth_test,
+        depth_write: true,
+        primitive_type,
+        ..Default::default()
+    };
+
+    return ctx.new_pipeline(
+        &[BufferLayout::default()],
+        &[
+            VertexAttribute::new("in_pos", VertexFormat::Float3),
+            VertexAttribute::new("in_normal", VertexFormat::Float3),
+            VertexAttribute::new("uv_pos", VertexFormat::Float2),
+            VertexAttribute::new("in_tangent", VertexFormat::Float4),
+        ],
+        shader,
+        pipelineparams,
+    );
+}
+
 impl EventHandler for Stage {
     fn update(&mut self) {
         self.update();
@@ -124,101 +328,454 @@ impl EventHandler for Stage {
     fn mouse_motion_event(&mut self, _x: f32, _y: f32) {
         self.mouse_motion_event(_x, _y);
     }
+
+    fn mouse_wheel_event(&mut self, _x: f32, _y: f32) {
+        self.mouse_wheel_event(_x, _y);
+    }
+
+    fn char_event(&mut self, _character: char, _keymods: KeyMods, _repeat: bool) {
+        self.char_event(_character, _keymods, _repeat);
+    }
+
+    fn resize_event(&mut self, _width: f32, _height: f32) {
+        self.resize_event(_width, _height);
+    }
     fn draw(&mut self) {
+        profile_scope!("draw");
         if self.meta.exited {
             return;
         }
 
-        self.ctx.begin_default_pass(Default::default());
+        // nothing to draw into while minimized
+        let (width, height) = self.meta.screen_size;
+        if width < 1.0 || height < 1.0 {
+            return;
+        }
+
+        self.scene_target
+            .ensure_size(&mut self.ctx, width as u32, height as u32);
 
-        unsafe {
-            gl::glEnable(GL_DEPTH_BUFFER_BIT);
+        // moving things are drawn between the last two simulation steps
+        let cam = self.render_camera();
+
+        self.ctx
+            .begin_pass(Some(self.scene_target.pass()), PassAction::Nothing);
+
+        // reversed depth has the far end at 0.0
+        let reversed_z = cam.reversed_z();
+        let depth_test = if reversed_z {
+            Comparison::Greater
+        } else {
+            Comparison::Less
+        };
+        if depth_test != self.scene_depth_test {
+            self.scene_depth_test = depth_test;
+            self.rebuild_scene_pipelines();
         }
 
-        self.ctx.clear(Some((0.0, 0.0, 0.0, 0.0)), Some(1.0), None);
+        let clear_depth = if reversed_z { 0.0 } else { 1.0 };
+        self.ctx
+            .clear(Some((0.0, 0.0, 0.0, 0.0)), Some(clear_depth), None);
+
+        let environment_texture = self.world.environment.get_texture(&mut self.ctx);
 
-        unsafe {
-            // toggle the wireframe rendering by changing the gl polygon format
-            if self.settings.render_wireframe {
-                raw_gl::glPolygonMode(GL_FRONT_AND_BACK, GL_LINE);
+        // wireframes draw the triangle edges as a line list instead of the triangles
+        let wireframe = self.settings.render_wireframe;
+        if wireframe {
+            self.ctx.apply_pipeline(&self.wireframe_pipeline);
+        } else {
+            self.ctx.apply_pipeline(&self.pipeline);
+        }
+
+        // objects entirely outside of the view are skipped before anything is uploaded for them
+        let frustum = Frustum::from_matrix(cam.projection_matrix() * cam.get_view_matrix());
+        let mut stats = RenderStats::default();
+
+        for object in self.renderable_objects.iter_mut() {
+            profile_scope!("draw object");
+            let models = object.get_instance_transforms();
+
+            let local_bounds = object.get_local_bounds();
+            let total = models.len();
+            let visible: Vec<glam::Mat4> = models
+                .into_iter()
+                .filter(|model| {
+                    let bounds = local_bounds.transformed(*model);
+                    let inside = frustum.intersects(&bounds);
+
+                    if self.settings.show_bounds {
+                        draw_bounds(&bounds, inside);
+                        if inside {
+                            self.text.draw_world(
+                                &object.get_material().name,
+                                bounds_top(&bounds),
+                                &TextStyle {
+                                    scale: 0.015,
+                                    ..Default::default()
+                                },
+                            );
+                        }
+                        if let BoundingVolume::Aabb(aabb) = &local_bounds {
+                            // the box itself, rotated with the model unlike its world aabb
+                            let local_box = glam::Mat4::from_scale_rotation_translation(
+                                aabb.max - aabb.min,
+                                glam::Quat::IDENTITY,
+                                (aabb.min + aabb.max) / 2.0,
+                            );
+                            debug_draw::wire_box(*model * local_box, debug_draw::BLUE, 0.0);
+                        }
+                    }
+
+                    inside
+                })
+                .collect();
+
+            stats.drawn += visible.len();
+            stats.culled += total - visible.len();
+
+            if visible.is_empty() {
+                continue;
+            }
+
+            let (mut bindings, element_count) = if wireframe {
+                let bindings = object.get_wireframe_bindings(&mut self.ctx);
+                (bindings, object.get_wireframe_element_count())
             } else {
-                raw_gl::glPolygonMode(GL_FRONT_AND_BACK, GL_FILL);
+                let bindings = object.get_bindings(&mut self.ctx);
+                (bindings, object.get_element_count())
+            };
+            bindings.images.push(environment_texture);
+
+            self.ctx.apply_bindings(&bindings);
+
+            let material = object.get_material();
+
+            for model in visible {
+                self.ctx
+                    .apply_uniforms(UniformsSource::table(&shader::Uniforms {
+                        model,
+                        view: cam.get_view_matrix(),
+                        projection: cam.projection_matrix(),
+                        camera_pos: cam.camera_pos.extend(1.0),
+                        light_direction: self.world.light.direction_uniform(),
+                        light_color: self.world.light.color_uniform(),
+                        base_color_factor: material.base_color_factor,
+                        emissive_factor: material.emissive_factor.extend(0.0),
+                        material_params: material.params(),
+                        environment_params: self.world.environment.params(),
+                        time: self.time.uniform(),
+                    }));
+
+                self.ctx.draw(0, element_count, 1);
             }
         }
 
-        let bindings = self
-            .renderable_objects
-            .get_mut(0)
-            .unwrap()
-            .get_bindings(&mut self.ctx);
-
-        self.ctx.apply_pipeline(&self.pipeline);
-        self.ctx.apply_bindings(&bindings);
-
-        #[rustfmt::skip]
-        let cube_pos: [glam::Vec3; 10] = [
-            glam::Vec3 { x:  0.0, y:  0.0,z:  0.0 },
-            glam::Vec3 { x:  2.0, y:  5.0,z: -15.0 },
-            glam::Vec3 { x: -1.5, y: -2.2,z: -2.5 },
-            glam::Vec3 { x: -3.8, y: -2.0,z: -12.3 },
-            glam::Vec3 { x:  2.4, y: -0.4,z: -3.5 },
-            glam::Vec3 { x: -1.7, y:  3.0,z: -7.5 },
-            glam::Vec3 { x:  1.3, y: -2.0,z: -2.5 },
-            glam::Vec3 { x:  1.5, y:  2.0,z: -2.5 },
-            glam::Vec3 { x:  1.5, y:  0.2,z: -1.5 },
-            glam::Vec3 { x: -1.3, y:  1.0,z: -1.5 },
-        ];
-
-        for pos in cube_pos.iter() {
-            self.ctx
-                .apply_uniforms(UniformsSource::table(&shader::Uniforms {
-                    model: glam::Mat4::from_translation(*pos),
-                    view: self.world.cam.get_view_matrix(),
-                    projection: self.world.cam.get_perspective_matrix(),
-                }));
-
-            unsafe {
-                gl::glDrawArrays(GL_TRIANGLES, 0, 36);
-            }
+        if let Some(hit) = &self.selection {
+            draw_selection(hit);
+            self.text.draw_world(
+                &self.renderable_objects[hit.object].get_material().name,
+                bounds_top(&hit.bounds),
+                &TextStyle {
+                    color: debug_draw::YELLOW,
+                    scale: 0.015,
+                    ..Default::default()
+                },
+            );
         }
 
+        self.meta.render_stats = stats;
+
         self.ctx.end_render_pass();
 
+        let (width, height) = self.scene_target.size();
+
+        // a captured frame is finished offscreen, read back and then shown
+        let output = self.capture.output(&mut self.ctx, width, height);
+
+        self.post.run(
+            &mut self.ctx,
+            self.scene_target.color_texture(),
+            output,
+            width,
+            height,
+        );
+
+        if self.settings.show_reference_lines {
+            draw_reference_lines(&self.world.light);
+        }
+
+        self.debug_draw.draw(
+            &mut self.ctx,
+            cam.projection_matrix() * cam.get_view_matrix(),
+            output,
+        );
+
+        // recorded sequences are meant for videos, the hud only makes it into screenshots
+        if self.capture.fixed_delta().is_none() {
+            self.queue_hud();
+        }
+        self.text.draw(
+            &mut self.ctx,
+            cam.projection_matrix() * cam.get_view_matrix(),
+            cam.get_view_matrix(),
+            self.meta.logical_screen_size(),
+            output,
+        );
+
+        if output.is_some() {
+            let frame = self.capture.finish_frame(&mut self.ctx);
+            self.post.present(&mut self.ctx, frame, width, height);
+        }
+
+        self.debug_ui.draw(
+            &mut self.ctx,
+            DebugUiState {
+                settings: &mut self.settings,
+                world: &mut self.world,
+                post: &mut self.post,
+                camera_path: &mut self.camera_path,
+                actions: &mut self.actions,
+                time: &mut self.time,
+                config: &mut self.config,
+                console: &mut self.console,
+                meta: &self.meta,
+            },
+        );
+
+        profile_scope!("commit frame");
         self.ctx.commit_frame();
     }
 }
 
+/// world space bounds of an object, green when it is drawn, red when it is culled
+fn draw_bounds(bounds: &BoundingVolume, visible: bool) {
+    let color = if visible {
+        debug_draw::GREEN
+    } else {
+        debug_draw::RED
+    };
+
+    match bounds {
+        BoundingVolume::Aabb(aabb) => debug_draw::wire_aabb(aabb, color, 0.0),
+        BoundingVolume::Sphere(sphere) => {
+            debug_draw::wire_sphere(sphere.center, sphere.radius, color, 0.0)
+        }
+    }
+}
+
+/// outline of the selected instance and the surface normal where it was clicked
+fn draw_selection(hit: &picking::PickHit) {
+    match &hit.bounds {
+        BoundingVolume::Aabb(aabb) => debug_draw::wire_aabb(aabb, debug_draw::YELLOW, 0.0),
+        BoundingVolume::Sphere(sphere) => {
+            debug_draw::wire_sphere(sphere.center, sphere.radius, debug_draw::YELLOW, 0.0)
+        }
+    }
+
+    debug_draw::arrow(
+        hit.point,
+        hit.point + hit.normal * 0.5,
+        debug_draw::YELLOW,
+        0.0,
+    );
+}
+
+/// point just above the bounds, where labels go
+fn bounds_top(bounds: &BoundingVolume) -> glam::Vec3 {
+    match bounds {
+        BoundingVolume::Aabb(aabb) => {
+            glam::vec3(aabb.center().x, aabb.max.y + 0.1, aabb.center().z)
+        }
+        BoundingVolume::Sphere(sphere) => sphere.center + glam::Vec3::Y * (sphere.radius + 0.1),
+    }
+}
+
+/// world axes, a grid on the ground plane and the direction of the sun
+fn draw_reference_lines(light: &DirectionalLight) {
+    debug_draw::axes(glam::Mat4::IDENTITY, 1.0, 0.0);
+
+    let grid_color = glam::vec4(0.5, 0.5, 0.5, 1.0);
+    for i in -10..=10 {
+        let offset = i as f32 * 2.0;
+        debug_draw::line(
+            glam::vec3(offset, -4.0, -20.0),
+            glam::vec3(offset, -4.0, 20.0),
+            grid_color,
+            0.0,
+        );
+        debug_draw::line(
+            glam::vec3(-20.0, -4.0, offset),
+            glam::vec3(20.0, -4.0, offset),
+            grid_color,
+            0.0,
+        );
+    }
+
+    let sun = glam::vec3(0.0, 4.0, 0.0);
+    debug_draw::arrow(
+        sun,
+        sun + light.direction.normalize() * 2.0,
+        debug_draw::YELLOW,
+        0.0,
+    );
+}
+
+/// command line options, everything is optional
+/// `[model] [--headless [frames]] [--fullscreen] [--window-size WIDTHxHEIGHT]`
+struct Options {
+    /// .obj, .gltf or .glb file shown next to the test scene, e.g. `models/helmet.gltf`
+    model_path: Option<String>,
+    /// draw this many frames without a window and print the gpu calls
+    headless_frames: Option<usize>,
+    fullscreen: bool,
+    /// input recording to play back from the first frame instead of live input
+    replay_path: Option<String>,
+    /// in logical pixels, also the size of a headless frame. Overrides the config
+    window_size: Option<(i32, i32)>,
+}
+
+impl Options {
+    fn parse(args: impl Iterator<Item = String>) -> Self {
+        let mut options = Options {
+            model_path: None,
+            headless_frames: None,
+            fullscreen: false,
+            replay_path: None,
+            window_size: None,
+        };
+
+        let mut args = args.peekable();
+        while let Some(arg) = args.next() {
+            match arg.as_str() {
+                "--headless" => {
+                    let frames = args.next_if(|a| a.parse::<usize>().is_ok());
+                    options.headless_frames = Some(frames.map_or(1, |f| f.parse().unwrap()));
+                }
+                "--fullscreen" => options.fullscreen = true,
+                "--replay" => match args.next() {
+                    Some(path) => options.replay_path = Some(path),
+                    None => log::error!("--replay expects a recording, e.g. input_recording.txt"),
+                },
+                "--window-size" => {
+                    let size = args.next().and_then(|size| {
+                        let (width, height) = size.split_once('x')?;
+                        Some((width.parse().ok()?, height.parse().ok()?))
+                    });
+
+                    match size {
+                        Some((width, height)) if width > 0 && height > 0 => {
+                            options.window_size = Some((width, height))
+                        }
+                        _ => log::error!("--window-size expects WIDTHxHEIGHT, e.g. 1280x720"),
+                    }
+                }
+                _ => options.model_path = Some(arg),
+            }
+        }
+
+        return options;
+    }
+}
+
 fn main() {
     println!("Hello world");
+    console::init_logging();
+
+    let options = Options::parse(std::env::args().skip(1));
+
+    let replay = options.replay_path.as_ref().and_then(|path| {
+        InputRecording::load(path.as_ref())
+            .inspect_err(|e| log::error!("{}, not replaying", e))
+            .ok()
+    });
+
+    // `cargo run -- --headless 3 [model]` draws 3 frames without a window and prints the gpu calls
+    if let Some(frames) = options.headless_frames {
+        // headless runs neither watch the config nor write changes back to it
+        let config = ConfigFile::load(config::DEFAULT_CONFIG_FILE.into(), false);
+        let (width, height) = options
+            .window_size
+            .unwrap_or((config.config.window.width, config.config.window.height));
+        headless::run(
+            frames,
+            options.model_path,
+            (width as f32, height as f32),
+            replay,
+            config,
+        );
+        return;
+    }
 
-    let mut conf = conf::Conf::default();
-    conf.platform.apple_gfx_api = conf::AppleGfxApi::OpenGl;
-    conf.window_height = 600;
-    conf.window_width = 600;
-
-    miniquad::start(conf, move || Box::new(Stage::new()));
+    let config = ConfigFile::load(config::DEFAULT_CONFIG_FILE.into(), true);
+    let window = &config.config.window;
+    let (width, height) = options.window_size.unwrap_or((window.width, window.height));
+    let fullscreen = options.fullscreen || window.fullscreen;
+
+    let mut conf = conf::Conf {
+        window_title: "rustic".to_owned(),
+        window_width: width,
+        window_height: height,
+        window_resizable: true,
+        fullscreen,
+        high_dpi: window.high_dpi,
+        ..Default::default()
+    };
+    conf.platform.apple_gfx_api = window.apple_gfx_api;
+
+    miniquad::start(conf, move || {
+        let mut stage = Stage::new(options.model_path, fullscreen, config);
+        if let Some(recording) = replay {
+            stage.start_replay(recording);
+        }
+        Box::new(stage)
+    });
 
-    println!("exiting miniquad");
+    log::info!("exiting miniquad");
 }
 
 mod shader {
     use miniquad::*;
     pub fn meta() -> ShaderMeta {
         ShaderMeta {
-            images: vec!["tex".to_string()],
+            // material slots first, then the environment
+            images: vec![
+                "base_color_tex".to_string(),
+                "metallic_roughness_tex".to_string(),
+                "normal_tex".to_string(),
+                "occlusion_tex".to_string(),
+                "emissive_tex".to_string(),
+                "environment_tex".to_string(),
+            ],
             uniforms: UniformBlockLayout {
                 uniforms: vec![
                     UniformDesc::new("model", UniformType::Mat4),
                     UniformDesc::new("view", UniformType::Mat4),
                     UniformDesc::new("projection", UniformType::Mat4),
+                    UniformDesc::new("camera_pos", UniformType::Float4),
+                    UniformDesc::new("light_direction", UniformType::Float4),
+                    UniformDesc::new("light_color", UniformType::Float4),
+                    UniformDesc::new("base_color_factor", UniformType::Float4),
+                    UniformDesc::new("emissive_factor", UniformType::Float4),
+                    UniformDesc::new("material_params", UniformType::Float4),
+                    UniformDesc::new("environment_params", UniformType::Float4),
+                    UniformDesc::new("time", UniformType::Float4),
                 ],
             },
         }
     }
+    // only mat4 and vec4 so the rust layout matches the tightly packed uniform layout
     #[repr(C)]
     pub struct Uniforms {
         pub model: glam::f32::Mat4,
         pub view: glam::f32::Mat4,
         pub projection: glam::f32::Mat4,
+        pub camera_pos: glam::Vec4,
+        pub light_direction: glam::Vec4,
+        pub light_color: glam::Vec4,
+        pub base_color_factor: glam::Vec4,
+        pub emissive_factor: glam::Vec4,
+        pub material_params: glam::Vec4,
+        pub environment_params: glam::Vec4,
+        pub time: glam::Vec4,
     }
 }
@@ -0,0 +1,151 @@
+use miniquad::{
+    FilterMode, MipmapFilterMode, RenderingBackend, TextureAccess, TextureFormat, TextureId,
+    TextureKind, TextureParams, TextureSource, TextureWrap,
+};
+
+use crate::{profiler::profile_scope, textures};
+
+type BackendArg = Box<dyn RenderingBackend>;
+
+/// face order opengl expects for cubemaps: +x, -x, +y, -y, +z, -z
+const CUBEMAP_FACES: [&str; 6] = ["px", "nx", "py", "ny", "pz", "nz"];
+
+/// Surrounding of the scene used for image based lighting.
+/// Without a cubemap a 1x1 cube of `ambient_color` is used, which boils down to a constant ambient
+/// term
+pub struct Environment {
+    /// folder inside of ./sprites holding px.png, nx.png, py.png, ny.png, pz.png and nz.png
+    pub cubemap: Option<String>,
+    pub ambient_color: [u8; 4],
+
+    /// disabled ibl only keeps a very dim constant ambient term in the shader
+    pub enabled: bool,
+    pub intensity: f32,
+
+    /// opengl vars
+    texture_id: Option<TextureId>,
+    mip_levels: u32,
+}
+
+impl Environment {
+    pub fn new(cubemap: Option<String>) -> Self {
+        Self {
+            cubemap,
+            ambient_color: [80, 80, 90, 255],
+            enabled: true,
+            intensity: 1.0,
+            texture_id: None,
+            mip_levels: 1,
+        }
+    }
+
+    /// the faces of `cubemap` that are not there, the ambient color is used instead unless this is empty
+    pub fn missing_faces(cubemap: &str) -> Vec<std::path::PathBuf> {
+        CUBEMAP_FACES
+            .iter()
+            .map(|face| textures::sprite_path(&format!("{}/{}.png", cubemap, face)))
+            .filter(|path| !path.is_file())
+            .collect()
+    }
+
+    pub fn get_texture(&mut self, ctx: &mut BackendArg) -> TextureId {
+        if self.texture_id.is_none() {
+            let cubemap = self.cubemap.clone().and_then(|folder| {
+                self.upload_cubemap(ctx, &folder)
+                    .inspect_err(|e| log::error!("{}, using the ambient color instead", e))
+                    .ok()
+            });
+
+            self.texture_id = Some(match cubemap {
+                Some(id) => id,
+                None => {
+                    self.mip_levels = 1;
+                    let face: &[u8] = &self.ambient_color;
+                    new_cubemap(ctx, 1, [&[face]; 6], false)
+                }
+            });
+        }
+
+        self.texture_id.unwrap()
+    }
+
+    fn upload_cubemap(&mut self, ctx: &mut BackendArg, folder: &str) -> Result<TextureId, String> {
+        profile_scope!("load environment");
+        let faces = CUBEMAP_FACES
+            .iter()
+            .map(|face| textures::Texture::load(format!("{}/{}.png", folder, face), false))
+            .collect::<Result<Vec<_>, _>>()?;
+
+        let size = faces[0].width;
+        if faces.iter().any(|f| f.width != size || f.height != size) {
+            return Err(format!(
+                "cubemap {} faces must all be square and of the same size",
+                folder
+            ));
+        }
+
+        let bytes = [
+            &[faces[0].rgba8_bytes()][..],
+            &[faces[1].rgba8_bytes()][..],
+            &[faces[2].rgba8_bytes()][..],
+            &[faces[3].rgba8_bytes()][..],
+            &[faces[4].rgba8_bytes()][..],
+            &[faces[5].rgba8_bytes()][..],
+        ];
+
+        // rougher surfaces sample lower mips so the whole chain is needed
+        self.mip_levels = (size as u32).ilog2() + 1;
+
+        let id = new_cubemap(ctx, size as u32, bytes, true);
+        ctx.texture_generate_mipmaps(id);
+        return Ok(id);
+    }
+
+    /// enabled, intensity and the number of mip levels packed the way the shader reads it
+    pub fn params(&self) -> glam::Vec4 {
+        glam::vec4(
+            if self.enabled { 1.0 } else { 0.0 },
+            self.intensity,
+            self.mip_levels as f32,
+            0.0,
+        )
+    }
+
+    /// deallocate any resources that are allocated on opengl
+    pub fn drop_gl_resources(&mut self, ctx: &mut BackendArg) {
+        if let Some(val) = self.texture_id.take() {
+            ctx.delete_texture(val);
+        }
+    }
+}
+
+fn new_cubemap(ctx: &mut BackendArg, size: u32, faces: [&[&[u8]]; 6], mipmaps: bool) -> TextureId {
+    ctx.new_texture(
+        TextureAccess::Static,
+        TextureSource::Array(&faces),
+        TextureParams {
+            kind: TextureKind::CubeMap,
+            format: TextureFormat::RGBA8,
+            wrap: TextureWrap::Clamp,
+            min_filter: FilterMode::Linear,
+            mag_filter: FilterMode::Linear,
+            mipmap_filter: if mipmaps {
+                MipmapFilterMode::Linear
+            } else {
+                MipmapFilterMode::None
+            },
+            width: size,
+            height: size,
+            allocate_mipmaps: mipmaps,
+            sample_count: 1,
+        },
+    )
+}
+
+impl Drop for Environment {
+    fn drop(&mut self) {
+        if self.texture_id.is_some() {
+            panic!("Environment was not cleared properly");
+        }
+    }
+}
@@ -0,0 +1,188 @@
+mod environment;
+pub use environment::Environment;
+
+use std::path::Path;
+
+use miniquad::{FilterMode, MipmapFilterMode, RenderingBackend, TextureId};
+
+use crate::{profiler::profile_scope, textures};
+
+type BackendArg = Box<dyn RenderingBackend>;
+
+/// number of texture slots a material binds, in the order the pbr shader expects them
+pub const MATERIAL_TEXTURE_SLOTS: usize = 5;
+
+/// Metallic-roughness material, the fields follow the glTF 2.0 material model so glTF data can be
+/// taken over as is. Every texture is optional, a missing one is replaced by a 1x1 texture that
+/// leaves the factors unchanged (white or a flat normal)
+pub struct Material {
+    pub name: String,
+
+    pub base_color_factor: glam::Vec4,
+    pub metallic_factor: f32,
+    pub roughness_factor: f32,
+    pub emissive_factor: glam::Vec3,
+    pub normal_scale: f32,
+    pub occlusion_strength: f32,
+
+    /// paths relative to ./sprites, absolute paths are used as is
+    pub base_color_texture: Option<String>,
+    /// roughness is read from the green, metallic from the blue channel
+    pub metallic_roughness_texture: Option<String>,
+    /// tangent space normal map
+    pub normal_texture: Option<String>,
+    /// ambient occlusion is read from the red channel
+    pub occlusion_texture: Option<String>,
+    pub emissive_texture: Option<String>,
+    /// how every texture is sampled, Nearest keeps the texels of small textures sharp
+    pub filter: FilterMode,
+
+    /// opengl vars
+    texture_ids: Option<[TextureId; MATERIAL_TEXTURE_SLOTS]>,
+}
+
+impl Material {
+    /// a plain white dielectric, change the fields to taste before the first use
+    pub fn new(name: String) -> Self {
+        Self {
+            name,
+            base_color_factor: glam::Vec4::ONE,
+            metallic_factor: 0.0,
+            roughness_factor: 0.5,
+            emissive_factor: glam::Vec3::ZERO,
+            normal_scale: 1.0,
+            occlusion_strength: 1.0,
+
+            base_color_texture: None,
+            metallic_roughness_texture: None,
+            normal_texture: None,
+            occlusion_texture: None,
+            emissive_texture: None,
+            filter: FilterMode::Linear,
+
+            texture_ids: None,
+        }
+    }
+
+    /// Take over the material description of a glTF file.
+    /// Only textures that reference an external image are supported, the uri is resolved relative
+    /// to `folder`; embedded images are ignored and fall back to the default texture
+    pub fn from_gltf(material: &gltf::Material, folder: &Path) -> Self {
+        let texture_uri = |texture: gltf::Texture| -> Option<String> {
+            match texture.source().source() {
+                gltf::image::Source::Uri { uri, .. } => {
+                    Some(folder.join(uri).to_string_lossy().into_owned())
+                }
+                gltf::image::Source::View { .. } => {
+                    log::warn!("embedded gltf images are not supported, using default texture");
+                    None
+                }
+            }
+        };
+
+        let pbr = material.pbr_metallic_roughness();
+
+        Self {
+            name: material.name().unwrap_or("unnamed").to_owned(),
+            base_color_factor: glam::Vec4::from_array(pbr.base_color_factor()),
+            metallic_factor: pbr.metallic_factor(),
+            roughness_factor: pbr.roughness_factor(),
+            emissive_factor: glam::Vec3::from_array(material.emissive_factor()),
+            normal_scale: material.normal_texture().map_or(1.0, |t| t.scale()),
+            occlusion_strength: material.occlusion_texture().map_or(1.0, |t| t.strength()),
+
+            base_color_texture: pbr
+                .base_color_texture()
+                .and_then(|t| texture_uri(t.texture())),
+            metallic_roughness_texture: pbr
+                .metallic_roughness_texture()
+                .and_then(|t| texture_uri(t.texture())),
+            normal_texture: material
+                .normal_texture()
+                .and_then(|t| texture_uri(t.texture())),
+            occlusion_texture: material
+                .occlusion_texture()
+                .and_then(|t| texture_uri(t.texture())),
+            emissive_texture: material
+                .emissive_texture()
+                .and_then(|t| texture_uri(t.texture())),
+            filter: FilterMode::Linear,
+
+            texture_ids: None,
+        }
+    }
+
+    /// Get the textures of all material slots, uploading them if that did not happen yet
+    pub fn get_images(&mut self, ctx: &mut BackendArg) -> [TextureId; MATERIAL_TEXTURE_SLOTS] {
+        if self.texture_ids.is_none() {
+            profile_scope!("upload textures");
+            let mut upload = |file: &Option<String>, fallback| {
+                upload_or_default(ctx, file, fallback, self.filter)
+            };
+            self.texture_ids = Some([
+                upload(&self.base_color_texture, [255, 255, 255, 255]),
+                upload(&self.metallic_roughness_texture, [255, 255, 255, 255]),
+                upload(&self.normal_texture, [128, 128, 255, 255]),
+                upload(&self.occlusion_texture, [255, 255, 255, 255]),
+                upload(&self.emissive_texture, [255, 255, 255, 255]),
+            ]);
+        }
+
+        self.texture_ids.unwrap()
+    }
+
+    /// metallic, roughness, occlusion strength and normal scale packed the way the shader reads it
+    pub fn params(&self) -> glam::Vec4 {
+        glam::vec4(
+            self.metallic_factor,
+            self.roughness_factor,
+            self.occlusion_strength,
+            self.normal_scale,
+        )
+    }
+
+    /// deallocate any resources that are allocated on opengl
+    pub fn drop_gl_resources(&mut self, ctx: &mut BackendArg) {
+        if let Some(ids) = self.texture_ids.take() {
+            for id in ids {
+                ctx.delete_texture(id);
+            }
+        }
+    }
+}
+
+/// Upload the texture if a file name is given, else create a 1x1 texture of the fallback color.
+/// A texture that can not be loaded is reported and gets the fallback as well
+fn upload_or_default(
+    ctx: &mut BackendArg,
+    file: &Option<String>,
+    fallback: [u8; 4],
+    filter: FilterMode,
+) -> TextureId {
+    let texture = file.as_ref().and_then(|file| {
+        textures::Texture::new(file.clone())
+            .inspect_err(|e| log::error!("{}, using the default texture", e))
+            .ok()
+    });
+    let Some(texture) = texture else {
+        return ctx.new_texture_from_rgba8(1, 1, &fallback);
+    };
+
+    let id = ctx.new_texture_from_rgba8(
+        texture.width as u16,
+        texture.height as u16,
+        texture.rgba8_bytes(),
+    );
+
+    ctx.texture_set_filter(id, filter, MipmapFilterMode::None);
+
+    return id;
+}
+
+impl Drop for Material {
+    fn drop(&mut self) {
+        if self.texture_ids.is_some() {
+            panic!("Material {} was not cleared properly", self.name);
+        }
+    }
+}
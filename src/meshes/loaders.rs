@@ -0,0 +1,218 @@
+use std::path::Path;
+
+use super::Mesh;
+use crate::{
+    materials::Material,
+    objects::{DataVertex3DTexture, MeshObject},
+    profiler::profile_scope,
+};
+
+/// Load every mesh of an .obj, .gltf or .glb file together with its material.
+/// Tangents stored in the file are used as is, missing ones are generated
+pub fn load_model(path: &str) -> Result<Vec<MeshObject>, String> {
+    profile_scope!("load model");
+    match extension(path).as_deref() {
+        Some("obj") => load_obj(path),
+        Some("gltf") | Some("glb") => load_gltf(path),
+        _ => Err(format!("unknown model format {}", path)),
+    }
+}
+
+/// whether `path` names a file load_model can read, going by its extension
+pub fn is_model(path: &str) -> bool {
+    return matches!(extension(path).as_deref(), Some("obj" | "gltf" | "glb"));
+}
+
+fn extension(path: &str) -> Option<String> {
+    return Path::new(path)
+        .extension()
+        .and_then(|e| e.to_str())
+        .map(|e| e.to_lowercase());
+}
+
+/// folder of the model file as absolute path, textures are referenced relative to it
+fn model_folder(path: &str) -> Result<std::path::PathBuf, String> {
+    let absolute = std::fs::canonicalize(path).map_err(|e| format!("{}: {}", path, e))?;
+
+    return Ok(absolute.parent().unwrap().to_path_buf());
+}
+
+fn load_obj(path: &str) -> Result<Vec<MeshObject>, String> {
+    let folder = model_folder(path)?;
+
+    let (models, materials) = tobj::load_obj(path, &tobj::GPU_LOAD_OPTIONS)
+        .map_err(|e| format!("could not load {}: {}", path, e))?;
+
+    let materials = materials.unwrap_or_else(|e| {
+        log::warn!("could not load materials of {}: {}", path, e);
+        vec![]
+    });
+
+    let mut objects = vec![];
+
+    for model in models {
+        let obj_mesh = &model.mesh;
+        let vertex_count = obj_mesh.positions.len() / 3;
+
+        let vertices = (0..vertex_count)
+            .map(|i| DataVertex3DTexture {
+                pos: glam::Vec3::from_slice(&obj_mesh.positions[i * 3..]),
+                normal: if obj_mesh.normals.is_empty() {
+                    glam::Vec3::Y
+                } else {
+                    glam::Vec3::from_slice(&obj_mesh.normals[i * 3..])
+                },
+                uv: if obj_mesh.texcoords.is_empty() {
+                    glam::Vec2::ZERO
+                } else {
+                    glam::Vec2::from_slice(&obj_mesh.texcoords[i * 2..])
+                },
+                tangent: glam::Vec4::ZERO,
+            })
+            .collect();
+
+        // obj has no notion of tangents so they are always generated
+        let mut mesh = Mesh::new(model.name.clone(), vertices, obj_mesh.indices.clone());
+        mesh.generate_tangents();
+
+        let material = match obj_mesh.material_id.and_then(|id| materials.get(id)) {
+            Some(mtl) => material_from_mtl(mtl, &folder),
+            None => Material::new(model.name.clone()),
+        };
+
+        objects.push(MeshObject::new(mesh, material, glam::Mat4::IDENTITY));
+    }
+
+    return Ok(objects);
+}
+
+/// mtl is a phong description, the values are mapped to the closest metallic-roughness ones
+fn material_from_mtl(mtl: &tobj::Material, folder: &Path) -> Material {
+    let mut material = Material::new(mtl.name.clone());
+
+    if let Some(diffuse) = mtl.diffuse {
+        material.base_color_factor =
+            glam::Vec3::from_array(diffuse).extend(mtl.dissolve.unwrap_or(1.0));
+    }
+
+    if let Some(shininess) = mtl.shininess {
+        material.roughness_factor = (2.0 / (shininess + 2.0)).sqrt();
+    }
+
+    let texture_path = |file: &Option<String>| {
+        file.as_ref()
+            .map(|f| folder.join(f).to_string_lossy().into_owned())
+    };
+
+    material.base_color_texture = texture_path(&mtl.diffuse_texture);
+    material.normal_texture = texture_path(&mtl.normal_texture);
+
+    return material;
+}
+
+fn load_gltf(path: &str) -> Result<Vec<MeshObject>, String> {
+    let folder = model_folder(path)?;
+
+    let gltf = gltf::Gltf::open(path).map_err(|e| format!("could not load {}: {}", path, e))?;
+
+    // only the binary chunk of .glb files and external .bin files are supported
+    let mut buffers = vec![];
+    for buffer in gltf.buffers() {
+        buffers.push(match buffer.source() {
+            gltf::buffer::Source::Bin => gltf
+                .blob
+                .clone()
+                .ok_or(format!("{} references a missing binary chunk", path))?,
+            gltf::buffer::Source::Uri(uri) if uri.starts_with("data:") => {
+                return Err(format!("{}: embedded data uris are not supported", path));
+            }
+            gltf::buffer::Source::Uri(uri) => std::fs::read(folder.join(uri))
+                .map_err(|e| format!("could not read buffer {}: {}", uri, e))?,
+        });
+    }
+
+    let mut objects = vec![];
+
+    let Some(scene) = gltf.default_scene().or_else(|| gltf.scenes().next()) else {
+        return Ok(objects);
+    };
+
+    // flatten the node hierarchy, every primitive becomes an object with its world transform
+    let mut nodes: Vec<(gltf::Node, glam::Mat4)> =
+        scene.nodes().map(|n| (n, glam::Mat4::IDENTITY)).collect();
+
+    while let Some((node, parent_transform)) = nodes.pop() {
+        let transform =
+            parent_transform * glam::Mat4::from_cols_array_2d(&node.transform().matrix());
+
+        nodes.extend(node.children().map(|child| (child, transform)));
+
+        let Some(gltf_mesh) = node.mesh() else {
+            continue;
+        };
+
+        for primitive in gltf_mesh.primitives() {
+            if primitive.mode() != gltf::mesh::Mode::Triangles {
+                log::warn!("skipping non triangle primitive in {}", path);
+                continue;
+            }
+
+            let reader = primitive.reader(|buffer| Some(&buffers[buffer.index()]));
+
+            let Some(positions) = reader.read_positions() else {
+                continue;
+            };
+
+            let mut vertices: Vec<DataVertex3DTexture> = positions
+                .map(|pos| DataVertex3DTexture {
+                    pos: glam::Vec3::from_array(pos),
+                    normal: glam::Vec3::Y,
+                    uv: glam::Vec2::ZERO,
+                    tangent: glam::Vec4::ZERO,
+                })
+                .collect();
+
+            if let Some(normals) = reader.read_normals() {
+                for (vertex, normal) in vertices.iter_mut().zip(normals) {
+                    vertex.normal = glam::Vec3::from_array(normal);
+                }
+            }
+
+            if let Some(uvs) = reader.read_tex_coords(0) {
+                for (vertex, uv) in vertices.iter_mut().zip(uvs.into_f32()) {
+                    // gltf uvs start top left, the textures are flipped on load
+                    vertex.uv = glam::vec2(uv[0], 1.0 - uv[1]);
+                }
+            }
+
+            let indices = match reader.read_indices() {
+                Some(indices) => indices.into_u32().collect(),
+                None => (0..vertices.len() as u32).collect(),
+            };
+
+            let name = gltf_mesh.name().unwrap_or("unnamed").to_owned();
+
+            let mesh = match reader.read_tangents() {
+                Some(tangents) => {
+                    for (vertex, tangent) in vertices.iter_mut().zip(tangents) {
+                        // the uv v flip above mirrors the bitangent
+                        vertex.tangent =
+                            glam::Vec4::from_array(tangent) * glam::vec4(1.0, 1.0, 1.0, -1.0);
+                    }
+                    Mesh::new(name, vertices, indices)
+                }
+                None => {
+                    let mut mesh = Mesh::new(name, vertices, indices);
+                    mesh.generate_tangents();
+                    mesh
+                }
+            };
+
+            let material = Material::from_gltf(&primitive.material(), &folder);
+
+            objects.push(MeshObject::new(mesh, material, transform));
+        }
+    }
+
+    return Ok(objects);
+}
@@ -0,0 +1,116 @@
+mod loaders;
+mod primitives;
+
+pub use loaders::{is_model, load_model};
+
+use std::collections::HashSet;
+
+use bevy_mikktspace::Geometry;
+
+use crate::{
+    bounds::{Aabb, BoundingVolume},
+    objects::DataVertex3DTexture,
+};
+
+/// Indexed triangle list in the vertex layout of the lit pipeline.
+/// Every constructor fills in tangents, either from the source file or generated
+pub struct Mesh {
+    pub name: String,
+
+    pub vertices: Vec<DataVertex3DTexture>,
+    pub indices: Vec<u32>,
+
+    /// in object space, a box around all vertices unless the constructor knows better
+    pub bounds: BoundingVolume,
+}
+
+impl Mesh {
+    pub fn new(name: String, vertices: Vec<DataVertex3DTexture>, indices: Vec<u32>) -> Self {
+        let bounds = BoundingVolume::Aabb(Aabb::from_points(vertices.iter().map(|v| v.pos)));
+
+        Self {
+            name,
+            vertices,
+            indices,
+            bounds,
+        }
+    }
+
+    /// Generate MikkTSpace tangents for all vertices, overwriting whatever was there before
+    pub fn generate_tangents(&mut self) {
+        if !generate_tangents(&mut self.vertices, &self.indices) {
+            log::warn!(
+                "could not generate tangents for mesh {}, normal mapping is disabled for it",
+                self.name
+            );
+        }
+    }
+}
+
+/// Line list indices for the edges of an indexed triangle list, used to draw wireframes without
+/// a polygon mode. Edges shared by two triangles through the same vertices are only listed once
+pub fn edge_indices(triangle_indices: &[u32]) -> Vec<u32> {
+    let mut seen = HashSet::new();
+    let mut lines = Vec::with_capacity(triangle_indices.len() * 2);
+
+    for triangle in triangle_indices.chunks_exact(3) {
+        let (a, b, c) = (triangle[0], triangle[1], triangle[2]);
+
+        for (from, to) in [(a, b), (b, c), (c, a)] {
+            if seen.insert((from.min(to), from.max(to))) {
+                lines.push(from);
+                lines.push(to);
+            }
+        }
+    }
+
+    return lines;
+}
+
+/// Generate MikkTSpace tangents for an indexed triangle list. Unindexed lists pass `0..len` as
+/// indices.
+/// MikkTSpace works per face corner, vertices shared between faces get the tangent of the last
+/// face that references them. For meshes that are split along uv and normal seams, like most
+/// exported ones, the tangents of the corners agree anyway.
+/// Returns false if the geometry is unusable, e.g. when it has no uvs
+pub fn generate_tangents(vertices: &mut [DataVertex3DTexture], indices: &[u32]) -> bool {
+    struct TangentGeometry<'a> {
+        vertices: &'a mut [DataVertex3DTexture],
+        indices: &'a [u32],
+    }
+
+    impl TangentGeometry<'_> {
+        fn vertex(&self, face: usize, vert: usize) -> &DataVertex3DTexture {
+            &self.vertices[self.indices[face * 3 + vert] as usize]
+        }
+    }
+
+    impl Geometry for TangentGeometry<'_> {
+        fn num_faces(&self) -> usize {
+            self.indices.len() / 3
+        }
+
+        fn num_vertices_of_face(&self, _face: usize) -> usize {
+            3
+        }
+
+        fn position(&self, face: usize, vert: usize) -> [f32; 3] {
+            self.vertex(face, vert).pos.to_array()
+        }
+
+        fn normal(&self, face: usize, vert: usize) -> [f32; 3] {
+            self.vertex(face, vert).normal.to_array()
+        }
+
+        fn tex_coord(&self, face: usize, vert: usize) -> [f32; 2] {
+            self.vertex(face, vert).uv.to_array()
+        }
+
+        fn set_tangent_encoded(&mut self, tangent: [f32; 4], face: usize, vert: usize) {
+            let index = self.indices[face * 3 + vert] as usize;
+            self.vertices[index].tangent = glam::Vec4::from_array(tangent);
+        }
+    }
+
+    bevy_mikktspace::generate_tangents(&mut TangentGeometry { vertices, indices })
+}
@@ -0,0 +1,119 @@
+use std::f32::consts::PI;
+
+use super::Mesh;
+use crate::{
+    bounds::{BoundingSphere, BoundingVolume},
+    objects::DataVertex3DTexture,
+};
+
+// procedurally generated meshes, all of them centered around the origin
+impl Mesh {
+    /// sphere made of `segments` slices around the y axis and `rings` stacks from pole to pole
+    pub fn uv_sphere(radius: f32, segments: u32, rings: u32) -> Self {
+        let mut vertices = Vec::with_capacity(((segments + 1) * (rings + 1)) as usize);
+        let mut indices = Vec::with_capacity((segments * rings * 6) as usize);
+
+        for ring in 0..=rings {
+            let v = ring as f32 / rings as f32;
+            let theta = v * PI;
+
+            for segment in 0..=segments {
+                let u = segment as f32 / segments as f32;
+                let phi = u * 2.0 * PI;
+
+                let normal = glam::vec3(
+                    theta.sin() * phi.cos(),
+                    theta.cos(),
+                    -theta.sin() * phi.sin(),
+                );
+
+                vertices.push(DataVertex3DTexture {
+                    pos: normal * radius,
+                    normal,
+                    uv: glam::vec2(u, 1.0 - v),
+                    tangent: glam::Vec4::ZERO,
+                });
+            }
+        }
+
+        // the seam and the poles have duplicated vertices so the uvs do not wrap around
+        let row = segments + 1;
+        for ring in 0..rings {
+            for segment in 0..segments {
+                let a = ring * row + segment;
+                let b = a + row;
+
+                indices.extend_from_slice(&[a, b, a + 1, a + 1, b, b + 1]);
+            }
+        }
+
+        let mut mesh = Mesh::new("uv sphere".to_owned(), vertices, indices);
+        mesh.bounds = BoundingVolume::Sphere(BoundingSphere {
+            center: glam::Vec3::ZERO,
+            radius,
+        });
+        mesh.generate_tangents();
+        return mesh;
+    }
+
+    /// flat square on the xz plane facing up, the uvs repeat `uv_scale` times along each side
+    pub fn plane(size: f32, uv_scale: f32) -> Self {
+        let half = size / 2.0;
+
+        #[rustfmt::skip]
+        let corners = [
+            (glam::vec3(-half, 0.0,  half), glam::vec2(0.0,      0.0)),
+            (glam::vec3( half, 0.0,  half), glam::vec2(uv_scale, 0.0)),
+            (glam::vec3( half, 0.0, -half), glam::vec2(uv_scale, uv_scale)),
+            (glam::vec3(-half, 0.0, -half), glam::vec2(0.0,      uv_scale)),
+        ];
+
+        let vertices = corners
+            .iter()
+            .map(|(pos, uv)| DataVertex3DTexture {
+                pos: *pos,
+                normal: glam::Vec3::Y,
+                uv: *uv,
+                tangent: glam::Vec4::ZERO,
+            })
+            .collect();
+
+        let mut mesh = Mesh::new("plane".to_owned(), vertices, vec![0, 1, 2, 2, 3, 0]);
+        mesh.generate_tangents();
+        return mesh;
+    }
+
+    /// box with sides of length `size`, every face has its own vertices and the full uv range
+    pub fn cube(size: f32) -> Self {
+        let half = size / 2.0;
+
+        // normal, then the right and up direction of the face seen from outside
+        let faces = [
+            (glam::Vec3::X, glam::Vec3::NEG_Z, glam::Vec3::Y),
+            (glam::Vec3::NEG_X, glam::Vec3::Z, glam::Vec3::Y),
+            (glam::Vec3::Y, glam::Vec3::X, glam::Vec3::NEG_Z),
+            (glam::Vec3::NEG_Y, glam::Vec3::X, glam::Vec3::Z),
+            (glam::Vec3::Z, glam::Vec3::X, glam::Vec3::Y),
+            (glam::Vec3::NEG_Z, glam::Vec3::NEG_X, glam::Vec3::Y),
+        ];
+
+        let mut vertices = Vec::with_capacity(24);
+        let mut indices = Vec::with_capacity(36);
+        for (normal, right, up) in faces {
+            let first = vertices.len() as u32;
+            for (x, y) in [(-1.0, -1.0), (1.0, -1.0), (1.0, 1.0), (-1.0, 1.0)] {
+                vertices.push(DataVertex3DTexture {
+                    pos: (normal + right * x + up * y) * half,
+                    normal,
+                    uv: glam::vec2((x + 1.0) / 2.0, (y + 1.0) / 2.0),
+                    tangent: glam::Vec4::ZERO,
+                });
+            }
+            indices.extend([0, 1, 2, 2, 3, 0].map(|i| first + i));
+        }
+
+        let mut mesh = Mesh::new("cube".to_owned(), vertices, indices);
+        mesh.generate_tangents();
+        return mesh;
+    }
+}
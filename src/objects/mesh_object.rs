@@ -0,0 +1,169 @@
+use std::panic;
+
+use miniquad::{Bindings, BufferId, BufferSource, BufferType, BufferUsage};
+
+use super::{BackendArg, RenderableObject};
+use crate::{
+    bounds::BoundingVolume,
+    materials::Material,
+    meshes::{self, Mesh},
+};
+
+/// A mesh placed in the world with a material, for loaded models and procedural primitives
+pub struct MeshObject {
+    pub mesh: Mesh,
+    pub material: Material,
+    pub transform: glam::Mat4,
+    /// transforms relative to `transform`, the object is drawn once for each of them. It is
+    /// drawn once at `transform` when there are none
+    pub instances: Vec<glam::Mat4>,
+    /// set when a script spawned the object, see RenderableObject::get_script_id
+    pub script_id: Option<i64>,
+    /// set when it was built from the scene file, see RenderableObject::get_scene_object
+    pub scene_object: Option<usize>,
+
+    /// opengl vars
+    vertex_buffer_id: Option<BufferId>,
+    index_buffer_id: Option<BufferId>,
+    wireframe_index_buffer_id: Option<BufferId>,
+    wireframe_element_count: i32,
+}
+
+impl MeshObject {
+    pub fn new(mesh: Mesh, material: Material, transform: glam::Mat4) -> Self {
+        Self {
+            mesh,
+            material,
+            transform,
+            instances: vec![],
+            script_id: None,
+            scene_object: None,
+            vertex_buffer_id: None,
+            index_buffer_id: None,
+            wireframe_index_buffer_id: None,
+            wireframe_element_count: 0,
+        }
+    }
+}
+
+impl RenderableObject for MeshObject {
+    fn get_bindings(self: &mut Self, ctx: &mut BackendArg) -> Bindings {
+        if self.vertex_buffer_id.is_none() {
+            self.vertex_buffer_id = Some(ctx.new_buffer(
+                BufferType::VertexBuffer,
+                BufferUsage::Immutable,
+                BufferSource::slice(&self.mesh.vertices),
+            ));
+        }
+
+        if self.index_buffer_id.is_none() {
+            self.index_buffer_id = Some(ctx.new_buffer(
+                BufferType::IndexBuffer,
+                BufferUsage::Immutable,
+                BufferSource::slice(&self.mesh.indices),
+            ));
+        }
+
+        return Bindings {
+            vertex_buffers: vec![self.vertex_buffer_id.unwrap()],
+            index_buffer: self.index_buffer_id.unwrap(),
+            images: self.material.get_images(ctx).to_vec(),
+        };
+    }
+
+    fn get_material(self: &Self) -> &Material {
+        &self.material
+    }
+
+    fn get_element_count(self: &Self) -> i32 {
+        self.mesh.indices.len() as i32
+    }
+
+    fn get_wireframe_bindings(self: &mut Self, ctx: &mut BackendArg) -> Bindings {
+        let mut bindings = self.get_bindings(ctx);
+
+        if self.wireframe_index_buffer_id.is_none() {
+            let indices = meshes::edge_indices(&self.mesh.indices);
+            self.wireframe_element_count = indices.len() as i32;
+            self.wireframe_index_buffer_id = Some(ctx.new_buffer(
+                BufferType::IndexBuffer,
+                BufferUsage::Immutable,
+                BufferSource::slice(&indices),
+            ));
+        }
+
+        bindings.index_buffer = self.wireframe_index_buffer_id.unwrap();
+        return bindings;
+    }
+
+    fn get_wireframe_element_count(self: &Self) -> i32 {
+        self.wireframe_element_count
+    }
+
+    fn get_model_matrix(self: &Self) -> glam::Mat4 {
+        self.transform
+    }
+
+    fn set_model_matrix(self: &mut Self, model: glam::Mat4) {
+        self.transform = model;
+    }
+
+    fn get_instance_transforms(self: &Self) -> Vec<glam::Mat4> {
+        if self.instances.is_empty() {
+            return vec![self.transform];
+        }
+        return self
+            .instances
+            .iter()
+            .map(|instance| self.transform * *instance)
+            .collect();
+    }
+
+    fn get_local_bounds(self: &Self) -> BoundingVolume {
+        self.mesh.bounds
+    }
+
+    fn get_script_id(self: &Self) -> Option<i64> {
+        self.script_id
+    }
+
+    fn get_scene_object(self: &Self) -> Option<usize> {
+        self.scene_object
+    }
+
+    fn get_triangles(self: &Self) -> Vec<[glam::Vec3; 3]> {
+        let vertices = &self.mesh.vertices;
+        self.mesh
+            .indices
+            .chunks_exact(3)
+            .map(|t| [t[0], t[1], t[2]].map(|i| vertices[i as usize].pos))
+            .collect()
+    }
+
+    fn drop_gl_resources(self: &mut Self, ctx: &mut BackendArg) {
+        if let Some(val) = self.vertex_buffer_id.take() {
+            ctx.delete_buffer(val);
+        }
+
+        if let Some(val) = self.index_buffer_id.take() {
+            ctx.delete_buffer(val);
+        }
+
+        if let Some(val) = self.wireframe_index_buffer_id.take() {
+            ctx.delete_buffer(val);
+        }
+
+        self.material.drop_gl_resources(ctx);
+    }
+}
+
+impl Drop for MeshObject {
+    fn drop(self: &mut Self) {
+        if self.vertex_buffer_id.is_some()
+            || self.index_buffer_id.is_some()
+            || self.wireframe_index_buffer_id.is_some()
+        {
+            panic!("Mesh {} was not cleared properly", self.mesh.name);
+        }
+    }
+}
@@ -1,21 +1,17 @@
-use std::panic;
+mod mesh_object;
+pub use mesh_object::MeshObject;
 
-use miniquad::{
-    Bindings, BufferId, BufferSource, BufferType, BufferUsage, FilterMode, MipmapFilterMode,
-    RenderingBackend, TextureId,
-};
+use miniquad::{Bindings, RenderingBackend};
 
-use crate::textures;
+use crate::{bounds::BoundingVolume, materials::Material};
 
 #[repr(C)]
 pub struct DataVertex3DTexture {
     pub pos: glam::Vec3,
+    pub normal: glam::Vec3,
     pub uv: glam::Vec2,
-}
-
-#[repr(C)]
-pub struct DataVertex3D {
-    pos: glam::Vec3,
+    /// xyz is the tangent, w the handedness of the bitangent (+1 or -1)
+    pub tangent: glam::Vec4,
 }
 
 type BackendArg = Box<dyn RenderingBackend>;
@@ -24,155 +20,51 @@ type BackendArg = Box<dyn RenderingBackend>;
 pub trait RenderableObject {
     /// get the binding, buffered if possible
     /// Should any resource be missing (opengl, texture, etc.) create
+    /// The images are the ones of the material, in the order of the material slots
     fn get_bindings(self: &mut Self, ctx: &mut BackendArg) -> Bindings;
 
-    /// deallocate any resources that are allocated on opengl
-    fn drop_gl_resources(self: &mut Self, ctx: &mut BackendArg);
-}
-
-pub struct TestTexturedCube {
-    pub vertices: [DataVertex3DTexture; 36],
+    /// the material the object should be shaded with
+    fn get_material(self: &Self) -> &Material;
 
-    /// opengl vars
+    /// number of vertices to draw, the index count for indexed objects
+    fn get_element_count(self: &Self) -> i32;
 
-    ///
-    vertex_buffer_id: Option<BufferId>,
-    index_buffer_id: Option<BufferId>,
-    texture: Option<textures::Texture>,
-    texture_id: Option<TextureId>,
-}
+    /// Same as get_bindings but with a line list index buffer over the triangle edges, to be
+    /// drawn with a lines pipeline for wireframe rendering
+    fn get_wireframe_bindings(self: &mut Self, ctx: &mut BackendArg) -> Bindings;
 
-impl TestTexturedCube {
-    pub fn new() -> Self {
-        TestTexturedCube {
-            #[rustfmt::skip]
-            vertices: [
-                // back 
-                DataVertex3DTexture { pos: glam::vec3(-0.5, -0.5, -0.5), uv: glam::vec2(0.0, 0.0)},
-                DataVertex3DTexture { pos: glam::vec3( 0.5, -0.5, -0.5), uv: glam::vec2(1.0, 0.0)},
-                DataVertex3DTexture { pos: glam::vec3( 0.5,  0.5, -0.5), uv: glam::vec2(1.0, 1.0)},
-                DataVertex3DTexture { pos: glam::vec3( 0.5,  0.5, -0.5), uv: glam::vec2(1.0, 1.0)},
-                DataVertex3DTexture { pos: glam::vec3(-0.5,  0.5, -0.5), uv: glam::vec2(0.0, 1.0)},
-                DataVertex3DTexture { pos: glam::vec3(-0.5, -0.5, -0.5), uv: glam::vec2(0.0, 0.0)},
-                // front
-                DataVertex3DTexture { pos: glam::vec3(-0.5, -0.5,  0.5), uv: glam::vec2(0.0, 0.0)},
-                DataVertex3DTexture { pos: glam::vec3( 0.5, -0.5,  0.5), uv: glam::vec2(1.0, 0.0)},
-                DataVertex3DTexture { pos: glam::vec3( 0.5,  0.5,  0.5), uv: glam::vec2(1.0, 1.0)},
-                DataVertex3DTexture { pos: glam::vec3( 0.5,  0.5,  0.5), uv: glam::vec2(1.0, 1.0)},
-                DataVertex3DTexture { pos: glam::vec3(-0.5,  0.5,  0.5), uv: glam::vec2(0.0, 1.0)},
-                DataVertex3DTexture { pos: glam::vec3(-0.5, -0.5,  0.5), uv: glam::vec2(0.0, 0.0)},
-
-                DataVertex3DTexture { pos: glam::vec3(-0.5,  0.5,  0.5), uv: glam::vec2(1.0, 0.0)},
-                DataVertex3DTexture { pos: glam::vec3(-0.5,  0.5, -0.5), uv: glam::vec2(1.0, 1.0)},
-                DataVertex3DTexture { pos: glam::vec3(-0.5, -0.5, -0.5), uv: glam::vec2(0.0, 1.0)},
-                DataVertex3DTexture { pos: glam::vec3(-0.5, -0.5, -0.5), uv: glam::vec2(0.0, 1.0)},
-                DataVertex3DTexture { pos: glam::vec3(-0.5, -0.5,  0.5), uv: glam::vec2(0.0, 0.0)},
-                DataVertex3DTexture { pos: glam::vec3(-0.5,  0.5,  0.5), uv: glam::vec2(1.0, 0.0)},
-
-                DataVertex3DTexture { pos: glam::vec3( 0.5,  0.5,  0.5), uv: glam::vec2(1.0, 0.0)},
-                DataVertex3DTexture { pos: glam::vec3( 0.5,  0.5, -0.5), uv: glam::vec2(1.0, 1.0)},
-                DataVertex3DTexture { pos: glam::vec3( 0.5, -0.5, -0.5), uv: glam::vec2(0.0, 1.0)},
-                DataVertex3DTexture { pos: glam::vec3( 0.5, -0.5, -0.5), uv: glam::vec2(0.0, 1.0)},
-                DataVertex3DTexture { pos: glam::vec3( 0.5, -0.5,  0.5), uv: glam::vec2(0.0, 0.0)},
-                DataVertex3DTexture { pos: glam::vec3( 0.5,  0.5,  0.5), uv: glam::vec2(1.0, 0.0)},
-
-                DataVertex3DTexture { pos: glam::vec3(-0.5, -0.5, -0.5), uv: glam::vec2(0.0, 1.0)},
-                DataVertex3DTexture { pos: glam::vec3( 0.5, -0.5, -0.5), uv: glam::vec2(1.0, 1.0)},
-                DataVertex3DTexture { pos: glam::vec3( 0.5, -0.5,  0.5), uv: glam::vec2(1.0, 0.0)},
-                DataVertex3DTexture { pos: glam::vec3( 0.5, -0.5,  0.5), uv: glam::vec2(1.0, 0.0)},
-                DataVertex3DTexture { pos: glam::vec3(-0.5, -0.5,  0.5), uv: glam::vec2(0.0, 0.0)},
-                DataVertex3DTexture { pos: glam::vec3(-0.5, -0.5, -0.5), uv: glam::vec2(0.0, 1.0)},
-
-                DataVertex3DTexture { pos: glam::vec3(-0.5,  0.5, -0.5), uv: glam::vec2(0.0, 1.0)},
-                DataVertex3DTexture { pos: glam::vec3( 0.5,  0.5, -0.5), uv: glam::vec2(1.0, 1.0)},
-                DataVertex3DTexture { pos: glam::vec3( 0.5,  0.5,  0.5), uv: glam::vec2(1.0, 0.0)},
-                DataVertex3DTexture { pos: glam::vec3( 0.5,  0.5,  0.5), uv: glam::vec2(1.0, 0.0)},
-                DataVertex3DTexture { pos: glam::vec3(-0.5,  0.5,  0.5), uv: glam::vec2(0.0, 0.0)},
-                DataVertex3DTexture { pos: glam::vec3(-0.5,  0.5, -0.5), uv: glam::vec2(0.0, 1.0)},
-            ],
-            vertex_buffer_id: None,
-            index_buffer_id: None,
-            texture: None,
-            texture_id: None,
-        }
-    }
-}
+    /// number of indices in the wireframe index buffer, valid after get_wireframe_bindings
+    fn get_wireframe_element_count(self: &Self) -> i32;
 
-impl RenderableObject for TestTexturedCube {
-    fn get_bindings(self: &mut Self, ctx: &mut BackendArg) -> Bindings {
-        if self.vertex_buffer_id.is_none() {
-            self.vertex_buffer_id = Some(ctx.new_buffer(
-                BufferType::VertexBuffer,
-                BufferUsage::Immutable,
-                BufferSource::slice(&self.vertices),
-            ));
-        }
-
-        if self.index_buffer_id.is_none() {
-            let indices: [u16; 0] = [];
-            self.index_buffer_id = Some(ctx.new_buffer(
-                BufferType::IndexBuffer,
-                BufferUsage::Immutable,
-                BufferSource::slice(&indices),
-            ));
-        }
-
-        if self.texture_id.is_none() {
-            let texture = textures::Texture::new("test.png".to_owned());
-
-            self.texture_id = Some(ctx.new_texture_from_rgba8(
-                texture.width as u16,
-                texture.width as u16,
-                unsafe {
-                    std::slice::from_raw_parts(
-                        texture.img,
-                        (texture.width * texture.height * 4) as usize,
-                    )
-                },
-            ));
-
-            ctx.texture_set_filter(
-                self.texture_id.clone().unwrap(),
-                FilterMode::Nearest,
-                MipmapFilterMode::None,
-            );
-            self.texture = Some(texture);
-        }
-
-        return Bindings {
-            vertex_buffers: vec![self.vertex_buffer_id.unwrap()],
-            index_buffer: self.index_buffer_id.unwrap(),
-            images: vec![self.texture_id.clone().unwrap()],
-        };
+    /// transform from object into world space
+    fn get_model_matrix(self: &Self) -> glam::Mat4 {
+        glam::Mat4::IDENTITY
     }
 
-    fn drop_gl_resources(self: &mut Self, ctx: &mut BackendArg) {
-        if let Some(val) = self.vertex_buffer_id.take() {
-            ctx.delete_buffer(val);
-        }
+    /// move the object, objects that are always at the origin ignore it
+    fn set_model_matrix(self: &mut Self, _model: glam::Mat4) {}
 
-        if let Some(val) = self.index_buffer_id.take() {
-            ctx.delete_buffer(val);
-        }
+    /// where the object is drawn, more than once for instanced objects
+    fn get_instance_transforms(self: &Self) -> Vec<glam::Mat4> {
+        vec![self.get_model_matrix()]
+    }
 
-        if let Some(val) = self.texture_id.take() {
-            ctx.delete_texture(val);
-        }
+    /// volume around the object in object space, transform it with the model matrix to cull
+    fn get_local_bounds(self: &Self) -> BoundingVolume;
 
-        if let Some(val) = self.texture.take() {
-            drop(val);
-        }
+    /// id of the script object this is, None for everything that was not spawned by a script
+    fn get_script_id(self: &Self) -> Option<i64> {
+        None
     }
-}
 
-impl Drop for TestTexturedCube {
-    fn drop(self: &mut Self) {
-        if self.vertex_buffer_id.is_some()
-            || self.texture.is_some()
-            || self.texture_id.is_some()
-            || self.index_buffer_id.is_some()
-        {
-            panic!("Something inside object was not cleared properly");
-        }
+    /// index of the scene object this was built from, None for objects saving the scene skips
+    fn get_scene_object(self: &Self) -> Option<usize> {
+        None
     }
+
+    /// every triangle in object space, for picking and other cpu side queries
+    fn get_triangles(self: &Self) -> Vec<[glam::Vec3; 3]>;
+
+    /// deallocate any resources that are allocated on opengl
+    fn drop_gl_resources(self: &mut Self, ctx: &mut BackendArg);
 }
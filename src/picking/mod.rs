@@ -0,0 +1,85 @@
+use glam::*;
+
+use crate::{
+    bounds::{BoundingVolume, Ray},
+    objects::RenderableObject,
+};
+
+/// closest surface hit by a ray
+#[derive(Debug, Clone, Copy)]
+pub struct PickHit {
+    /// index into the renderable objects and into the transforms the object is drawn with
+    pub object: usize,
+    pub instance: usize,
+
+    /// along the ray, in world units if the ray direction has a length of 1
+    pub distance: f32,
+    pub point: Vec3,
+    /// world space normal of the hit triangle, facing back along the ray
+    pub normal: Vec3,
+
+    /// world space bounds of the hit instance
+    pub bounds: BoundingVolume,
+}
+
+/// Something that can be hit: object index, instance index, model matrix and the object
+pub type PickCandidate<'a> = (usize, usize, Mat4, &'a dyn RenderableObject);
+
+/// Find the closest triangle along `ray`. The bounds are tested first, triangles are only tested
+/// for instances whose bounds are hit closer than the best triangle so far
+pub fn pick<'a>(ray: &Ray, candidates: impl Iterator<Item = PickCandidate<'a>>) -> Option<PickHit> {
+    let mut closest: Option<PickHit> = None;
+
+    for (object_index, instance, model, object) in candidates {
+        let bounds = object.get_local_bounds().transformed(model);
+        let Some(bounds_distance) = ray.intersect_bounds(&bounds) else {
+            continue;
+        };
+        if closest.is_some_and(|hit| hit.distance < bounds_distance) {
+            continue;
+        }
+
+        // testing in object space keeps the triangles as they are, the distances stay the same
+        let local_ray = ray.transformed(model.inverse());
+
+        let mut best: Option<(f32, [Vec3; 3])> = None;
+        for triangle in object.get_triangles() {
+            let Some(distance) = local_ray.intersect_triangle(triangle) else {
+                continue;
+            };
+            if best.is_none_or(|(best_distance, _)| distance < best_distance) {
+                best = Some((distance, triangle));
+            }
+        }
+
+        let Some((distance, [a, b, c])) = best else {
+            continue;
+        };
+        if closest.is_some_and(|hit| hit.distance <= distance) {
+            continue;
+        }
+
+        // normals go through the inverse transpose to survive non uniform scaling
+        let local_normal = (b - a).cross(c - a);
+        let normal = Mat3::from_mat4(model)
+            .inverse()
+            .transpose()
+            .mul_vec3(local_normal)
+            .normalize();
+
+        closest = Some(PickHit {
+            object: object_index,
+            instance,
+            distance,
+            point: ray.at(distance),
+            normal: if normal.dot(ray.direction) > 0.0 {
+                -normal
+            } else {
+                normal
+            },
+            bounds,
+        });
+    }
+
+    return closest;
+}
@@ -0,0 +1,308 @@
+use miniquad::{
+    Bindings, BufferId, BufferLayout, BufferSource, BufferType, BufferUsage, PassAction, Pipeline,
+    PipelineParams, RenderPass, RenderingBackend, ShaderId, TextureFormat, TextureId,
+    UniformsSource, VertexAttribute, VertexFormat,
+};
+
+use crate::{profiler::profile_scope, render_targets::RenderTarget, shaders::ShaderFile};
+
+type BackendArg = Box<dyn RenderingBackend>;
+
+/// One fullscreen pass reading the output of the previous one.
+/// The fragment shader lives in ./shaders/post_<name>.frag.glsl and is hot reloaded, all effects
+/// share the post_fullscreen vertex shader
+pub struct PostEffect {
+    pub name: String,
+    pub enabled: bool,
+
+    /// effect specific values, documented at the top of each effect's fragment shader
+    pub params: glam::Vec4,
+
+    shader_file: ShaderFile,
+
+    /// opengl vars
+    shader: Option<ShaderId>,
+    pipeline: Option<Pipeline>,
+}
+
+impl PostEffect {
+    pub fn new(name: &str, params: glam::Vec4) -> Self {
+        Self {
+            name: name.to_owned(),
+            enabled: true,
+            params,
+            shader_file: ShaderFile::from_files(
+                name.to_owned(),
+                "post_fullscreen",
+                &format!("post_{}", name),
+            ),
+            shader: None,
+            pipeline: None,
+        }
+    }
+
+    /// Compile the shader if it changed on disk or was never compiled. A broken shader keeps the
+    /// last working pipeline so editing an effect does not kill the frame, and is only tried again
+    /// once the file changes
+    fn update_pipeline(&mut self, ctx: &mut BackendArg) {
+        if !self.shader_file.reload_if_needed()
+            && (self.pipeline.is_some() || self.shader_file.compile_failed())
+        {
+            return;
+        }
+
+        let compiled = ctx.new_shader(self.shader_file.get_shadersource(), shader::meta());
+        self.shader_file.report_compile(&compiled);
+        let Ok(shader) = compiled else {
+            return;
+        };
+
+        self.drop_gl_resources(ctx);
+
+        self.pipeline = Some(ctx.new_pipeline(
+            &[BufferLayout::default()],
+            &[
+                VertexAttribute::new("in_pos", VertexFormat::Float2),
+                VertexAttribute::new("in_uv", VertexFormat::Float2),
+            ],
+            shader,
+            PipelineParams::default(),
+        ));
+        self.shader = Some(shader);
+    }
+
+    /// deallocate any resources that are allocated on opengl
+    fn drop_gl_resources(&mut self, ctx: &mut BackendArg) {
+        if let Some(val) = self.pipeline.take() {
+            ctx.delete_pipeline(val);
+        }
+
+        if let Some(val) = self.shader.take() {
+            ctx.delete_shader(val);
+        }
+    }
+}
+
+/// Ordered list of fullscreen effects applied to the rendered scene, the last enabled one
+/// writes into the window. Intermediate results ping-pong between two render targets
+pub struct PostProcessChain {
+    pub effects: Vec<PostEffect>,
+
+    /// plain blit, used when every effect is disabled
+    copy: PostEffect,
+
+    targets: [RenderTarget; 2],
+
+    /// opengl vars
+    quad_vertex_buffer: Option<BufferId>,
+    quad_index_buffer: Option<BufferId>,
+}
+
+impl PostProcessChain {
+    /// the default chain: bloom and tonemapping on hdr values, then gamma and the ldr effects
+    pub fn new() -> Self {
+        Self {
+            effects: vec![
+                PostEffect::new("bloom", glam::vec4(1.0, 0.6, 4.0, 0.0)),
+                PostEffect::new("tonemap", glam::vec4(1.0, 0.0, 0.0, 0.0)),
+                PostEffect::new("gamma", glam::vec4(2.2, 0.0, 0.0, 0.0)),
+                PostEffect::new("fxaa", glam::vec4(8.0, 1.0 / 8.0, 1.0 / 128.0, 0.0)),
+                PostEffect::new("vignette", glam::vec4(0.4, 0.75, 0.45, 0.0)),
+            ],
+            copy: PostEffect::new("copy", glam::Vec4::ZERO),
+            targets: [
+                RenderTarget::new(TextureFormat::RGBA16F, false),
+                RenderTarget::new(TextureFormat::RGBA16F, false),
+            ],
+            quad_vertex_buffer: None,
+            quad_index_buffer: None,
+        }
+    }
+
+    /// flip an effect on or off, returns the new state or None if there is no such effect
+    pub fn toggle(&mut self, index: usize) -> Option<bool> {
+        let effect = self.effects.get_mut(index)?;
+        effect.enabled = !effect.enabled;
+
+        return Some(effect.enabled);
+    }
+
+    /// Run all enabled effects on `source` and write the result into `output`, None being the
+    /// window. Must be called outside of a render pass
+    pub fn run(
+        &mut self,
+        ctx: &mut BackendArg,
+        source: TextureId,
+        output: Option<RenderPass>,
+        width: u32,
+        height: u32,
+    ) {
+        profile_scope!("post process");
+        for target in self.targets.iter_mut() {
+            target.ensure_size(ctx, width, height);
+        }
+
+        self.ensure_quad(ctx);
+
+        for effect in self.effects.iter_mut() {
+            if effect.enabled {
+                effect.update_pipeline(ctx);
+            }
+        }
+        self.copy.update_pipeline(ctx);
+
+        let mut enabled: Vec<&PostEffect> = self
+            .effects
+            .iter()
+            .filter(|e| e.enabled && e.pipeline.is_some())
+            .collect();
+
+        if enabled.is_empty() {
+            enabled.push(&self.copy);
+        }
+
+        let resolution = glam::vec4(
+            width as f32,
+            height as f32,
+            1.0 / width as f32,
+            1.0 / height as f32,
+        );
+
+        let mut input = source;
+
+        for (index, effect) in enabled.iter().enumerate() {
+            let target = &self.targets[index % 2];
+            let last = index == enabled.len() - 1;
+
+            if last {
+                ctx.begin_pass(output, PassAction::Nothing);
+            } else {
+                ctx.begin_pass(Some(target.pass()), PassAction::Nothing);
+            }
+
+            ctx.apply_pipeline(&effect.pipeline.unwrap());
+            ctx.apply_bindings(&Bindings {
+                vertex_buffers: vec![self.quad_vertex_buffer.unwrap()],
+                index_buffer: self.quad_index_buffer.unwrap(),
+                images: vec![input],
+            });
+            ctx.apply_uniforms(UniformsSource::table(&shader::Uniforms {
+                resolution,
+                params: effect.params,
+            }));
+            ctx.draw(0, 6, 1);
+            ctx.end_render_pass();
+
+            input = target.color_texture();
+        }
+    }
+
+    /// Copy `source` into the window unchanged, for frames that were rendered offscreen.
+    /// Must be called outside of a render pass
+    pub fn present(&mut self, ctx: &mut BackendArg, source: TextureId, width: u32, height: u32) {
+        profile_scope!("present");
+        self.ensure_quad(ctx);
+        self.copy.update_pipeline(ctx);
+
+        let Some(pipeline) = self.copy.pipeline else {
+            return;
+        };
+
+        ctx.begin_default_pass(PassAction::Nothing);
+        ctx.apply_pipeline(&pipeline);
+        ctx.apply_bindings(&Bindings {
+            vertex_buffers: vec![self.quad_vertex_buffer.unwrap()],
+            index_buffer: self.quad_index_buffer.unwrap(),
+            images: vec![source],
+        });
+        ctx.apply_uniforms(UniformsSource::table(&shader::Uniforms {
+            resolution: glam::vec4(
+                width as f32,
+                height as f32,
+                1.0 / width as f32,
+                1.0 / height as f32,
+            ),
+            params: self.copy.params,
+        }));
+        ctx.draw(0, 6, 1);
+        ctx.end_render_pass();
+    }
+
+    fn ensure_quad(&mut self, ctx: &mut BackendArg) {
+        if self.quad_vertex_buffer.is_none() {
+            #[rustfmt::skip]
+            let vertices: [f32; 16] = [
+                // pos         uv
+                -1.0, -1.0,    0.0, 0.0,
+                 1.0, -1.0,    1.0, 0.0,
+                 1.0,  1.0,    1.0, 1.0,
+                -1.0,  1.0,    0.0, 1.0,
+            ];
+
+            self.quad_vertex_buffer = Some(ctx.new_buffer(
+                BufferType::VertexBuffer,
+                BufferUsage::Immutable,
+                BufferSource::slice(&vertices),
+            ));
+        }
+
+        if self.quad_index_buffer.is_none() {
+            let indices: [u16; 6] = [0, 1, 2, 0, 2, 3];
+
+            self.quad_index_buffer = Some(ctx.new_buffer(
+                BufferType::IndexBuffer,
+                BufferUsage::Immutable,
+                BufferSource::slice(&indices),
+            ));
+        }
+    }
+
+    /// deallocate any resources that are allocated on opengl
+    pub fn drop_gl_resources(&mut self, ctx: &mut BackendArg) {
+        for effect in self.effects.iter_mut() {
+            effect.drop_gl_resources(ctx);
+        }
+        self.copy.drop_gl_resources(ctx);
+
+        for target in self.targets.iter_mut() {
+            target.drop_gl_resources(ctx);
+        }
+
+        if let Some(val) = self.quad_vertex_buffer.take() {
+            ctx.delete_buffer(val);
+        }
+
+        if let Some(val) = self.quad_index_buffer.take() {
+            ctx.delete_buffer(val);
+        }
+    }
+}
+
+impl Drop for PostProcessChain {
+    fn drop(&mut self) {
+        if self.quad_vertex_buffer.is_some() || self.quad_index_buffer.is_some() {
+            panic!("Post processing chain was not cleared properly");
+        }
+    }
+}
+
+mod shader {
+    use miniquad::*;
+    pub fn meta() -> ShaderMeta {
+        ShaderMeta {
+            images: vec!["source".to_string()],
+            uniforms: UniformBlockLayout {
+                uniforms: vec![
+                    UniformDesc::new("resolution", UniformType::Float4),
+                    UniformDesc::new("params", UniformType::Float4),
+                ],
+            },
+        }
+    }
+    #[repr(C)]
+    pub struct Uniforms {
+        // width, height, 1 / width, 1 / height
+        pub resolution: glam::Vec4,
+        pub params: glam::Vec4,
+    }
+}
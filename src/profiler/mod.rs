@@ -0,0 +1,193 @@
+// CPU frame profiler, callable from anywhere on the main thread.
+// `profile_scope!("name")` times the rest of the enclosing block. The scopes of a frame are
+// collected when the next one starts and kept for the last HISTORY frames, the debug panel graphs
+// them and save_chrome_trace writes them for chrome://tracing or ui.perfetto.dev
+use std::{
+    cell::RefCell,
+    collections::VecDeque,
+    fmt::Write,
+    time::{Duration, Instant},
+};
+
+/// frames kept for the graph and the trace, a few seconds worth
+pub const HISTORY: usize = 240;
+pub const DEFAULT_TRACE_FILE: &str = "./profile_trace.json";
+
+/// One finished scope. Times are relative to the start of the profiler
+#[derive(Debug, Clone, Copy)]
+pub struct Sample {
+    pub name: &'static str,
+    pub start: Duration,
+    pub duration: Duration,
+    /// how many scopes were open around this one
+    pub depth: u32,
+}
+
+#[derive(Debug, Clone)]
+pub struct FrameProfile {
+    pub number: u64,
+    pub start: Duration,
+    pub duration: Duration,
+    /// in the order the scopes ended, inner scopes come before the ones around them
+    pub samples: Vec<Sample>,
+}
+
+impl FrameProfile {
+    /// Total time and count per scope name, in the order the names were first entered. Calls of
+    /// the same name add up, e.g. every model that was loaded
+    pub fn breakdown(&self) -> Vec<(&'static str, u32, Duration, u32)> {
+        let mut samples = self.samples.clone();
+        samples.sort_by_key(|s| s.start);
+
+        let mut totals: Vec<(&'static str, u32, Duration, u32)> = vec![];
+        for sample in samples {
+            match totals.iter_mut().find(|t| t.0 == sample.name) {
+                Some(total) => {
+                    total.2 += sample.duration;
+                    total.3 += 1;
+                }
+                None => totals.push((sample.name, sample.depth, sample.duration, 1)),
+            }
+        }
+        return totals;
+    }
+}
+
+struct Profiler {
+    epoch: Instant,
+    /// stop collecting frames, to look at one that stood out
+    paused: bool,
+    frame_number: u64,
+    frame_start: Duration,
+    depth: u32,
+    samples: Vec<Sample>,
+    frames: VecDeque<FrameProfile>,
+}
+
+thread_local! {
+    static PROFILER: RefCell<Profiler> = RefCell::new(Profiler {
+        epoch: Instant::now(),
+        paused: false,
+        frame_number: 0,
+        frame_start: Duration::ZERO,
+        depth: 0,
+        samples: vec![],
+        frames: VecDeque::with_capacity(HISTORY),
+    });
+}
+
+/// Times the rest of the enclosing block under `$name`, a &'static str
+macro_rules! profile_scope {
+    ($name:expr) => {
+        let _profile_scope = $crate::profiler::Scope::new($name);
+    };
+}
+pub(crate) use profile_scope;
+
+/// Records a sample when dropped, made by profile_scope!
+pub struct Scope {
+    name: &'static str,
+    start: Duration,
+}
+
+impl Scope {
+    pub fn new(name: &'static str) -> Self {
+        let start = PROFILER.with_borrow_mut(|p| {
+            p.depth += 1;
+            p.epoch.elapsed()
+        });
+        return Self { name, start };
+    }
+}
+
+impl Drop for Scope {
+    fn drop(&mut self) {
+        PROFILER.with_borrow_mut(|p| {
+            p.depth -= 1;
+            p.samples.push(Sample {
+                name: self.name,
+                start: self.start,
+                duration: p.epoch.elapsed() - self.start,
+                depth: p.depth,
+            });
+        });
+    }
+}
+
+/// End the current frame and start the next one, called first thing every frame. Scopes still
+/// open at this point count towards the next frame
+pub fn new_frame() {
+    PROFILER.with_borrow_mut(|p| {
+        let now = p.epoch.elapsed();
+        let samples = std::mem::take(&mut p.samples);
+        // the first frame also holds everything that was loaded before the window opened
+        if !p.paused {
+            if p.frames.len() == HISTORY {
+                p.frames.pop_front();
+            }
+            p.frames.push_back(FrameProfile {
+                number: p.frame_number,
+                start: p.frame_start,
+                duration: now - p.frame_start,
+                samples,
+            });
+        }
+        p.frame_number += 1;
+        p.frame_start = now;
+    });
+}
+
+pub fn is_paused() -> bool {
+    PROFILER.with_borrow(|p| p.paused)
+}
+
+pub fn set_paused(paused: bool) {
+    PROFILER.with_borrow_mut(|p| p.paused = paused);
+}
+
+/// Look at the kept frames, oldest first
+pub fn with_frames<R>(f: impl FnOnce(&VecDeque<FrameProfile>) -> R) -> R {
+    PROFILER.with_borrow(|p| f(&p.frames))
+}
+
+/// Write the kept frames in the Chrome trace event format. Every frame is an event on its own
+/// row above the scopes it contains
+pub fn save_chrome_trace(path: &str) -> Result<(), String> {
+    let mut events = vec![];
+    with_frames(|frames| {
+        for frame in frames {
+            let name = format!("frame {}", frame.number);
+            events.push(trace_event(&name, frame.start, frame.duration, 0));
+            for sample in &frame.samples {
+                events.push(trace_event(sample.name, sample.start, sample.duration, 1));
+            }
+        }
+    });
+
+    let json = format!(
+        "{{\"displayTimeUnit\":\"ms\",\"traceEvents\":[\n{}\n]}}\n",
+        events.join(",\n")
+    );
+    std::fs::write(path, json).map_err(|e| format!("{}: {}", path, e))?;
+    return Ok(());
+}
+
+/// a complete ("X") event, trace timestamps are in microseconds
+fn trace_event(name: &str, start: Duration, duration: Duration, thread: u32) -> String {
+    let mut escaped = String::new();
+    for c in name.chars() {
+        match c {
+            '"' => escaped.push_str("\\\""),
+            '\\' => escaped.push_str("\\\\"),
+            c if (c as u32) < 0x20 => write!(escaped, "\\u{:04x}", c as u32).unwrap(),
+            c => escaped.push(c),
+        }
+    }
+    return format!(
+        "{{\"name\":\"{}\",\"cat\":\"cpu\",\"ph\":\"X\",\"ts\":{:.3},\"dur\":{:.3},\"pid\":1,\"tid\":{}}}",
+        escaped,
+        start.as_secs_f64() * 1e6,
+        duration.as_secs_f64() * 1e6,
+        thread
+    );
+}
@@ -0,0 +1,107 @@
+use miniquad::{
+    FilterMode, MipmapFilterMode, RenderPass, RenderingBackend, TextureFormat, TextureId,
+    TextureKind, TextureParams, TextureWrap,
+};
+
+type BackendArg = Box<dyn RenderingBackend>;
+
+/// Offscreen color (and optionally depth) attachment to render into instead of the window.
+/// The attachments are created lazily and recreated whenever the requested size changes
+pub struct RenderTarget {
+    pub color_format: TextureFormat,
+    pub with_depth: bool,
+
+    width: u32,
+    height: u32,
+
+    /// opengl vars
+    color_texture: Option<TextureId>,
+    depth_texture: Option<TextureId>,
+    pass: Option<RenderPass>,
+}
+
+impl RenderTarget {
+    pub fn new(color_format: TextureFormat, with_depth: bool) -> Self {
+        Self {
+            color_format,
+            with_depth,
+            width: 0,
+            height: 0,
+            color_texture: None,
+            depth_texture: None,
+            pass: None,
+        }
+    }
+
+    /// Make sure the attachments exist and have the given size, rebuilding them if needed.
+    /// Everything rendered into the old attachments is lost
+    pub fn ensure_size(&mut self, ctx: &mut BackendArg, width: u32, height: u32) {
+        let (width, height) = (width.max(1), height.max(1));
+
+        if self.pass.is_some() && self.width == width && self.height == height {
+            return;
+        }
+
+        self.drop_gl_resources(ctx);
+
+        let color = ctx.new_render_texture(Self::params(self.color_format, width, height));
+        let depth = self
+            .with_depth
+            .then(|| ctx.new_render_texture(Self::params(TextureFormat::Depth, width, height)));
+
+        self.pass = Some(ctx.new_render_pass(color, depth));
+        self.color_texture = Some(color);
+        self.depth_texture = depth;
+        self.width = width;
+        self.height = height;
+    }
+
+    fn params(format: TextureFormat, width: u32, height: u32) -> TextureParams {
+        TextureParams {
+            kind: TextureKind::Texture2D,
+            format,
+            wrap: TextureWrap::Clamp,
+            min_filter: FilterMode::Linear,
+            mag_filter: FilterMode::Linear,
+            mipmap_filter: MipmapFilterMode::None,
+            width,
+            height,
+            allocate_mipmaps: false,
+            sample_count: 1,
+        }
+    }
+
+    /// panics if `ensure_size` was never called
+    pub fn pass(&self) -> RenderPass {
+        self.pass.expect("render target used before it was sized")
+    }
+
+    /// panics if `ensure_size` was never called
+    pub fn color_texture(&self) -> TextureId {
+        self.color_texture
+            .expect("render target used before it was sized")
+    }
+
+    pub fn size(&self) -> (u32, u32) {
+        (self.width, self.height)
+    }
+
+    /// deallocate any resources that are allocated on opengl
+    pub fn drop_gl_resources(&mut self, ctx: &mut BackendArg) {
+        // deleting the pass also deletes its attachments
+        if let Some(val) = self.pass.take() {
+            ctx.delete_render_pass(val);
+        }
+
+        self.color_texture = None;
+        self.depth_texture = None;
+    }
+}
+
+impl Drop for RenderTarget {
+    fn drop(&mut self) {
+        if self.pass.is_some() || self.color_texture.is_some() || self.depth_texture.is_some() {
+            panic!("Render target was not cleared properly");
+        }
+    }
+}
@@ -0,0 +1,404 @@
+// Input recording and replay. Every key and mouse event that reaches the game is stored with the
+// frame it arrived in. Recording and replaying both run on a fixed time step, so a replay gives
+// the stage the same input at the same simulated time and it ends up in the same state
+use std::path::{Path, PathBuf};
+
+use glam::*;
+use miniquad::{KeyCode, KeyMods, MouseButton};
+
+use crate::actions::{Binding, Input};
+
+/// where recordings are saved to and replayed from
+pub const DEFAULT_RECORDING_FILE: &str = "./input_recording.txt";
+
+/// seconds per frame while recording and replaying
+const TIMESTEP: f64 = 1.0 / 60.0;
+
+/// an event after the debug ui had its look at it. Key repeats are not recorded, nothing in the
+/// game reacts to them
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum InputEvent {
+    KeyDown {
+        key: KeyCode,
+        mods: KeyMods,
+    },
+    KeyUp {
+        key: KeyCode,
+        mods: KeyMods,
+    },
+    /// positions in physical pixels from the top left, like the miniquad events
+    MouseDown {
+        button: MouseButton,
+        x: f32,
+        y: f32,
+    },
+    MouseUp {
+        button: MouseButton,
+        x: f32,
+        y: f32,
+    },
+    MouseMotion {
+        x: f32,
+        y: f32,
+    },
+    MouseWheel {
+        x: f32,
+        y: f32,
+    },
+}
+
+/// What the input acts on when the recording starts. Settings like the mouse sensitivity and the
+/// window size are not part of it, they have to match for a replay to end up in the same place
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct StartState {
+    pub camera_pos: Vec3,
+    pub orientation: Quat,
+    pub controller: usize,
+    /// cursor position in pixels from the top left
+    pub mouse: Vec2,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct InputRecording {
+    /// seconds per frame
+    pub timestep: f64,
+    /// seconds per simulation step, a replay has to take the same steps
+    pub simulation_step: f64,
+    pub start: StartState,
+    /// number of frames recorded, the replay runs this long even after the last event
+    pub frames: u64,
+    /// sorted by frame, events of one frame in the order they arrived
+    pub events: Vec<(u64, InputEvent)>,
+}
+
+impl InputRecording {
+    /// Read a recording written by save. A header of `name values` lines followed by one event
+    /// per line: the frame, a short event name and its arguments. Empty lines and lines starting
+    /// with # are skipped
+    pub fn load(path: &Path) -> Result<Self, String> {
+        let source = std::fs::read_to_string(path)
+            .map_err(|e| format!("could not read {}: {}", path.display(), e))?;
+
+        let mut timestep = None;
+        let mut simulation_step = None;
+        let mut start = StartState {
+            camera_pos: Vec3::ZERO,
+            orientation: Quat::IDENTITY,
+            controller: 0,
+            mouse: Vec2::ZERO,
+        };
+        let mut frames = 0;
+        let mut events = vec![];
+
+        for (number, line) in source.lines().enumerate() {
+            let error =
+                |message: String| format!("{} line {}: {}", path.display(), number + 1, message);
+
+            let line = line.trim();
+            if line.is_empty() || line.starts_with('#') {
+                continue;
+            }
+
+            let words: Vec<&str> = line.split_whitespace().collect();
+            let floats = |values: &[&str], count: usize| -> Result<Vec<f32>, String> {
+                if values.len() != count {
+                    return Err(error(format!(
+                        "expected {} numbers, found {}",
+                        count,
+                        values.len()
+                    )));
+                }
+                values
+                    .iter()
+                    .map(|v| v.parse::<f32>().map_err(|e| error(e.to_string())))
+                    .collect()
+            };
+
+            match words[0] {
+                "timestep" => {
+                    let value = words.get(1).and_then(|v| v.parse::<f64>().ok());
+                    timestep = Some(value.ok_or_else(|| error("expected seconds".to_owned()))?);
+                }
+                "simulation_step" => {
+                    let value = words.get(1).and_then(|v| v.parse::<f64>().ok());
+                    simulation_step =
+                        Some(value.ok_or_else(|| error("expected seconds".to_owned()))?);
+                }
+                "camera" => {
+                    let v = floats(&words[1..], 7)?;
+                    start.camera_pos = vec3(v[0], v[1], v[2]);
+                    start.orientation = Quat::from_xyzw(v[3], v[4], v[5], v[6]).normalize();
+                }
+                "controller" => {
+                    let value = words.get(1).and_then(|v| v.parse().ok());
+                    start.controller =
+                        value.ok_or_else(|| error("expected an index".to_owned()))?;
+                }
+                "mouse" => {
+                    let v = floats(&words[1..], 2)?;
+                    start.mouse = vec2(v[0], v[1]);
+                }
+                "frames" => {
+                    let value = words.get(1).and_then(|v| v.parse().ok());
+                    frames = value.ok_or_else(|| error("expected a frame count".to_owned()))?;
+                }
+                frame => {
+                    let frame: u64 = frame
+                        .parse()
+                        .map_err(|_| error(format!("unknown line {:?}", line)))?;
+                    let event = parse_event(&words[1..]).map_err(error)?;
+
+                    if events.last().is_some_and(|(last, _)| *last > frame) {
+                        return Err(error("events are not sorted by frame".to_owned()));
+                    }
+                    events.push((frame, event));
+                }
+            }
+        }
+
+        let Some(timestep) = timestep.filter(|t| *t > 0.0) else {
+            return Err(format!("{}: no timestep", path.display()));
+        };
+
+        return Ok(Self {
+            timestep,
+            // recordings from before the fixed simulation step ran one step per frame
+            simulation_step: simulation_step.filter(|s| *s > 0.0).unwrap_or(timestep),
+            start,
+            frames,
+            events,
+        });
+    }
+
+    pub fn save(&self, path: &Path) -> Result<(), String> {
+        let s = &self.start;
+        let mut text = format!(
+            "timestep {}\nsimulation_step {}\ncamera {} {} {} {} {} {} {}\ncontroller {}\nmouse {} {}\nframes {}\n",
+            self.timestep,
+            self.simulation_step,
+            s.camera_pos.x,
+            s.camera_pos.y,
+            s.camera_pos.z,
+            s.orientation.x,
+            s.orientation.y,
+            s.orientation.z,
+            s.orientation.w,
+            s.controller,
+            s.mouse.x,
+            s.mouse.y,
+            self.frames
+        );
+
+        text += "# frame event arguments\n";
+        for (frame, event) in self.events.iter() {
+            text += &format!("{} {}\n", frame, format_event(event));
+        }
+
+        return std::fs::write(path, text)
+            .map_err(|e| format!("could not save {}: {}", path.display(), e));
+    }
+}
+
+/// Keys and buttons are written like bindings, so the modifiers come along: `kd Ctrl+S`,
+/// `md MouseLeft 10 20`, `mm 12 20`
+fn format_event(event: &InputEvent) -> String {
+    let key = |key, mods| Binding {
+        input: Input::Key(key),
+        mods,
+    };
+    let button = |button| Binding {
+        input: Input::Mouse(button),
+        mods: KeyMods::default(),
+    };
+
+    match *event {
+        InputEvent::KeyDown { key: k, mods } => format!("kd {}", key(k, mods)),
+        InputEvent::KeyUp { key: k, mods } => format!("ku {}", key(k, mods)),
+        InputEvent::MouseDown { button: b, x, y } => format!("md {} {} {}", button(b), x, y),
+        InputEvent::MouseUp { button: b, x, y } => format!("mu {} {} {}", button(b), x, y),
+        InputEvent::MouseMotion { x, y } => format!("mm {} {}", x, y),
+        InputEvent::MouseWheel { x, y } => format!("mw {} {}", x, y),
+    }
+}
+
+fn parse_event(words: &[&str]) -> Result<InputEvent, String> {
+    let key = |words: &[&str]| -> Result<(KeyCode, KeyMods), String> {
+        let binding = Binding::parse(words.first().copied().unwrap_or_default())?;
+        match binding.input {
+            Input::Key(key) => Ok((key, binding.mods)),
+            Input::Mouse(_) => Err(format!("expected a key, found {}", binding)),
+        }
+    };
+    let button = |words: &[&str]| -> Result<MouseButton, String> {
+        match Binding::parse(words.first().copied().unwrap_or_default())?.input {
+            Input::Mouse(button) => Ok(button),
+            Input::Key(key) => Err(format!("expected a mouse button, found {:?}", key)),
+        }
+    };
+    let position = |words: &[&str]| -> Result<(f32, f32), String> {
+        let [x, y] = words else {
+            return Err(format!("expected x and y, found {:?}", words.join(" ")));
+        };
+        let parse = |v: &str| v.parse::<f32>().map_err(|e| e.to_string());
+        Ok((parse(x)?, parse(y)?))
+    };
+
+    let Some((name, arguments)) = words.split_first() else {
+        return Err("expected an event after the frame".to_owned());
+    };
+
+    let event = match *name {
+        "kd" => {
+            let (key, mods) = key(arguments)?;
+            InputEvent::KeyDown { key, mods }
+        }
+        "ku" => {
+            let (key, mods) = key(arguments)?;
+            InputEvent::KeyUp { key, mods }
+        }
+        "md" | "mu" => {
+            let button = button(arguments)?;
+            let (x, y) = position(arguments.get(1..).unwrap_or_default())?;
+            if *name == "md" {
+                InputEvent::MouseDown { button, x, y }
+            } else {
+                InputEvent::MouseUp { button, x, y }
+            }
+        }
+        "mm" => {
+            let (x, y) = position(arguments)?;
+            InputEvent::MouseMotion { x, y }
+        }
+        "mw" => {
+            let (x, y) = position(arguments)?;
+            InputEvent::MouseWheel { x, y }
+        }
+        _ => return Err(format!("unknown event {:?}", name)),
+    };
+
+    return Ok(event);
+}
+
+pub enum ReplayState {
+    Idle,
+    Recording(InputRecording),
+    /// `next_event` indexes the events of the recording
+    Replaying {
+        recording: InputRecording,
+        next_event: usize,
+    },
+}
+
+/// Records live input or feeds a recording to the stage instead of it
+pub struct InputReplay {
+    pub state: ReplayState,
+    pub file: PathBuf,
+
+    /// frames since recording or replaying started
+    frame: u64,
+}
+
+impl InputReplay {
+    pub fn new() -> Self {
+        Self {
+            state: ReplayState::Idle,
+            file: PathBuf::from(DEFAULT_RECORDING_FILE),
+            frame: 0,
+        }
+    }
+
+    pub fn start_recording(&mut self, start: StartState, simulation_step: f64) {
+        self.frame = 0;
+        self.state = ReplayState::Recording(InputRecording {
+            timestep: TIMESTEP,
+            simulation_step,
+            start,
+            frames: 0,
+            events: vec![],
+        });
+    }
+
+    pub fn start_replay(&mut self, recording: InputRecording) {
+        self.frame = 0;
+        self.state = ReplayState::Replaying {
+            recording,
+            next_event: 0,
+        };
+    }
+
+    /// Go back to live input. Returns the recording if one was being made
+    pub fn stop(&mut self) -> Option<InputRecording> {
+        match std::mem::replace(&mut self.state, ReplayState::Idle) {
+            ReplayState::Recording(mut recording) => {
+                recording.frames = self.frame;
+                Some(recording)
+            }
+            _ => None,
+        }
+    }
+
+    pub fn is_recording(&self) -> bool {
+        matches!(self.state, ReplayState::Recording(_))
+    }
+
+    /// live input has to be ignored while this is true
+    pub fn is_replaying(&self) -> bool {
+        matches!(self.state, ReplayState::Replaying { .. })
+    }
+
+    /// the simulated time step while recording or replaying
+    pub fn fixed_delta(&self) -> Option<f64> {
+        match &self.state {
+            ReplayState::Idle => None,
+            ReplayState::Recording(recording) | ReplayState::Replaying { recording, .. } => {
+                Some(recording.timestep)
+            }
+        }
+    }
+
+    /// store a live event for the current frame
+    pub fn record(&mut self, event: InputEvent) {
+        if let ReplayState::Recording(recording) = &mut self.state {
+            recording.events.push((self.frame, event));
+        }
+    }
+
+    /// the recorded events of the current frame, in the order they arrived
+    pub fn due_events(&mut self) -> Vec<InputEvent> {
+        let ReplayState::Replaying {
+            recording,
+            next_event,
+        } = &mut self.state
+        else {
+            return vec![];
+        };
+
+        let due: Vec<InputEvent> = recording.events[*next_event..]
+            .iter()
+            .take_while(|(frame, _)| *frame <= self.frame)
+            .map(|(_, event)| *event)
+            .collect();
+        *next_event += due.len();
+
+        return due;
+    }
+
+    /// Count the frame, called at the end of every update. Returns true when a replay has run for
+    /// as many frames as were recorded and live input takes over again
+    pub fn end_frame(&mut self) -> bool {
+        self.frame += 1;
+
+        if let ReplayState::Replaying { recording, .. } = &self.state
+            && self.frame >= recording.frames
+        {
+            self.state = ReplayState::Idle;
+            return true;
+        }
+
+        return false;
+    }
+
+    pub fn frame(&self) -> u64 {
+        self.frame
+    }
+}
@@ -0,0 +1,439 @@
+// Scenes as RON files: the camera, the sun, the environment, named materials and the objects
+// using them. A file starts with its version, newer versions than this build knows are refused.
+// Assets that a scene references but that are not there are reported and left out, so a scene
+// with a missing texture or model still loads as far as it can. save_scene in the console writes
+// the file back without comments, which is why the scenes that ship have none
+use std::path::Path;
+
+use miniquad::FilterMode;
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    materials::{Environment, Material},
+    meshes::{self, Mesh},
+    objects::MeshObject,
+    stage::{Camera, DirectionalLight},
+    textures,
+};
+
+pub const DEFAULT_SCENE: &str = "./scenes/demo.ron";
+
+/// what save writes, bump it and keep reading the older versions in parse when the format changes
+pub const SCENE_VERSION: u32 = 1;
+
+/// Everything a scene file holds
+#[derive(Serialize, Deserialize, Clone)]
+pub struct Scene {
+    pub version: u32,
+    pub camera: CameraDescription,
+    pub light: LightDescription,
+    pub environment: EnvironmentDescription,
+    #[serde(default)]
+    pub materials: Vec<MaterialDescription>,
+    #[serde(default)]
+    pub objects: Vec<ObjectDescription>,
+}
+
+/// where the camera starts, the projection comes from the config
+#[derive(Serialize, Deserialize, Clone)]
+pub struct CameraDescription {
+    pub position: [f32; 3],
+    /// degrees, see Camera::yaw_pitch_roll
+    #[serde(default)]
+    pub yaw_pitch_roll: [f32; 3],
+}
+
+#[derive(Serialize, Deserialize, Clone)]
+pub struct LightDescription {
+    /// the direction the light travels in
+    pub direction: [f32; 3],
+    pub color: [f32; 3],
+    pub intensity: f32,
+}
+
+#[derive(Serialize, Deserialize, Clone)]
+pub struct EnvironmentDescription {
+    /// folder inside of ./sprites with px.png, nx.png, py.png, ny.png, pz.png and nz.png, a
+    /// constant ambient color without one
+    #[serde(default)]
+    pub cubemap: Option<String>,
+    pub enabled: bool,
+    pub intensity: f32,
+}
+
+/// A Material without its gpu side, missing fields keep the values of Material::new
+#[derive(Serialize, Deserialize, Clone)]
+#[serde(default)]
+pub struct MaterialDescription {
+    pub name: String,
+    pub base_color: [f32; 4],
+    pub metallic: f32,
+    pub roughness: f32,
+    pub emissive: [f32; 3],
+    pub normal_scale: f32,
+    pub occlusion_strength: f32,
+
+    /// paths relative to ./sprites like the ones of Material
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub base_color_texture: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub metallic_roughness_texture: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub normal_texture: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub occlusion_texture: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub emissive_texture: Option<String>,
+    #[serde(with = "FilterName")]
+    pub filter: FilterMode,
+}
+
+/// Material::filter as it is written in the file
+#[derive(Serialize, Deserialize)]
+#[serde(remote = "FilterMode")]
+enum FilterName {
+    Linear,
+    Nearest,
+}
+
+#[derive(Serialize, Deserialize, Clone)]
+pub enum Shape {
+    Cube {
+        size: f32,
+    },
+    Sphere {
+        radius: f32,
+    },
+    Plane {
+        size: f32,
+        uv_scale: f32,
+    },
+    /// an .obj, .gltf or .glb file relative to the working directory, it brings its own materials
+    Model {
+        path: String,
+    },
+}
+
+#[derive(Serialize, Deserialize, Clone)]
+pub struct ObjectDescription {
+    pub name: String,
+    pub shape: Shape,
+    /// name of one of the scene's materials, a plain white one when there is none
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub material: Option<String>,
+
+    #[serde(default)]
+    pub position: [f32; 3],
+    /// degrees around x, y and z, applied in the order y, x, z
+    #[serde(default)]
+    pub rotation: [f32; 3],
+    #[serde(default = "unit_scale")]
+    pub scale: [f32; 3],
+    /// positions relative to the object, it is drawn once at each of them. Without any it is
+    /// drawn once where it is
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub instances: Vec<[f32; 3]>,
+}
+
+fn unit_scale() -> [f32; 3] {
+    [1.0; 3]
+}
+
+/// only the version, read before the rest to know how to read the rest
+#[derive(Deserialize)]
+#[serde(rename = "Scene")]
+struct Header {
+    version: u32,
+}
+
+impl Default for Scene {
+    /// nothing but a camera and the sun, what the stage starts with when there is no scene
+    fn default() -> Self {
+        Self {
+            version: SCENE_VERSION,
+            camera: CameraDescription {
+                position: [0.0, 0.0, 10.0],
+                yaw_pitch_roll: [0.0; 3],
+            },
+            light: LightDescription {
+                direction: [-0.4, -1.0, -0.3],
+                color: [1.0; 3],
+                intensity: 3.0,
+            },
+            environment: EnvironmentDescription {
+                cubemap: None,
+                enabled: true,
+                intensity: 1.0,
+            },
+            materials: vec![],
+            objects: vec![],
+        }
+    }
+}
+
+impl Scene {
+    pub fn load(file: &Path) -> Result<Scene, String> {
+        let source = std::fs::read_to_string(file)
+            .map_err(|e| format!("could not read {}: {}", file.display(), e))?;
+        return Self::parse(&source, &file.display().to_string());
+    }
+
+    /// `origin` names the source in errors
+    pub fn parse(source: &str, origin: &str) -> Result<Scene, String> {
+        let header: Header = ron::from_str(source).map_err(|e| format!("{}:{}", origin, e))?;
+
+        match header.version {
+            SCENE_VERSION => ron::from_str(source).map_err(|e| format!("{}:{}", origin, e)),
+            version if version > SCENE_VERSION => Err(format!(
+                "{} is version {}, this build reads up to version {}",
+                origin, version, SCENE_VERSION
+            )),
+            version => Err(format!(
+                "{} is version {}, which never existed",
+                origin, version
+            )),
+        }
+    }
+
+    pub fn save(&self, file: &Path) -> Result<(), String> {
+        let text = ron::ser::to_string_pretty(self, ron::ser::PrettyConfig::new())
+            .map_err(|e| format!("could not write scene: {}", e))?;
+
+        if let Some(folder) = file.parent()
+            && !folder.as_os_str().is_empty()
+        {
+            std::fs::create_dir_all(folder)
+                .map_err(|e| format!("could not create {}: {}", folder.display(), e))?;
+        }
+        std::fs::write(file, text)
+            .map_err(|e| format!("could not write {}: {}", file.display(), e))?;
+        return Ok(());
+    }
+
+    /// The objects `object` turns into, more than one for models. What is missing is added to
+    /// `problems` and left out
+    pub fn build_object(
+        &self,
+        object: &ObjectDescription,
+        problems: &mut Vec<String>,
+    ) -> Vec<MeshObject> {
+        let mesh = match &object.shape {
+            Shape::Cube { size } => Mesh::cube(*size),
+            Shape::Sphere { radius } => Mesh::uv_sphere(*radius, 32, 16),
+            Shape::Plane { size, uv_scale } => Mesh::plane(*size, *uv_scale),
+            Shape::Model { path } => return self.build_model(object, path, problems),
+        };
+
+        let material = match &object.material {
+            Some(name) => match self.materials.iter().find(|m| m.name == *name) {
+                Some(material) => material.build(problems),
+                None => {
+                    problems.push(format!("{}: there is no material {}", object.name, name));
+                    Material::new(object.name.clone())
+                }
+            },
+            None => Material::new(object.name.clone()),
+        };
+
+        let mut mesh_object = MeshObject::new(mesh, material, object.transform());
+        mesh_object.instances = object.instance_transforms();
+        return vec![mesh_object];
+    }
+
+    fn build_model(
+        &self,
+        object: &ObjectDescription,
+        path: &str,
+        problems: &mut Vec<String>,
+    ) -> Vec<MeshObject> {
+        if !Path::new(path).is_file() {
+            problems.push(format!("{}: missing model {}", object.name, path));
+            return vec![];
+        }
+
+        let mut parts = match meshes::load_model(path) {
+            Ok(parts) => parts,
+            Err(e) => {
+                problems.push(format!("{}: {}", object.name, e));
+                return vec![];
+            }
+        };
+
+        // textures of the model are not checked before they are used, a missing one would panic
+        for part in parts.iter_mut() {
+            for texture in texture_slots(&mut part.material) {
+                forget_if_missing(texture, &object.name, problems);
+            }
+
+            // every part is placed at the object, where it sits in the model goes into its
+            // instances. Moving the object moves all of its parts that way
+            let in_model = part.transform;
+            part.transform = object.transform();
+            part.instances = match object.instance_transforms() {
+                instances if instances.is_empty() => vec![in_model],
+                instances => instances
+                    .into_iter()
+                    .map(|instance| instance * in_model)
+                    .collect(),
+            };
+        }
+        return parts;
+    }
+}
+
+impl CameraDescription {
+    pub fn from_camera(cam: &Camera) -> Self {
+        let (yaw, pitch, roll) = cam.yaw_pitch_roll();
+        Self {
+            position: cam.camera_pos.to_array(),
+            // no -0.0 in the file
+            yaw_pitch_roll: [yaw, pitch, roll].map(|angle| angle + 0.0),
+        }
+    }
+
+    pub fn apply(&self, cam: &mut Camera) {
+        let [yaw, pitch, roll] = self.yaw_pitch_roll;
+        cam.camera_pos = glam::Vec3::from_array(self.position);
+        cam.set_yaw_pitch_roll(yaw, pitch, roll);
+    }
+}
+
+impl LightDescription {
+    pub fn from_light(light: &DirectionalLight) -> Self {
+        Self {
+            direction: light.direction.to_array(),
+            color: light.color.to_array(),
+            intensity: light.intensity,
+        }
+    }
+
+    pub fn build(&self) -> DirectionalLight {
+        DirectionalLight {
+            direction: glam::Vec3::from_array(self.direction),
+            color: glam::Vec3::from_array(self.color),
+            intensity: self.intensity,
+        }
+    }
+}
+
+impl EnvironmentDescription {
+    /// a cubemap with missing faces is reported and replaced by the ambient color
+    pub fn build(&self, problems: &mut Vec<String>) -> Environment {
+        let cubemap = self.cubemap.clone().filter(|cubemap| {
+            let missing = Environment::missing_faces(cubemap);
+            if !missing.is_empty() {
+                let missing: Vec<String> =
+                    missing.iter().map(|p| p.display().to_string()).collect();
+                problems.push(format!("environment: missing {}", missing.join(", ")));
+            }
+            missing.is_empty()
+        });
+
+        let mut environment = Environment::new(cubemap);
+        environment.enabled = self.enabled;
+        environment.intensity = self.intensity;
+        return environment;
+    }
+}
+
+impl Default for MaterialDescription {
+    fn default() -> Self {
+        let material = Material::new(String::new());
+        Self {
+            name: material.name.clone(),
+            base_color: material.base_color_factor.to_array(),
+            metallic: material.metallic_factor,
+            roughness: material.roughness_factor,
+            emissive: material.emissive_factor.to_array(),
+            normal_scale: material.normal_scale,
+            occlusion_strength: material.occlusion_strength,
+            base_color_texture: None,
+            metallic_roughness_texture: None,
+            normal_texture: None,
+            occlusion_texture: None,
+            emissive_texture: None,
+            filter: material.filter,
+        }
+    }
+}
+
+impl MaterialDescription {
+    /// textures that are not there are reported and left empty
+    pub fn build(&self, problems: &mut Vec<String>) -> Material {
+        let mut material = Material::new(self.name.clone());
+        material.base_color_factor = glam::Vec4::from_array(self.base_color);
+        material.metallic_factor = self.metallic;
+        material.roughness_factor = self.roughness;
+        material.emissive_factor = glam::Vec3::from_array(self.emissive);
+        material.normal_scale = self.normal_scale;
+        material.occlusion_strength = self.occlusion_strength;
+
+        material.base_color_texture = self.base_color_texture.clone();
+        material.metallic_roughness_texture = self.metallic_roughness_texture.clone();
+        material.normal_texture = self.normal_texture.clone();
+        material.occlusion_texture = self.occlusion_texture.clone();
+        material.emissive_texture = self.emissive_texture.clone();
+        material.filter = self.filter;
+
+        let name = format!("material {}", self.name);
+        for texture in texture_slots(&mut material) {
+            forget_if_missing(texture, &name, problems);
+        }
+        return material;
+    }
+}
+
+impl ObjectDescription {
+    /// object into world space
+    pub fn transform(&self) -> glam::Mat4 {
+        let [x, y, z] = self.rotation.map(f32::to_radians);
+        glam::Mat4::from_scale_rotation_translation(
+            glam::Vec3::from_array(self.scale),
+            glam::Quat::from_euler(glam::EulerRot::YXZ, y, x, z),
+            glam::Vec3::from_array(self.position),
+        )
+    }
+
+    /// Take over where `transform` puts the object. Nothing changes when it is where the
+    /// description already puts it, so saving does not round the numbers of unmoved objects
+    pub fn set_transform(&mut self, transform: glam::Mat4) {
+        if transform.abs_diff_eq(self.transform(), 1e-5) {
+            return;
+        }
+
+        let (scale, rotation, position) = transform.to_scale_rotation_translation();
+        let (y, x, z) = rotation.to_euler(glam::EulerRot::YXZ);
+        self.position = position.to_array();
+        // no -0.0 in the file
+        self.rotation = [x, y, z].map(|angle| angle.to_degrees() + 0.0);
+        self.scale = scale.to_array();
+    }
+
+    /// instances relative to the object, see MeshObject::instances
+    fn instance_transforms(&self) -> Vec<glam::Mat4> {
+        self.instances
+            .iter()
+            .map(|offset| glam::Mat4::from_translation(glam::Vec3::from_array(*offset)))
+            .collect()
+    }
+}
+
+fn texture_slots(material: &mut Material) -> [&mut Option<String>; 5] {
+    [
+        &mut material.base_color_texture,
+        &mut material.metallic_roughness_texture,
+        &mut material.normal_texture,
+        &mut material.occlusion_texture,
+        &mut material.emissive_texture,
+    ]
+}
+
+/// loading a texture that is not there panics, it falls back to the default texture instead
+fn forget_if_missing(texture: &mut Option<String>, user: &str, problems: &mut Vec<String>) {
+    if let Some(name) = texture
+        && !textures::sprite_path(name).is_file()
+    {
+        problems.push(format!("{}: missing texture {}", user, name));
+        *texture = None;
+    }
+}
@@ -0,0 +1,374 @@
+// Rhai scripts for scene setup and simple behaviors. ./scripts/main.rhai runs at startup and again
+// whenever a script in its folder changes. Its top level spawns objects and registers callbacks
+// with on_frame that run once per simulation step. Scripts never touch the stage directly, what
+// they do is queued in ScriptState and the stage applies it after they ran
+use std::{cell::RefCell, collections::HashMap, path::PathBuf, rc::Rc};
+
+use glam::{EulerRot, Mat4, Quat, Vec3};
+use rhai::{AST, Dynamic, Engine, EvalAltResult, FnPtr, Scope};
+
+use crate::{actions::ActionMap, file_watch::FolderWatch, meshes};
+
+pub const DEFAULT_SCRIPT: &str = "./scripts/main.rhai";
+
+/// what add_object accepts besides the path of a model, spawn is a reserved word in rhai, the
+/// stage knows how to build them
+pub const SHAPES: [&str; 3] = ["cube", "sphere", "plane"];
+
+/// where a scripted object is, rotation in degrees around x, y and z
+#[derive(Debug, Clone, Copy)]
+struct ScriptTransform {
+    position: Vec3,
+    rotation: Vec3,
+    scale: f32,
+}
+
+impl ScriptTransform {
+    fn matrix(&self) -> Mat4 {
+        let rotation = Quat::from_euler(
+            EulerRot::YXZ,
+            self.rotation.y.to_radians(),
+            self.rotation.x.to_radians(),
+            self.rotation.z.to_radians(),
+        );
+        Mat4::from_scale_rotation_translation(Vec3::splat(self.scale), rotation, self.position)
+    }
+}
+
+/// an object a script asked for
+pub struct Spawn {
+    pub id: i64,
+    /// one of SHAPES or the path of an .obj, .gltf or .glb file
+    pub shape: String,
+    /// name of one of the scene's materials, like the material of a scene object
+    pub material: Option<String>,
+    pub transform: Mat4,
+}
+
+/// Everything the registered functions share with the host
+#[derive(Default)]
+struct ScriptState {
+    next_id: i64,
+    transforms: HashMap<i64, ScriptTransform>,
+    spawned: Vec<Spawn>,
+    /// objects whose transform changed since the stage last looked
+    moved: Vec<i64>,
+    callbacks: Vec<FnPtr>,
+
+    /// the input of the current frame
+    active: Vec<String>,
+    pressed: Vec<String>,
+    axes: HashMap<String, f32>,
+    elapsed: f64,
+}
+
+type ScriptResult<T> = Result<T, Box<EvalAltResult>>;
+
+/// The engine, the loaded script and what it registered
+pub struct ScriptHost {
+    pub file: PathBuf,
+
+    engine: Engine,
+    ast: Option<AST>,
+    state: Rc<RefCell<ScriptState>>,
+    /// None when not watching, e.g. headless
+    watch: Option<FolderWatch>,
+    reload_requested: bool,
+}
+
+impl ScriptHost {
+    /// Set up the engine for `file` and watch its folder when `watch` is set. Nothing runs before
+    /// the first reload
+    pub fn new(file: PathBuf, watch: bool) -> Self {
+        let state = Rc::new(RefCell::new(ScriptState::default()));
+        let mut engine = Engine::new();
+        register_api(&mut engine, &state);
+
+        let watch = watch
+            .then(|| FolderWatch::containing(&file))
+            .and_then(|watch| {
+                watch
+                    .inspect_err(|e| log::warn!("not watching {}: {}", file.display(), e))
+                    .ok()
+            });
+
+        Self {
+            file,
+            engine,
+            ast: None,
+            state,
+            watch,
+            reload_requested: true,
+        }
+    }
+
+    /// run the script again with the next reload_if_needed
+    pub fn request_reload(&mut self) {
+        self.reload_requested = true;
+    }
+
+    /// Compile and run the script if it changed or a reload was requested. Returns true when the
+    /// new one replaced the old one, the objects of the old one have to go then. A script that
+    /// does not compile leaves the old one running
+    pub fn reload_if_needed(&mut self) -> bool {
+        let changed = self.watch.as_ref().is_some_and(|watch| {
+            watch.changed(|path| path.extension().is_some_and(|e| e == "rhai"))
+        });
+        if !std::mem::take(&mut self.reload_requested) && !changed {
+            return false;
+        }
+
+        if !self.file.exists() {
+            if self.ast.is_none() {
+                log::info!("no script at {}", self.file.display());
+            }
+            return false;
+        }
+
+        let ast = match self.engine.compile_file(self.file.clone()) {
+            Ok(ast) => ast,
+            Err(e) => {
+                log::error!("{}: {}", self.file.display(), e);
+                return false;
+            }
+        };
+
+        {
+            let mut state = self.state.borrow_mut();
+            state.transforms.clear();
+            state.spawned.clear();
+            state.moved.clear();
+            state.callbacks.clear();
+        }
+
+        if let Err(e) = self.engine.run_ast_with_scope(&mut Scope::new(), &ast) {
+            log::error!("{}: {}", self.file.display(), e);
+        }
+        self.ast = Some(ast);
+        log::info!("ran {}", self.file.display());
+        return true;
+    }
+
+    /// Call the on_frame callbacks with the length of one simulation `step`. One that fails is
+    /// reported and not called again
+    pub fn run_step(&mut self, actions: &ActionMap, step: f64, elapsed: f64) {
+        let Some(ast) = &self.ast else {
+            return;
+        };
+
+        let callbacks = {
+            let mut state = self.state.borrow_mut();
+            state.active = actions
+                .bindings
+                .actions
+                .iter()
+                .filter(|action| actions.is_active(&action.name))
+                .map(|action| action.name.clone())
+                .collect();
+            state.pressed = actions.pressed_actions();
+            state.axes = actions
+                .bindings
+                .axes
+                .iter()
+                .map(|axis| (axis.name.clone(), actions.axis(&axis.name)))
+                .collect();
+            state.elapsed = elapsed;
+            std::mem::take(&mut state.callbacks)
+        };
+
+        let mut kept = vec![];
+        for callback in callbacks {
+            match callback.call::<Dynamic>(&self.engine, ast, (step,)) {
+                Ok(_) => kept.push(callback),
+                Err(e) => log::error!("{}, on_frame({}) removed", e, callback.fn_name()),
+            }
+        }
+
+        // callbacks registered by callbacks come after the ones that were there
+        let mut state = self.state.borrow_mut();
+        kept.append(&mut state.callbacks);
+        state.callbacks = kept;
+    }
+
+    /// objects spawned since the last call
+    pub fn take_spawns(&mut self) -> Vec<Spawn> {
+        std::mem::take(&mut self.state.borrow_mut().spawned)
+    }
+
+    /// new transforms of objects moved since the last call
+    pub fn take_moves(&mut self) -> Vec<(i64, Mat4)> {
+        let mut state = self.state.borrow_mut();
+        let mut moved = std::mem::take(&mut state.moved);
+        moved.sort();
+        moved.dedup();
+        moved
+            .into_iter()
+            .filter_map(|id| Some((id, state.transforms.get(&id)?.matrix())))
+            .collect()
+    }
+}
+
+/// A number from a script, integers included so `vec3(0, 1.5, 2)` works
+fn number(value: Dynamic) -> ScriptResult<f32> {
+    if let Ok(float) = value.as_float() {
+        return Ok(float as f32);
+    }
+    if let Ok(int) = value.as_int() {
+        return Ok(int as f32);
+    }
+    return Err(format!("expected a number, found {}", value.type_name()).into());
+}
+
+/// Everything scripts can call
+fn register_api(engine: &mut Engine, state: &Rc<RefCell<ScriptState>>) {
+    engine.on_print(|text| log::info!("{}", text));
+    engine.on_debug(|text, source, position| {
+        log::info!("{} {}: {}", source.unwrap_or("script"), position, text)
+    });
+
+    engine
+        .register_type_with_name::<Vec3>("Vec3")
+        .register_fn(
+            "vec3",
+            |x: Dynamic, y: Dynamic, z: Dynamic| -> ScriptResult<Vec3> {
+                Ok(Vec3::new(number(x)?, number(y)?, number(z)?))
+            },
+        )
+        .register_get_set(
+            "x",
+            |v: &mut Vec3| v.x as f64,
+            |v: &mut Vec3, x: f64| v.x = x as f32,
+        )
+        .register_get_set(
+            "y",
+            |v: &mut Vec3| v.y as f64,
+            |v: &mut Vec3, y: f64| v.y = y as f32,
+        )
+        .register_get_set(
+            "z",
+            |v: &mut Vec3| v.z as f64,
+            |v: &mut Vec3, z: f64| v.z = z as f32,
+        )
+        .register_fn("+", |a: Vec3, b: Vec3| a + b)
+        .register_fn("-", |a: Vec3, b: Vec3| a - b)
+        .register_fn("*", |a: Vec3, f: Dynamic| -> ScriptResult<Vec3> {
+            Ok(a * number(f)?)
+        })
+        .register_fn("*", |f: Dynamic, a: Vec3| -> ScriptResult<Vec3> {
+            Ok(a * number(f)?)
+        })
+        .register_fn("length", |v: &mut Vec3| v.length() as f64)
+        .register_fn("to_string", |v: &mut Vec3| {
+            format!("vec3({}, {}, {})", v.x, v.y, v.z)
+        })
+        .register_fn("to_debug", |v: &mut Vec3| {
+            format!("vec3({}, {}, {})", v.x, v.y, v.z)
+        });
+
+    // add_object(shape, position) and add_object(shape, position, material)
+    fn add_object(
+        state: &Rc<RefCell<ScriptState>>,
+        shape: &str,
+        position: Vec3,
+        material: Option<String>,
+    ) -> ScriptResult<i64> {
+        if !SHAPES.contains(&shape) && !meshes::is_model(shape) {
+            return Err(format!(
+                "can not add {}, only {} or an .obj, .gltf or .glb file",
+                shape,
+                SHAPES.join(", ")
+            )
+            .into());
+        }
+
+        let mut state = state.borrow_mut();
+        let id = state.next_id;
+        state.next_id += 1;
+
+        let transform = ScriptTransform {
+            position,
+            rotation: Vec3::ZERO,
+            scale: 1.0,
+        };
+        state.transforms.insert(id, transform);
+        state.spawned.push(Spawn {
+            id,
+            shape: shape.to_owned(),
+            material,
+            transform: transform.matrix(),
+        });
+        return Ok(id);
+    }
+
+    let s = state.clone();
+    engine.register_fn("add_object", move |shape: &str, position: Vec3| {
+        add_object(&s, shape, position, None)
+    });
+    let s = state.clone();
+    engine.register_fn(
+        "add_object",
+        move |shape: &str, position: Vec3, material: &str| {
+            add_object(&s, shape, position, Some(material.to_owned()))
+        },
+    );
+
+    // setters and getters of the transform, all of them fail for ids that were never spawned
+    fn with_transform<R>(
+        state: &Rc<RefCell<ScriptState>>,
+        id: i64,
+        f: impl FnOnce(&mut ScriptTransform) -> R,
+    ) -> ScriptResult<R> {
+        let mut state = state.borrow_mut();
+        let Some(transform) = state.transforms.get_mut(&id) else {
+            return Err(format!("no object {}", id).into());
+        };
+        let result = f(transform);
+        state.moved.push(id);
+        return Ok(result);
+    }
+
+    let s = state.clone();
+    engine.register_fn("set_position", move |id: i64, position: Vec3| {
+        with_transform(&s, id, |t| t.position = position)
+    });
+    let s = state.clone();
+    engine.register_fn("set_rotation", move |id: i64, degrees: Vec3| {
+        with_transform(&s, id, |t| t.rotation = degrees)
+    });
+    let s = state.clone();
+    engine.register_fn("set_scale", move |id: i64, scale: Dynamic| {
+        let scale = number(scale)?;
+        with_transform(&s, id, |t| t.scale = scale)
+    });
+    let s = state.clone();
+    engine.register_fn("position", move |id: i64| {
+        with_transform(&s, id, |t| t.position)
+    });
+    let s = state.clone();
+    engine.register_fn("rotation", move |id: i64| {
+        with_transform(&s, id, |t| t.rotation)
+    });
+
+    let s = state.clone();
+    engine.register_fn("on_frame", move |callback: FnPtr| {
+        s.borrow_mut().callbacks.push(callback);
+    });
+
+    let s = state.clone();
+    engine.register_fn("is_active", move |action: &str| {
+        s.borrow().active.iter().any(|a| a == action)
+    });
+    let s = state.clone();
+    engine.register_fn("just_pressed", move |action: &str| {
+        s.borrow().pressed.iter().any(|a| a == action)
+    });
+    let s = state.clone();
+    engine.register_fn("axis", move |name: &str| -> ScriptResult<f64> {
+        match s.borrow().axes.get(name) {
+            Some(value) => Ok(*value as f64),
+            None => Err(format!("unknown axis {}", name).into()),
+        }
+    });
+    let s = state.clone();
+    engine.register_fn("time", move || s.borrow().elapsed);
+}
@@ -3,17 +3,53 @@ use futures::{
     channel::mpsc::{Receiver, channel},
     executor::block_on,
 };
-use miniquad::ShaderSource;
+use miniquad::{ShaderError, ShaderSource};
 use notify::{
     Config, Event, EventKind, RecommendedWatcher, RecursiveMode, Watcher, event::ModifyKind,
 };
-use std::fs;
+use std::{
+    cell::RefCell,
+    fs,
+    rc::{Rc, Weak},
+};
+
+/// What the debug ui shows about a shader, shared between the ShaderFile and the registry
+#[derive(Debug, Clone)]
+pub struct ShaderStatus {
+    pub name: String,
+    pub vert_path: String,
+    pub frag_path: String,
+
+    /// how often the code was read from disk again after the first load
+    pub reloads: u32,
+    /// the error of the last compilation, None when it compiled fine or was never compiled
+    pub error: Option<String>,
+
+    /// reload on the next `reload_if_needed` even if the files did not change
+    pub reload_requested: bool,
+}
+
+thread_local! {
+    /// every ShaderFile that is alive, dropped ones are skipped and cleaned up lazily
+    static REGISTRY: RefCell<Vec<Weak<RefCell<ShaderStatus>>>> = const { RefCell::new(vec![]) };
+}
+
+/// status of all loaded shaders in the order they were created
+pub fn loaded_shaders() -> Vec<Rc<RefCell<ShaderStatus>>> {
+    REGISTRY.with_borrow_mut(|registry| {
+        registry.retain(|status| status.strong_count() > 0);
+        registry
+            .iter()
+            .filter_map(|status| status.upgrade())
+            .collect()
+    })
+}
 
 /**
 * Load a shader from  disk and automatically convert it to formats that miniquad can understand
 */
 pub struct ShaderFile {
-    _name: String,
+    status: Rc<RefCell<ShaderStatus>>,
 
     frag_path: String,
     vert_path: String,
@@ -36,13 +72,29 @@ pub struct ShaderFile {
 */
 impl ShaderFile {
     pub fn new(basename: String) -> Self {
-        let frag_path = format!("./shaders/{}.frag.glsl", basename);
-        let vert_path = format!("./shaders/{}.vert.glsl", basename);
+        Self::from_files(basename.clone(), &basename, &basename)
+    }
+
+    /// Use the vertex and fragment shader of different basenames, for shaders that share a vertex
+    /// stage like the fullscreen post processing passes
+    pub fn from_files(name: String, vert_basename: &str, frag_basename: &str) -> Self {
+        let frag_path = format!("./shaders/{}.frag.glsl", frag_basename);
+        let vert_path = format!("./shaders/{}.vert.glsl", vert_basename);
 
         let (mut tx_change_detected, rx_change_detected) = channel(1);
 
+        let status = Rc::new(RefCell::new(ShaderStatus {
+            name,
+            vert_path: vert_path.clone(),
+            frag_path: frag_path.clone(),
+            reloads: 0,
+            error: None,
+            reload_requested: false,
+        }));
+        REGISTRY.with_borrow_mut(|registry| registry.push(Rc::downgrade(&status)));
+
         let mut ret = ShaderFile {
-            _name: basename,
+            status,
             vertex_string_contents: String::from(""),
             fragment_string_contents: String::from(""),
             frag_path: frag_path.clone(),
@@ -90,15 +142,9 @@ impl ShaderFile {
                 match res {
                     Ok(event) => {
                         match event.kind {
-                            EventKind::Modify(modify_event) => {
-                                // println!("modify event {:?}", modify_event);
-                                match modify_event {
-                                    ModifyKind::Metadata(..) => {
-                                        // println!("Shader must be reloaded!");
-                                        drop(block_on(tx_change_detected.send(true)));
-                                    }
-                                    _ => (),
-                                }
+                            EventKind::Modify(ModifyKind::Metadata(..)) => {
+                                // println!("Shader must be reloaded!");
+                                drop(block_on(tx_change_detected.send(true)));
                             }
                             EventKind::Remove(..) => {
                                 // Vim fully removes files when writing; that means it destroys the
@@ -111,7 +157,7 @@ impl ShaderFile {
                                     // which will crash if we do it twice since its not watching it
                                     // anymore at this point
                                     watcher
-                                        .watch(&filepath, RecursiveMode::NonRecursive)
+                                        .watch(filepath, RecursiveMode::NonRecursive)
                                         .unwrap();
                                 }
                             }
@@ -131,17 +177,37 @@ impl ShaderFile {
      * reloaded
      */
     pub fn reload_if_needed(&mut self) -> bool {
-        match self.rx_file_watcher_detect_change.try_next() {
-            Ok(None) => {}
-            Ok(_) => {
-                self.load_from_disk();
-                return true;
-            }
-            Err(_) => {}
+        let requested = std::mem::take(&mut self.status.borrow_mut().reload_requested);
+
+        let changed = match self.rx_file_watcher_detect_change.try_next() {
+            Ok(None) => false,
+            Ok(_) => true,
+            Err(_) => false,
+        };
+
+        if changed || requested {
+            self.load_from_disk();
+            self.status.borrow_mut().reloads += 1;
+            return true;
         }
         return false;
     }
 
+    /// Remember whether compiling the current code worked, so it can be shown in the debug ui
+    pub fn report_compile<T>(&self, result: &Result<T, ShaderError>) {
+        let mut status = self.status.borrow_mut();
+        status.error = result.as_ref().err().map(|e| format!("{:?}", e));
+        if let Some(e) = &status.error {
+            log::error!("shader {}: {}", status.name, e);
+        }
+    }
+
+    /// whether the last compile of the current code failed, compiling it again is pointless
+    /// until it changes on disk
+    pub fn compile_failed(&self) -> bool {
+        return self.status.borrow().error.is_some();
+    }
+
     /** Load the shader from disk and into strings to hold the text information inside of em
      */
     fn load_from_disk(&mut self) {
@@ -155,7 +221,7 @@ impl ShaderFile {
             fs::read_to_string(vert_path).expect("Should have been able to read the file");
     }
 
-    pub fn get_shadersource(&self) -> ShaderSource {
+    pub fn get_shadersource(&self) -> ShaderSource<'_> {
         return ShaderSource::Glsl {
             vertex: &self.vertex_string_contents,
             fragment: &self.fragment_string_contents,
@@ -1,94 +1,228 @@
 use glam::*;
 
+use super::projection::{Projection, ProjectionTransition};
+use crate::bounds::Ray;
+
+#[derive(Clone)]
 pub struct Camera {
     pub camera_pos: Vec3,
-    pub camera_front: Vec3, // where the camera is looking at
-    pub camera_up: Vec3,    // relative 'up' for the camera
-
-    pub pitch: f32,
-    pub yaw: f32,
+    // rotation from camera space, which looks along -z with y up, into world space
+    pub orientation: Quat,
 
-    // multiplied with the direction the camera is moving to
-    // most likly applied per second
+    // top speed of the free-fly controller in units per second
     pub camera_speed: f32,
 
-    // the current projection matrix of the camera, may be orthogonal
-    // or perspective
-    pub fov_y_deg: f32,
+    // the current projection of the camera, may be orthogonal
+    // or perspective. Change it with set_projection to blend over
+    pub projection: Projection,
     pub aspect_ratio: f32,
-    pub z_near: f32,
-    pub z_far: f32,
+
+    // running blend from the previous projection, advanced by update_projection
+    pub transition: Option<ProjectionTransition>,
 }
 
-// implement camera movement
+/// the part of the camera the simulation moves
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct CameraPose {
+    pub position: Vec3,
+    pub orientation: Quat,
+}
+
+// direction helpers
 impl Camera {
-    pub fn move_forward(&mut self, time_delta: f32) {
-        self.camera_pos = self.camera_pos + (self.camera_front * self.camera_speed * time_delta);
+    /// unit vector of where the camera is looking
+    pub fn front(&self) -> Vec3 {
+        self.orientation * Vec3::NEG_Z
     }
 
-    pub fn move_backwards(&mut self, time_delta: f32) {
-        self.camera_pos = self.camera_pos - (self.camera_front * self.camera_speed * time_delta);
+    /// unit vector pointing to the top of the screen, tilts with roll
+    pub fn up(&self) -> Vec3 {
+        self.orientation * Vec3::Y
     }
 
-    pub fn move_left(&mut self, time_delta: f32) {
-        self.camera_pos = self.camera_pos
-            - self.camera_front.cross(self.camera_up).normalize() * self.camera_speed * time_delta;
+    /// unit vector pointing to the right of where the camera looks
+    pub fn right(&self) -> Vec3 {
+        self.orientation * Vec3::X
     }
+}
 
-    pub fn move_right(&mut self, time_delta: f32) {
-        self.camera_pos = self.camera_pos
-            + self.camera_front.cross(self.camera_up).normalize() * self.camera_speed * time_delta;
-    }
+// implement camera rotation
+impl Camera {
+    /**
+     * Orientation as angles in degrees, applied in the order yaw, pitch, roll.
+     * Yaw 0 looks along -z and grows turning right, pitch grows looking up and roll grows
+     * tilting the view to the right. Near a pitch of +-90 yaw and roll are the same rotation,
+     * the split between them is arbitrary there
+     */
+    pub fn yaw_pitch_roll(&self) -> (f32, f32, f32) {
+        let (yaw, pitch, roll) = self.orientation.to_euler(EulerRot::YXZ);
 
-    pub fn move_up(&mut self, time_delta: f32) {
-        self.camera_pos = self.camera_pos + (self.camera_up * self.camera_speed * time_delta);
+        return (-yaw.to_degrees(), pitch.to_degrees(), -roll.to_degrees());
     }
 
-    pub fn move_down(&mut self, time_delta: f32) {
-        self.camera_pos = self.camera_pos - (self.camera_up * self.camera_speed * time_delta);
+    pub fn set_yaw_pitch_roll(&mut self, yaw: f32, pitch: f32, roll: f32) {
+        self.orientation = Quat::from_euler(
+            EulerRot::YXZ,
+            -yaw.to_radians(),
+            pitch.to_radians(),
+            -roll.to_radians(),
+        );
     }
 
     /**
-     * Add delta to actual pitch values and recalculate the "camera_front" vector
-     * Which effectively means "rotate the current view by pitch/yaw"
-     * This is relative to the coordinate system, the camera has setup, so its relative to its
-     * front/up vectors.
-     * pitch is clamped at -89 and 89 in order to not infinitely flip the camera
+     * First person mouse look: yaw turns around the world up axis and pitch is clamped at -89
+     * and 89 in order to not infinitely flip the camera. Any roll is leveled out
      */
     pub fn change_pitch_yaw(&mut self, delta_yaw: f32, delta_pitch: f32) {
-        if delta_yaw == 0.0 && delta_pitch == 0.0 {
-            return;
+        let (yaw, pitch, _) = self.yaw_pitch_roll();
+
+        self.set_yaw_pitch_roll(
+            yaw + delta_yaw,
+            (pitch + delta_pitch).clamp(-89.0, 89.0),
+            0.0,
+        );
+    }
+
+    /// Turn around the camera's own axes, in degrees. Nothing is clamped so the camera can
+    /// loop and roll freely, like an aircraft
+    pub fn rotate_local(&mut self, delta_yaw: f32, delta_pitch: f32, delta_roll: f32) {
+        let rotation = Quat::from_euler(
+            EulerRot::YXZ,
+            -delta_yaw.to_radians(),
+            delta_pitch.to_radians(),
+            -delta_roll.to_radians(),
+        );
+
+        self.orientation = (self.orientation * rotation).normalize();
+    }
+
+    /// Turn the camera towards `target` with `up` at the top of the screen. Looking straight
+    /// along `up` keeps the current up direction instead, see look_rotation
+    pub fn look_at(&mut self, target: Vec3, up: Vec3) {
+        if let Some(orientation) = look_rotation(target - self.camera_pos, up, self.up()) {
+            self.orientation = orientation;
         }
-        self.yaw += delta_yaw;
-        self.pitch += delta_pitch;
+    }
 
-        self.pitch = self.pitch.clamp(-89.0, 89.0);
+    /// rotate the fraction `t` of the way towards `target` along the shortest arc
+    pub fn slerp_orientation(&mut self, target: Quat, t: f32) {
+        self.orientation = self.orientation.slerp(target, t).normalize();
+    }
+}
 
-        let direction = Vec3 {
-            x: self.yaw.to_radians().cos() * self.pitch.to_radians().cos(),
-            y: self.pitch.to_radians().sin(),
-            z: self.yaw.to_radians().sin() * self.pitch.to_radians().cos(),
-        };
+// render interpolation
+impl Camera {
+    pub fn pose(&self) -> CameraPose {
+        CameraPose {
+            position: self.camera_pos,
+            orientation: self.orientation,
+        }
+    }
 
-        self.camera_front = direction.normalize();
+    /// The camera `alpha` of the way from `previous` to where it is now, what a frame between
+    /// two simulation steps shows
+    pub fn interpolated(&self, previous: CameraPose, alpha: f32) -> Camera {
+        Camera {
+            camera_pos: previous.position.lerp(self.camera_pos, alpha),
+            orientation: previous
+                .orientation
+                .slerp(self.orientation, alpha)
+                .normalize(),
+            ..self.clone()
+        }
     }
 }
 
+/**
+ * Orientation that looks along `direction` with `up` pointing to the top of the screen.
+ * `up` has no effect when it is parallel to `direction`, `fallback_up` is used then, which
+ * should be the current up so the view does not spin when passing the pole.
+ * None if `direction` has no length
+ */
+pub fn look_rotation(direction: Vec3, up: Vec3, fallback_up: Vec3) -> Option<Quat> {
+    let forward = direction.try_normalize()?;
+
+    let right = forward
+        .cross(up)
+        .try_normalize()
+        .or_else(|| forward.cross(fallback_up).try_normalize())
+        .unwrap_or_else(|| forward.any_orthonormal_vector());
+    let up = right.cross(forward);
+
+    return Some(Quat::from_mat3(&Mat3::from_cols(right, up, -forward)));
+}
+
 impl Camera {
     pub fn get_view_matrix(&self) -> glam::f32::Mat4 {
-        glam::f32::Mat4::look_at_rh(
-            self.camera_pos,
-            self.camera_pos + self.camera_front,
-            self.camera_up,
-        )
-    }
-
-    pub fn get_perspective_matrix(&self) -> glam::f32::Mat4 {
-        glam::f32::Mat4::perspective_rh(
-            self.fov_y_deg.to_radians(),
-            self.aspect_ratio,
-            self.z_near,
-            self.z_far,
-        )
+        glam::f32::Mat4::look_to_rh(self.camera_pos, self.front(), self.up())
+    }
+
+    /// the projection for the current frame, blended while a projection change is running
+    pub fn projection_matrix(&self) -> glam::f32::Mat4 {
+        let matrix = self.projection.matrix(self.aspect_ratio);
+
+        match &self.transition {
+            Some(transition) => transition.blend(matrix),
+            None => matrix,
+        }
+    }
+
+    /**
+     * World space ray through a cursor position in pixels, top left being 0,0, for a screen of
+     * `screen_size` pixels. Starts on the near plane, so orthographic rays are parallel.
+     * The direction has a length of 1
+     */
+    pub fn screen_ray(&self, cursor: Vec2, screen_size: Vec2) -> Ray {
+        let ndc = vec2(
+            cursor.x / screen_size.x * 2.0 - 1.0,
+            1.0 - cursor.y / screen_size.y * 2.0,
+        );
+
+        // halfway in depth instead of the far plane, which is at infinity for some projections
+        let near_depth = if self.reversed_z() { 1.0 } else { 0.0 };
+        let inverse = (self.projection_matrix() * self.get_view_matrix()).inverse();
+        let near = inverse.project_point3(ndc.extend(near_depth));
+        let further = inverse.project_point3(ndc.extend(0.5));
+
+        return Ray {
+            origin: near,
+            direction: (further - near).normalize(),
+        };
+    }
+
+    /// depth compares the other way around, see Projection::InfinitePerspective
+    pub fn reversed_z(&self) -> bool {
+        self.projection.reversed_z()
+    }
+}
+
+// projection changes
+impl Camera {
+    /**
+     * Switch to another projection, blending over `duration` seconds.
+     * A change that starts or ends in reversed depth happens at once, depth halfway between the
+     * two conventions would be meaningless
+     */
+    pub fn set_projection(&mut self, projection: Projection, duration: f32) {
+        let reversed_z_changes = projection.reversed_z() != self.projection.reversed_z();
+
+        self.transition = (duration > 0.0 && !reversed_z_changes).then(|| ProjectionTransition {
+            // start from what is on screen right now so interrupting a blend does not jump
+            from: self.projection_matrix(),
+            elapsed: 0.0,
+            duration,
+        });
+        self.projection = projection;
+    }
+
+    /// advance a running projection blend by `time_delta` real seconds, unaffected by pause and
+    /// time scale
+    pub fn update_projection(&mut self, time_delta: f32) {
+        if let Some(transition) = self.transition.as_mut() {
+            transition.elapsed += time_delta;
+            if transition.elapsed >= transition.duration {
+                self.transition = None;
+            }
+        }
     }
 }
@@ -0,0 +1,268 @@
+use std::path::PathBuf;
+
+use glam::Vec3;
+
+use super::{Projection, Stage};
+use crate::{
+    console::{Command, Console, HasConsole, Value, Variable},
+    scene::{ObjectDescription, Shape},
+    shaders,
+};
+
+impl HasConsole for Stage {
+    fn console(self: &mut Self) -> &mut Console<Self> {
+        return &mut self.console;
+    }
+}
+
+/// Everything the console can do with the stage
+pub fn register_commands(console: &mut Console<Stage>) {
+    console.command(Command {
+        name: "action",
+        usage: "name",
+        help: "run a bound action as if its key was pressed, e.g. action screenshot",
+        run: |stage, args| {
+            let [name] = args else {
+                return Err("usage: action name".to_owned());
+            };
+            if !stage
+                .actions
+                .bindings
+                .actions
+                .iter()
+                .any(|a| a.name == *name)
+            {
+                return Err(format!("unknown action {}", name));
+            }
+            stage.run_command(name);
+            return Ok(String::new());
+        },
+    });
+    console.command(Command {
+        name: "reload_shaders",
+        usage: "",
+        help: "read every shader from disk again",
+        run: |_, _| {
+            let loaded = shaders::loaded_shaders();
+            for status in loaded.iter() {
+                status.borrow_mut().reload_requested = true;
+            }
+            return Ok(format!("reloading {} shaders", loaded.len()));
+        },
+    });
+    console.command(Command {
+        name: "reload_scripts",
+        usage: "",
+        help: "run the scripts again, replacing what they spawned",
+        run: |stage, _| {
+            stage.scripts.request_reload();
+            return Ok(format!("reloading {}", stage.scripts.file.display()));
+        },
+    });
+    console.command(Command {
+        name: "spawn",
+        usage: "cube|sphere x y z",
+        help: "add a unit sized object at a position to the scene",
+        run: |stage, args| {
+            let [shape_name, x, y, z] = args else {
+                return Err("usage: spawn cube|sphere x y z".to_owned());
+            };
+            let position = Value::Vec3(Vec3::ZERO).parse_like(&[x, y, z])?.as_vec3();
+            let shape = match *shape_name {
+                "cube" => Shape::Cube { size: 1.0 },
+                "sphere" => Shape::Sphere { radius: 0.5 },
+                _ => return Err(format!("can not spawn {}, only cube or sphere", shape_name)),
+            };
+
+            stage.add_to_scene(ObjectDescription {
+                name: format!("{} {}", shape_name, stage.scene.objects.len()),
+                shape,
+                material: None,
+                position: position.to_array(),
+                rotation: [0.0; 3],
+                scale: [1.0; 3],
+                instances: vec![],
+            })?;
+            return Ok(format!("spawned a {} at {}", shape_name, position));
+        },
+    });
+    console.command(Command {
+        name: "save_scene",
+        usage: "[file]",
+        help: "write the scene with the current camera and lighting, to the file it came from by default",
+        run: |stage, args| {
+            let file = match args {
+                [] => stage.scene_file.clone(),
+                [file] => PathBuf::from(file),
+                _ => return Err("usage: save_scene [file]".to_owned()),
+            };
+            stage.save_scene(file)?;
+            return Ok(format!("saved {}", stage.scene_file.display()));
+        },
+    });
+    console.command(Command {
+        name: "load_scene",
+        usage: "[file]",
+        help: "replace everything with a scene, the current one again by default",
+        run: |stage, args| {
+            let file = match args {
+                [] => stage.scene_file.clone(),
+                [file] => PathBuf::from(file),
+                _ => return Err("usage: load_scene [file]".to_owned()),
+            };
+            let missing = stage.load_scene(file)?;
+            return Ok(format!(
+                "loaded {}, {} missing assets",
+                stage.scene_file.display(),
+                missing
+            ));
+        },
+    });
+
+    console.variable(Variable {
+        name: "wireframe",
+        help: "draw triangle edges instead of faces",
+        get: |stage| Value::Bool(stage.settings.render_wireframe),
+        set: |stage, value| {
+            stage.settings.render_wireframe = value.as_bool();
+            return Ok(());
+        },
+    });
+    console.variable(Variable {
+        name: "bounds",
+        help: "show object bounds and labels",
+        get: |stage| Value::Bool(stage.settings.show_bounds),
+        set: |stage, value| {
+            stage.settings.show_bounds = value.as_bool();
+            return Ok(());
+        },
+    });
+    console.variable(Variable {
+        name: "reference_lines",
+        help: "show the axes, a grid and the sun direction",
+        get: |stage| Value::Bool(stage.settings.show_reference_lines),
+        set: |stage, value| {
+            stage.settings.show_reference_lines = value.as_bool();
+            return Ok(());
+        },
+    });
+    console.variable(Variable {
+        name: "debug_ui",
+        help: "show the debug panel",
+        get: |stage| Value::Bool(stage.debug_ui.visible),
+        set: |stage, value| {
+            if value.as_bool() != stage.debug_ui.visible {
+                stage.run_command("toggle_debug_ui");
+            }
+            return Ok(());
+        },
+    });
+    console.variable(Variable {
+        name: "mouse_sensitivity",
+        help: "pitch and yaw change per pixel moved",
+        get: |stage| Value::Float(stage.settings.mouse_sensitivity),
+        set: |stage, value| {
+            stage.settings.mouse_sensitivity = value.as_f32().clamp(0.01, 1.0);
+            return Ok(());
+        },
+    });
+
+    console.variable(Variable {
+        name: "cam.pos",
+        help: "camera position",
+        get: |stage| Value::Vec3(stage.world.cam.camera_pos),
+        set: |stage, value| {
+            stage.world.cam.camera_pos = value.as_vec3();
+            // jump there instead of sliding over from the last simulation step
+            stage.previous_camera_pose = stage.world.cam.pose();
+            let cam = stage.world.cam.clone();
+            stage.camera_controllers[stage.active_camera_controller].activate(&cam);
+            return Ok(());
+        },
+    });
+    console.variable(Variable {
+        name: "cam.speed",
+        help: "top speed in units per second",
+        get: |stage| Value::Float(stage.world.cam.camera_speed),
+        set: |stage, value| {
+            stage.world.cam.camera_speed = value.as_f32().max(0.1);
+            return Ok(());
+        },
+    });
+    console.variable(Variable {
+        name: "cam.fov",
+        help: "vertical field of view in degrees, perspective projections only",
+        get: |stage| match stage.world.cam.projection {
+            Projection::Perspective { fov_y_deg, .. }
+            | Projection::InfinitePerspective { fov_y_deg, .. } => Value::Float(fov_y_deg),
+            _ => Value::Float(0.0),
+        },
+        set: |stage, value| match &mut stage.world.cam.projection {
+            Projection::Perspective { fov_y_deg, .. }
+            | Projection::InfinitePerspective { fov_y_deg, .. } => {
+                *fov_y_deg = value.as_f32().clamp(1.0, 179.0);
+                Ok(())
+            }
+            projection => Err(format!("a {} projection has no fov", projection.name())),
+        },
+    });
+    console.variable(Variable {
+        name: "cam.z_near",
+        help: "near clip plane",
+        get: |stage| Value::Float(stage.world.cam.projection.view_volume().1),
+        set: |stage, value| {
+            let (_, _, z_far) = stage.world.cam.projection.view_volume();
+            let near = value.as_f32();
+            if near <= 0.0 || near >= z_far {
+                return Err(format!("has to be between 0 and z_far {}", z_far));
+            }
+            stage.world.cam.projection.set_clip_planes(Some(near), None);
+            return Ok(());
+        },
+    });
+    console.variable(Variable {
+        name: "cam.z_far",
+        help: "far clip plane, infinite perspective has none",
+        get: |stage| Value::Float(stage.world.cam.projection.view_volume().2),
+        set: |stage, value| {
+            if stage.world.cam.projection.reversed_z() {
+                return Err("an infinite perspective has no far plane".to_owned());
+            }
+            let (_, z_near, _) = stage.world.cam.projection.view_volume();
+            let far = value.as_f32();
+            if far <= z_near {
+                return Err(format!("has to be more than z_near {}", z_near));
+            }
+            stage.world.cam.projection.set_clip_planes(None, Some(far));
+            return Ok(());
+        },
+    });
+
+    console.variable(Variable {
+        name: "time.paused",
+        help: "stop the simulation",
+        get: |stage| Value::Bool(stage.time.paused),
+        set: |stage, value| {
+            stage.time.paused = value.as_bool();
+            return Ok(());
+        },
+    });
+    console.variable(Variable {
+        name: "time.scale",
+        help: "below 1 for slow motion",
+        get: |stage| Value::Float(stage.time.time_scale as f32),
+        set: |stage, value| {
+            stage.time.time_scale = value.as_f32().clamp(0.0, 4.0) as f64;
+            return Ok(());
+        },
+    });
+    console.variable(Variable {
+        name: "time.rate",
+        help: "simulation steps per second",
+        get: |stage| Value::Float(stage.time.simulation.rate() as f32),
+        set: |stage, value| {
+            stage.time.simulation.set_rate(value.as_f32() as f64);
+            return Ok(());
+        },
+    });
+}
@@ -0,0 +1,88 @@
+use glam::*;
+
+use super::{CameraController, smoothing_factor};
+use crate::{
+    actions::ActionMap,
+    debug_draw,
+    stage::{Camera, camera::look_rotation},
+};
+
+/// Third person camera trailing a target. Without a player object the target is a marker that
+/// move_forward moves along its heading and move_right turns
+pub struct FollowController {
+    pub target: Vec3,
+    /// direction the target faces around the y axis, in degrees like the camera yaw
+    pub heading: f32,
+
+    /// where the camera wants to be, behind and above the target
+    pub distance: f32,
+    pub height: f32,
+
+    /// how quickly the camera catches up, larger is stiffer
+    pub sharpness: f32,
+
+    /// target speed in units and turn rate in degrees per second
+    pub speed: f32,
+    pub turn_rate: f32,
+}
+
+impl FollowController {
+    pub fn new() -> Self {
+        Self {
+            target: Vec3::ZERO,
+            heading: 0.0,
+            distance: 6.0,
+            height: 2.5,
+            sharpness: 4.0,
+            speed: 5.0,
+            turn_rate: 120.0,
+        }
+    }
+
+    fn forward(self: &Self) -> Vec3 {
+        let heading = self.heading.to_radians();
+        vec3(heading.sin(), 0.0, -heading.cos())
+    }
+}
+
+impl CameraController for FollowController {
+    fn name(self: &Self) -> &'static str {
+        "follow"
+    }
+
+    /// start following from a bit in front of the camera, facing the same way
+    fn activate(self: &mut Self, cam: &Camera) {
+        self.heading = cam.yaw_pitch_roll().0;
+        self.target = cam.camera_pos + self.forward() * self.distance - Vec3::Y * self.height;
+    }
+
+    fn update(self: &mut Self, cam: &mut Camera, actions: &ActionMap, delta: f32) {
+        self.heading += actions.axis("move_right") * self.turn_rate * delta;
+        self.target += self.forward() * actions.axis("move_forward") * self.speed * delta;
+
+        let desired = self.target - self.forward() * self.distance + Vec3::Y * self.height;
+        cam.camera_pos = cam
+            .camera_pos
+            .lerp(desired, smoothing_factor(self.sharpness, delta));
+        // turning lags behind like the position does
+        let look_target = self.target + Vec3::Y * 0.5;
+        if let Some(orientation) = look_rotation(look_target - cam.camera_pos, Vec3::Y, cam.up()) {
+            cam.slerp_orientation(orientation, smoothing_factor(self.sharpness * 2.0, delta));
+        }
+    }
+
+    /// stand-in for the player
+    fn debug_draw(self: &Self) {
+        debug_draw::wire_sphere(self.target + Vec3::Y * 0.5, 0.5, debug_draw::YELLOW, 0.0);
+        debug_draw::arrow(
+            self.target + Vec3::Y * 0.5,
+            self.target + Vec3::Y * 0.5 + self.forward(),
+            debug_draw::YELLOW,
+            0.0,
+        );
+    }
+
+    fn mouse_wheel(self: &mut Self, _cam: &mut Camera, delta: f32) {
+        self.distance = (self.distance * 1.1_f32.powf(-delta)).clamp(1.0, 50.0);
+    }
+}
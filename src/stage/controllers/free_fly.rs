@@ -0,0 +1,83 @@
+use glam::*;
+
+use super::CameraController;
+use crate::{actions::ActionMap, stage::Camera};
+
+/// move_forward and move_right along the view direction, move_up to go up and down, drag to look
+/// around.
+/// The camera speeds up and coasts to a stop instead of moving at a fixed speed.
+/// First person keeps the horizon level, six degrees of freedom turns around the camera's own
+/// axes and rolls with the roll axis
+pub struct FreeFlyController {
+    velocity: Vec3,
+
+    /// how fast the velocity follows the input, also how fast it slows down without input.
+    /// The top speed is the camera_speed of the camera
+    pub damping: f32,
+
+    pub six_dof: bool,
+    /// degrees per second while roll is held
+    pub roll_rate: f32,
+}
+
+impl FreeFlyController {
+    /// first person, up is always the world up
+    pub fn new() -> Self {
+        Self {
+            velocity: Vec3::ZERO,
+            damping: 8.0,
+            six_dof: false,
+            roll_rate: 90.0,
+        }
+    }
+
+    /// flight without a fixed up direction
+    pub fn six_dof() -> Self {
+        Self {
+            six_dof: true,
+            ..Self::new()
+        }
+    }
+}
+
+impl CameraController for FreeFlyController {
+    fn name(self: &Self) -> &'static str {
+        if self.six_dof {
+            "6dof flight"
+        } else {
+            "free fly"
+        }
+    }
+
+    fn activate(self: &mut Self, _cam: &Camera) {
+        self.velocity = Vec3::ZERO;
+    }
+
+    fn update(self: &mut Self, cam: &mut Camera, actions: &ActionMap, delta: f32) {
+        let up = if self.six_dof { cam.up() } else { Vec3::Y };
+
+        let input = cam.front() * actions.axis("move_forward")
+            + cam.right() * actions.axis("move_right")
+            + up * actions.axis("move_up");
+
+        // accelerating by damping * top speed settles at exactly the top speed
+        let acceleration = input.normalize_or_zero() * cam.camera_speed * self.damping;
+        self.velocity += acceleration * delta;
+        self.velocity *= (-self.damping * delta).exp();
+
+        cam.camera_pos += self.velocity * delta;
+
+        if self.six_dof {
+            let roll = actions.axis("roll") * self.roll_rate * delta;
+            cam.rotate_local(0.0, 0.0, roll);
+        }
+    }
+
+    fn mouse_drag(self: &mut Self, cam: &mut Camera, delta: Vec2) {
+        if self.six_dof {
+            cam.rotate_local(delta.x, delta.y, 0.0);
+        } else {
+            cam.change_pitch_yaw(delta.x, delta.y);
+        }
+    }
+}
@@ -0,0 +1,38 @@
+mod follow;
+mod free_fly;
+mod orbit;
+pub use follow::FollowController;
+pub use free_fly::FreeFlyController;
+pub use orbit::OrbitController;
+
+use super::Camera;
+use crate::actions::ActionMap;
+
+/// Moves the camera from keyboard and mouse input. The stage owns one of each and hands the
+/// camera to whichever is active, switching keeps the camera where it is
+pub trait CameraController {
+    fn name(self: &Self) -> &'static str;
+
+    /// Called when the controller takes over, so it can pick up from where the camera is now
+    fn activate(self: &mut Self, _cam: &Camera) {}
+
+    /// called once per simulation step, movement comes from the move_* axes of `actions`
+    fn update(self: &mut Self, cam: &mut Camera, actions: &ActionMap, delta: f32);
+
+    /// The mouse was dragged while the look action was held, `delta` is already scaled by the mouse
+    /// sensitivity and in degrees, y pointing up
+    fn mouse_drag(self: &mut Self, _cam: &mut Camera, _delta: glam::Vec2) {}
+
+    /// scroll wheel, positive is away from the user
+    fn mouse_wheel(self: &mut Self, _cam: &mut Camera, _delta: f32) {}
+
+    /// queue debug shapes for the current frame, update may run several times or not at all
+    /// per frame
+    fn debug_draw(self: &Self) {}
+}
+
+/// Factor to move a value towards its target with, independent of the frame rate.
+/// `sharpness` is roughly the inverse of the time it takes to get most of the way there
+fn smoothing_factor(sharpness: f32, delta: f32) -> f32 {
+    1.0 - (-sharpness * delta).exp()
+}
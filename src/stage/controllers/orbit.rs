@@ -0,0 +1,48 @@
+use glam::*;
+
+use super::CameraController;
+use crate::{actions::ActionMap, stage::Camera};
+
+/// Circles around a target point, drag to rotate and scroll to zoom
+pub struct OrbitController {
+    pub target: Vec3,
+    pub distance: f32,
+
+    /// each scroll step changes the distance by this factor
+    pub zoom_step: f32,
+}
+
+impl OrbitController {
+    pub fn new() -> Self {
+        Self {
+            target: Vec3::ZERO,
+            distance: 10.0,
+            zoom_step: 1.1,
+        }
+    }
+}
+
+impl CameraController for OrbitController {
+    fn name(self: &Self) -> &'static str {
+        "orbit"
+    }
+
+    /// orbit around whatever is in front of the camera at the current distance
+    fn activate(self: &mut Self, cam: &Camera) {
+        self.target = cam.camera_pos + cam.front() * self.distance;
+    }
+
+    fn update(self: &mut Self, cam: &mut Camera, _actions: &ActionMap, _delta: f32) {
+        cam.camera_pos = self.target - cam.front() * self.distance;
+    }
+
+    fn mouse_drag(self: &mut Self, cam: &mut Camera, delta: Vec2) {
+        // the camera swings around the target so the scene turns along with the mouse
+        cam.change_pitch_yaw(delta.x, delta.y);
+        cam.camera_pos = self.target - cam.front() * self.distance;
+    }
+
+    fn mouse_wheel(self: &mut Self, _cam: &mut Camera, delta: f32) {
+        self.distance = (self.distance * self.zoom_step.powf(-delta)).clamp(0.5, 500.0);
+    }
+}
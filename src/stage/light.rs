@@ -0,0 +1,21 @@
+use glam::*;
+
+/// A light infinitely far away, like the sun. Only direction matters, not position
+pub struct DirectionalLight {
+    // direction the light travels in, not the direction towards the light
+    pub direction: Vec3,
+    pub color: Vec3,
+    pub intensity: f32,
+}
+
+impl DirectionalLight {
+    /// direction packed the way the shader reads it
+    pub fn direction_uniform(&self) -> Vec4 {
+        self.direction.normalize().extend(0.0)
+    }
+
+    /// color in rgb and intensity in a
+    pub fn color_uniform(&self) -> Vec4 {
+        self.color.extend(self.intensity)
+    }
+}
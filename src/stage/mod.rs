@@ -1,30 +1,123 @@
-mod camera;
-pub use camera::Camera;
-use miniquad::{Bindings, KeyCode, KeyMods, MouseButton, Pipeline, RenderingBackend, date, window};
-
-use crate::{objects::RenderableObject, shaders, textures};
+pub mod camera;
+mod console_commands;
+pub mod controllers;
+mod light;
+mod projection;
+mod scene;
+mod scripts;
+use std::path::PathBuf;
+
+pub use camera::{Camera, CameraPose};
+pub use console_commands::register_commands;
+pub use light::DirectionalLight;
+use miniquad::{
+    Comparison, KeyCode, KeyMods, MouseButton, Pipeline, RenderingBackend, ShaderId, date, window,
+};
+pub use projection::Projection;
+
+use controllers::CameraController;
+
+use crate::{
+    actions::ActionMap,
+    camera_path::{CameraPath, PathPlayer, PathState},
+    capture::FrameCapture,
+    config::ConfigFile,
+    console::Console,
+    debug_draw::{self, DebugDrawRenderer},
+    debug_ui::DebugUi,
+    materials::Environment,
+    objects::RenderableObject,
+    picking::PickHit,
+    postprocess::PostProcessChain,
+    profiler::{self, profile_scope},
+    render_targets::RenderTarget,
+    replay::{InputEvent, InputRecording, InputReplay, StartState},
+    scene::Scene,
+    scripting::ScriptHost,
+    shaders,
+    text::{TextAlign, TextRenderer, TextStyle},
+    time::Time,
+};
 
 pub struct WorldState {
     pub cam: Camera,
+
+    pub light: DirectionalLight,
+    pub environment: Environment,
 }
 
 pub struct StageMetadata {
-    pub last_time_update_fn_run: f64,
-    pub _time_stage_started: f64,
-
     pub exited: bool,
+
+    /// running against a recording backend without a window
+    pub headless: bool,
+    /// window size in physical pixels, the size every render target has. Kept up to date by
+    /// resize_event
+    pub screen_size: (f32, f32),
+    /// physical pixels per logical pixel, above 1.0 on high dpi screens
+    pub dpi_scale: f32,
+    pub fullscreen: bool,
+
+    pub render_stats: RenderStats,
+    pub fps: FpsCounter,
+
+    /// last message shown on the hud and the real elapsed time it disappears at
+    pub notice: Option<(String, f64)>,
+}
+
+impl StageMetadata {
+    /// window size in logical pixels, what ui code lays out in so it keeps its size on high dpi
+    /// screens
+    pub fn logical_screen_size(&self) -> (f32, f32) {
+        (
+            self.screen_size.0 / self.dpi_scale,
+            self.screen_size.1 / self.dpi_scale,
+        )
+    }
+}
+
+/// what happened during the last frame
+#[derive(Debug, Default, Clone, Copy)]
+pub struct RenderStats {
+    pub drawn: usize,
+    // outside of the view frustum
+    pub culled: usize,
+}
+
+/// frames per second averaged over half a second so the number stays readable
+#[derive(Debug, Default)]
+pub struct FpsCounter {
+    frames: u32,
+    elapsed: f64,
+
+    pub fps: f64,
+    pub frame_time_ms: f64,
+}
+
+impl FpsCounter {
+    pub fn tick(&mut self, delta: f64) {
+        self.frames += 1;
+        self.elapsed += delta;
+
+        if self.elapsed >= 0.5 {
+            self.fps = self.frames as f64 / self.elapsed;
+            self.frame_time_ms = self.elapsed * 1000.0 / self.frames as f64;
+            self.frames = 0;
+            self.elapsed = 0.0;
+        }
+    }
 }
 
 pub struct Settings {
     // pitch and yaw change per pixel moved
     pub mouse_sensitivity: f32,
 
-    // debug options
+    // debug options, also console variables
     pub render_wireframe: bool,
-    pub debug_toggle_1: bool,
-    pub debug_toggle_2: bool,
-    pub debug_toggle_3: bool,
-    pub debug_toggle_4: bool,
+    /// object bounds and material names
+    pub show_bounds: bool,
+    /// axes, a grid and the sun direction
+    pub show_reference_lines: bool,
 }
 
 pub struct Stage {
@@ -35,139 +128,628 @@ pub struct Stage {
     pub world: WorldState,
 
     pub pipeline: Pipeline,
+    /// same shader as pipeline, draws line lists for the wireframe debug mode
+    pub wireframe_pipeline: Pipeline,
+    pub scene_shader: ShaderId,
+    /// what both scene pipelines were built with, flips with the depth convention of the camera
+    pub scene_depth_test: Comparison,
+
+    /// the scene is rendered into this hdr target, the post processing chain presents it
+    pub scene_target: RenderTarget,
+    pub post: PostProcessChain,
+
+    /// draws everything queued through the debug_draw functions on top of the frame
+    pub debug_draw: DebugDrawRenderer,
+    /// hud, labels and everything else that is text
+    pub text: TextRenderer,
+    /// F12 panel to edit settings, camera and shaders live
+    pub debug_ui: DebugUi,
+    /// F9 screenshots and F10 image sequences
+    pub capture: FrameCapture,
+    /// recorded or hand placed camera fly-through
+    pub camera_path: PathPlayer,
+    /// what the last click hit, if anything
+    pub selection: Option<PickHit>,
 
     pub settings: Settings,
+    /// where the settings come from and go back to when they change
+    pub config: ConfigFile,
+    /// drop-down with commands and variables, toggle_console opens it
+    pub console: Console<Stage>,
+    /// level layout and behaviors from ./scripts, reloaded when they change
+    pub scripts: ScriptHost,
 
     pub shaders: Vec<shaders::ShaderFile>,
 
     pub input: input::InputData,
-
+    /// named actions and axes bound to keys and mouse buttons, what game code checks for input
+    pub actions: ActionMap,
+    /// frame and simulation time, pause and slow motion
+    pub time: Time,
+    /// where the camera was before the last simulation step
+    pub previous_camera_pose: CameraPose,
+
+    /// records the input that reaches the game or plays a recording back instead of it
+    pub replay: InputReplay,
+
+    /// free fly, orbit and follow, next_camera_controller switches to the next one
+    pub camera_controllers: Vec<Box<dyn CameraController>>,
+    pub active_camera_controller: usize,
+
+    /// what renderable_objects was loaded from, save_scene writes it back
+    pub scene: Scene,
+    pub scene_file: PathBuf,
     pub renderable_objects: Vec<Box<dyn RenderableObject>>,
 }
 
 // handle updates of various components
 impl Stage {
     pub fn update(&mut self) {
-        for shader in self.shaders.iter_mut() {
-            if shader.reload_if_needed() {
-                // println!("reload!");
+        // update is the first thing miniquad calls every frame
+        profiler::new_frame();
+        profile_scope!("update");
+
+        // the first shader is the one every scene object is drawn with
+        {
+            profile_scope!("shader reload");
+            if self.shaders[0].reload_if_needed() {
+                self.reload_scene_shader();
             }
         }
 
-        // a lot of update loops require some kind of time delta
-        let delta = date::now() - self.meta.last_time_update_fn_run;
+        // the only place that reads the wall clock, everything else goes through self.time
+        let fixed_delta = self.replay.fixed_delta().or(self.capture.fixed_delta());
+        let steps = self.time.begin_frame(date::now(), fixed_delta);
+        self.update_config();
+
+        for event in self.replay.due_events() {
+            self.apply_input(event);
+        }
+
+        // debug shapes and the fps counter keep going while the game is paused
+        debug_draw::tick(self.time.real_delta as f32);
+        self.meta.fps.tick(self.time.real_delta);
 
-        self.update_camera(delta as f32);
+        self.run_commands();
+        self.console.collect_log();
+        Console::run_submitted(self);
+        self.update_selection();
 
-        self.meta.last_time_update_fn_run = date::now();
+        for _ in 0..steps {
+            self.previous_camera_pose = self.world.cam.pose();
+            self.simulate(self.time.simulation.step);
+        }
+        self.update_scripts();
+        self.camera_controllers[self.active_camera_controller].debug_draw();
+        // the projection blend is part of the ui, it keeps going while paused or slowed down
+        self.world
+            .cam
+            .update_projection(self.time.real_delta as f32);
+
+        self.actions.end_frame();
+        if self.replay.end_frame() {
+            self.finish_replay();
+        }
     }
 
-    pub fn update_camera(&mut self, update_delta: f32) {
-        let pressed_keys = self.input.pressed_keys.clone();
+    /// Apply edits of the config file and write back the settings changed in the app
+    fn update_config(&mut self) {
+        profile_scope!("config");
+
+        if let Some(previous) = self.config.poll() {
+            self.config
+                .config
+                .apply(&mut self.settings, &mut self.world.cam);
+
+            let file = self.config.file.display();
+            let message = if !self.config.errors.is_empty() {
+                // the notice only has room for one, all of them are printed
+                for e in self.config.errors.iter().skip(1) {
+                    log::warn!("{}", e);
+                }
+                self.config.errors[0].clone()
+            } else if previous.needs_restart(&self.config.config) {
+                format!("reloaded {}, window settings apply after a restart", file)
+            } else {
+                format!("reloaded {}", file)
+            };
+            self.notify(message);
+            return;
+        }
 
-        // forward and back
-        if pressed_keys.contains(&KeyCode::W) {
-            self.world.cam.move_forward(update_delta as f32);
+        let runtime = self
+            .config
+            .config
+            .with_runtime(&self.settings, &self.world.cam);
+        self.config.changed_in_app(runtime, self.time.real_elapsed);
+        if let Err(e) = self.config.save_if_due(self.time.real_elapsed) {
+            self.notify(e);
         }
+    }
 
-        if pressed_keys.contains(&KeyCode::S) {
-            self.world.cam.move_backwards(update_delta as f32);
+    /// Advance everything that moves by one fixed step of `step` seconds
+    fn simulate(&mut self, step: f64) {
+        profile_scope!("simulate");
+        // a playing path owns the camera
+        if !self.camera_path.is_playing() {
+            self.update_camera(step as f32);
         }
+        if self.camera_path.update(&mut self.world.cam, step as f32) {
+            // continue from wherever the path left the camera
+            self.camera_controllers[self.active_camera_controller].activate(&self.world.cam);
+            self.notify("camera path stopped".to_owned());
+        }
+
+        self.scripts
+            .run_step(&self.actions, step, self.time.elapsed);
+    }
+
+    /// the camera as the frame shows it, between the last two simulation steps
+    pub fn render_camera(&self) -> Camera {
+        self.world
+            .cam
+            .interpolated(self.previous_camera_pose, self.time.simulation.alpha())
+    }
+
+    pub fn update_camera(&mut self, update_delta: f32) {
+        self.camera_controllers[self.active_camera_controller].update(
+            &mut self.world.cam,
+            &self.actions,
+            update_delta,
+        );
+    }
 
-        // left and right
-        if pressed_keys.contains(&KeyCode::A) {
-            self.world.cam.move_left(update_delta as f32);
+    /// a select that did not turn into a drag picks what is under the cursor
+    fn update_selection(&mut self) {
+        if self.actions.just_pressed("select") {
+            self.input.select_press_location = self.input.mouse_location;
         }
 
-        if pressed_keys.contains(&KeyCode::D) {
-            self.world.cam.move_right(update_delta as f32);
+        if self.actions.just_released("select") {
+            let dragged = (self.input.mouse_location - self.input.select_press_location).length()
+                > 4.0 * self.meta.dpi_scale;
+            if !dragged {
+                self.select_at(self.input.mouse_location);
+            }
         }
+    }
+
+    /// hand the camera to the next controller, it continues from the current view
+    pub fn next_camera_controller(&mut self) {
+        self.active_camera_controller =
+            (self.active_camera_controller + 1) % self.camera_controllers.len();
+
+        let controller = &mut self.camera_controllers[self.active_camera_controller];
+        controller.activate(&self.world.cam);
 
-        // up and down
-        if pressed_keys.contains(&KeyCode::Space) {
-            self.world.cam.move_up(update_delta as f32);
+        let message = format!("camera {}", controller.name());
+        self.notify(message);
+    }
+}
+
+// camera paths
+impl Stage {
+    pub fn toggle_path_recording(&mut self) {
+        if let PathState::Recording { .. } = self.camera_path.state {
+            self.camera_path.stop();
+            let message = format!(
+                "recorded {} keyframes, {} saves them",
+                self.camera_path.path.keyframes.len(),
+                self.actions.label("save_camera_path")
+            );
+            self.notify(message);
+        } else {
+            self.camera_path.start_recording();
+            let message = format!(
+                "recording camera path, {} stops",
+                self.actions.label("record_camera_path")
+            );
+            self.notify(message);
         }
+    }
 
-        if pressed_keys.contains(&KeyCode::C) {
-            self.world.cam.move_down(update_delta as f32);
+    /// append the current view two seconds after the last keyframe
+    pub fn add_path_keyframe(&mut self) {
+        let path = &mut self.camera_path.path;
+        let time = if path.keyframes.is_empty() {
+            0.0
+        } else {
+            path.duration() + 2.0
+        };
+        path.insert(time, &self.world.cam);
+
+        let message = format!("keyframe {} at {:.1}s", path.keyframes.len(), time);
+        self.notify(message);
+    }
+
+    pub fn toggle_path_playback(&mut self) {
+        if self.camera_path.is_playing() {
+            self.camera_path.stop();
+        } else if !self.camera_path.play() {
+            let message = format!(
+                "the camera path is empty, record with {} or add keyframes with {}",
+                self.actions.label("record_camera_path"),
+                self.actions.label("add_path_keyframe")
+            );
+            self.notify(message);
+        }
+    }
+
+    /// Play the path from the start and record every frame of it as an image sequence. The fixed
+    /// delta of the sequence makes the result the same on every run
+    pub fn record_path_sequence(&mut self) {
+        const FRAME_RATE: f64 = 30.0;
+
+        if !self.camera_path.play() {
+            self.notify("the camera path is empty, nothing to record".to_owned());
+            return;
         }
+
+        // one frame per step plus the one exactly at the end
+        let frames = (self.camera_path.playback_duration() as f64 * FRAME_RATE).ceil() as usize + 1;
+        self.capture.start_sequence(frames, FRAME_RATE);
+        self.notify(format!("recording the camera path as {} frames", frames));
+    }
+
+    pub fn save_camera_path(&mut self) {
+        let message = match self.camera_path.path.save(&self.camera_path.file) {
+            Ok(()) => format!("saved camera path to {}", self.camera_path.file.display()),
+            Err(e) => e,
+        };
+        self.notify(message);
+    }
+
+    pub fn load_camera_path(&mut self) {
+        let message = match CameraPath::load(&self.camera_path.file) {
+            Ok(path) => {
+                self.camera_path.stop();
+                self.camera_path.path = path;
+                format!(
+                    "loaded {} keyframes from {}",
+                    self.camera_path.path.keyframes.len(),
+                    self.camera_path.file.display()
+                )
+            }
+            Err(e) => e,
+        };
+        self.notify(message);
     }
 }
 
-// mouse and keyboard input
+// actions that do something once when they are pressed
 impl Stage {
-    pub fn quit_requested_event(&mut self) {
-        println!("Exit clearing objects");
-        for object in self.renderable_objects.iter_mut() {
-            object.drop_gl_resources(&mut self.ctx);
+    /// run the commands of every action pressed since the last update, in the order they were
+    /// pressed
+    pub fn run_commands(&mut self) {
+        for action in self.actions.pressed_actions() {
+            self.run_command(&action);
         }
     }
 
-    pub fn key_down_event(&mut self, _keycode: KeyCode, _keymods: KeyMods, _repeat: bool) {
-        // put all the pressed keys in a set so we can check em later if they are pressed down or
-        // not, remove them from the set when they are released
-        match _keycode {
-            KeyCode::Escape => {
+    pub fn run_command(&mut self, action: &str) {
+        match action {
+            "quit" => {
                 window::request_quit();
                 self.meta.exited = true;
             }
 
-            KeyCode::Key1 => {
+            "toggle_wireframe" => {
                 self.settings.render_wireframe = !self.settings.render_wireframe;
-                self.settings.debug_toggle_1 = !self.settings.debug_toggle_1;
-                println!("Toggle wireframe {}", self.settings.render_wireframe);
+                self.notify(format!("wireframe {}", self.settings.render_wireframe));
+            }
+
+            "toggle_bounds" => {
+                self.settings.show_bounds = !self.settings.show_bounds;
+                self.notify(format!("object bounds {}", self.settings.show_bounds));
+            }
+
+            "toggle_reference_lines" => {
+                self.settings.show_reference_lines = !self.settings.show_reference_lines;
+                self.notify(format!(
+                    "reference lines {}",
+                    self.settings.show_reference_lines
+                ));
+            }
+
+            "capture_frustum" => {
+                // freeze the current view volume so it can be inspected from the outside
+                debug_draw::frustum(
+                    self.world.cam.projection_matrix() * self.world.cam.get_view_matrix(),
+                    debug_draw::YELLOW,
+                    10.0,
+                );
+                self.notify("captured the camera frustum for 10 seconds".to_owned());
+            }
+
+            "next_camera_controller" => {
+                self.next_camera_controller();
+            }
+
+            "next_projection" => {
+                // orthographic modes show what the perspective shows at the origin
+                let focus_distance = self.world.cam.camera_pos.length().max(1.0);
+                let projection = self.world.cam.projection.next(focus_distance);
+                self.world.cam.set_projection(projection, 0.5);
+                self.notify(format!("projection {}", projection.name()));
+            }
+
+            "record_camera_path" => {
+                self.toggle_path_recording();
+            }
+
+            "add_path_keyframe" => {
+                self.add_path_keyframe();
+            }
+
+            "play_camera_path" => {
+                self.toggle_path_playback();
+            }
+
+            "save_camera_path" => {
+                self.save_camera_path();
+            }
+
+            "load_camera_path" => {
+                self.load_camera_path();
+            }
+
+            "record_path_sequence" => {
+                self.record_path_sequence();
+            }
+
+            "screenshot" => {
+                self.capture.request_screenshot();
+            }
+
+            "record_input" => {
+                self.toggle_input_recording();
+            }
+
+            "replay_input" => {
+                self.replay_input();
+            }
+
+            "toggle_pause" => {
+                self.time.toggle_pause();
+                let message = if self.time.paused {
+                    format!(
+                        "paused, {} steps one frame",
+                        self.actions.label("step_frame")
+                    )
+                } else {
+                    "unpaused".to_owned()
+                };
+                self.notify(message);
+            }
+
+            "step_frame" => {
+                self.time.step_one_frame();
+            }
+
+            "slower" | "faster" => {
+                let factor = if action == "slower" { 0.5 } else { 2.0 };
+                self.time.time_scale = (self.time.time_scale * factor).clamp(1.0 / 16.0, 4.0);
+                self.notify(format!("time scale {}", self.time.time_scale));
             }
 
-            KeyCode::Key2 => {
-                self.settings.debug_toggle_2 = !self.settings.debug_toggle_2;
-                println!("toggled debug 2");
+            "save_profile" => {
+                let message = match profiler::save_chrome_trace(profiler::DEFAULT_TRACE_FILE) {
+                    Ok(()) => format!("saved the last frames to {}", profiler::DEFAULT_TRACE_FILE),
+                    Err(e) => e,
+                };
+                self.notify(message);
             }
 
-            KeyCode::Key3 => {
-                self.settings.debug_toggle_3 = !self.settings.debug_toggle_3;
-                println!("toggled debug 3");
+            "record_sequence" => {
+                // four seconds of video
+                self.capture.start_sequence(120, 30.0);
+                self.notify("recording 120 frames at 30 fps".to_owned());
             }
 
-            KeyCode::Key4 => {
-                self.settings.debug_toggle_4 = !self.settings.debug_toggle_4;
-                println!("toggled debug 4");
+            "toggle_fullscreen" => {
+                self.toggle_fullscreen();
+            }
+
+            "toggle_console" => {
+                self.debug_ui.console_open = !self.debug_ui.console_open;
+            }
+
+            "toggle_debug_ui" => {
+                self.debug_ui.visible = !self.debug_ui.visible;
+
+                // the cursor is needed to click around in the panel
+                window::set_cursor_grab(!self.debug_ui.visible);
+                window::show_mouse(self.debug_ui.visible);
             }
 
             _ => {
-                self.input.pressed_keys.insert(_keycode);
+                // toggle_post_effect_1 and up, numbered like the list in the debug panel
+                let Some(index) = action
+                    .strip_prefix("toggle_post_effect_")
+                    .and_then(|n| n.parse::<usize>().ok())
+                    .and_then(|n| n.checked_sub(1))
+                else {
+                    // look and select are held, not commands
+                    return;
+                };
+
+                if let Some(enabled) = self.post.toggle(index) {
+                    self.notify(format!(
+                        "Toggle post effect {} {}",
+                        self.post.effects[index].name, enabled
+                    ));
+                }
             }
         }
     }
+}
+
+// window changes
+impl Stage {
+    /// Called with the new size in physical pixels whenever the window size or its dpi scale
+    /// changed
+    pub fn resize_event(&mut self, width: f32, height: f32) {
+        self.meta.screen_size = (width, height);
+        if !self.meta.headless {
+            self.meta.dpi_scale = window::dpi_scale();
+        }
+
+        // a minimized window can report a size of zero, keep everything as it was until it is back
+        if width < 1.0 || height < 1.0 {
+            return;
+        }
+
+        self.world.cam.aspect_ratio = width / height;
+        self.scene_target
+            .ensure_size(&mut self.ctx, width as u32, height as u32);
+    }
+
+    pub fn toggle_fullscreen(&mut self) {
+        self.meta.fullscreen = !self.meta.fullscreen;
+        window::set_fullscreen(self.meta.fullscreen);
+    }
+}
+
+// mouse and keyboard input
+impl Stage {
+    pub fn quit_requested_event(&mut self) {
+        log::info!("Exit clearing objects");
+        for object in self.renderable_objects.iter_mut() {
+            object.drop_gl_resources(&mut self.ctx);
+        }
+        self.world.environment.drop_gl_resources(&mut self.ctx);
+        self.scene_target.drop_gl_resources(&mut self.ctx);
+        self.post.drop_gl_resources(&mut self.ctx);
+        self.debug_draw.drop_gl_resources(&mut self.ctx);
+        self.text.drop_gl_resources(&mut self.ctx);
+
+        self.capture.drop_gl_resources(&mut self.ctx);
+
+        self.ctx.delete_pipeline(self.pipeline);
+        self.ctx.delete_pipeline(self.wireframe_pipeline);
+        self.ctx.delete_shader(self.scene_shader);
+    }
+
+    pub fn key_down_event(&mut self, _keycode: KeyCode, _keymods: KeyMods, _repeat: bool) {
+        // the console's text field takes every key, except the one that closes it again
+        if self.debug_ui.console_open
+            && self
+                .actions
+                .is_bound_to("toggle_console", _keycode, _keymods)
+        {
+            if !_repeat {
+                self.debug_ui.console_open = false;
+            }
+            return;
+        }
+
+        self.debug_ui.key_down_event(_keycode, _keymods);
+        if self.debug_ui.wants_keyboard() || _repeat {
+            return;
+        }
+
+        self.live_input(InputEvent::KeyDown {
+            key: _keycode,
+            mods: _keymods,
+        });
+    }
 
     pub fn key_up_event(&mut self, _keycode: KeyCode, _keymods: KeyMods) {
-        self.input.pressed_keys.remove(&_keycode);
+        self.debug_ui.key_up_event(_keycode, _keymods);
+        self.live_input(InputEvent::KeyUp {
+            key: _keycode,
+            mods: _keymods,
+        });
+    }
+
+    pub fn char_event(&mut self, _character: char, _keymods: KeyMods, _repeat: bool) {
+        self.debug_ui.char_event(_character);
+    }
+
+    pub fn mouse_wheel_event(&mut self, _x: f32, _y: f32) {
+        self.debug_ui.mouse_wheel_event(_x, _y);
+        if self.debug_ui.wants_pointer() {
+            return;
+        }
+
+        self.live_input(InputEvent::MouseWheel { x: _x, y: _y });
     }
 
     pub fn mouse_button_down_event(&mut self, _button: MouseButton, _x: f32, _y: f32) {
-        self.input.pressed_mouse_buttons.insert(_button);
+        self.debug_ui.mouse_button_down_event(_button, _x, _y);
+        if self.debug_ui.wants_pointer() {
+            return;
+        }
+
+        self.live_input(InputEvent::MouseDown {
+            button: _button,
+            x: _x,
+            y: _y,
+        });
     }
 
     pub fn mouse_button_up_event(&mut self, _button: MouseButton, _x: f32, _y: f32) {
-        self.input.pressed_mouse_buttons.remove(&_button);
+        self.debug_ui.mouse_button_up_event(_button, _x, _y);
+        self.live_input(InputEvent::MouseUp {
+            button: _button,
+            x: _x,
+            y: _y,
+        });
     }
 
     pub fn mouse_motion_event(&mut self, _x: f32, _y: f32) {
-        if !self
-            .input
-            .pressed_mouse_buttons
-            .contains(&MouseButton::Left)
-        {
+        self.debug_ui.mouse_motion_event(_x, _y);
+        self.live_input(InputEvent::MouseMotion { x: _x, y: _y });
+    }
+
+    /// input from the window, ignored while a recording is replayed
+    fn live_input(&mut self, event: InputEvent) {
+        if self.replay.is_replaying() {
+            return;
+        }
+
+        self.replay.record(event);
+        self.apply_input(event);
+    }
+
+    /// what the game does with an event, live or replayed
+    pub fn apply_input(&mut self, event: InputEvent) {
+        match event {
+            InputEvent::KeyDown { key, mods } => self.actions.key_down(key, mods),
+            InputEvent::KeyUp { key, mods } => self.actions.key_up(key, mods),
+            InputEvent::MouseDown { button, x, y } => {
+                self.input.mouse_location = glam::vec2(x, y);
+                self.actions.mouse_down(button);
+            }
+            InputEvent::MouseUp { button, x, y } => {
+                self.input.mouse_location = glam::vec2(x, y);
+                self.actions.mouse_up(button);
+            }
+            InputEvent::MouseMotion { x, y } => self.mouse_moved(x, y),
+            InputEvent::MouseWheel { y, .. } => {
+                self.camera_controllers[self.active_camera_controller]
+                    .mouse_wheel(&mut self.world.cam, y);
+            }
+        }
+    }
+
+    fn mouse_moved(&mut self, x: f32, y: f32) {
+        self.input.mouse_location = glam::vec2(x, y);
+
+        if !self.actions.is_active("look") {
             // nothing to do per se
-            self.input.prev_mouse_location = glam::Vec2 { x: _x, y: -_y };
+            self.input.prev_mouse_location = glam::Vec2 { x, y: -y };
             return;
         }
 
         // inverse y since the mouse position is top-left 0.0
         // but we want it in "screen space" which means 0.0 is bottom left
-        let new_position = glam::Vec2 { x: _x, y: -_y };
+        let new_position = glam::Vec2 { x, y: -y };
 
-        let delta =
-            (new_position - self.input.prev_mouse_location) * self.settings.mouse_sensitivity;
+        // mouse positions are physical pixels, the sensitivity is per logical pixel so high dpi
+        // screens do not turn faster
+        let delta = (new_position - self.input.prev_mouse_location) / self.meta.dpi_scale
+            * self.settings.mouse_sensitivity;
 
         self.input.prev_mouse_location = new_position;
 
@@ -175,27 +757,193 @@ impl Stage {
             return;
         }
 
-        self.world.cam.change_pitch_yaw(delta.x, delta.y);
+        self.camera_controllers[self.active_camera_controller]
+            .mouse_drag(&mut self.world.cam, delta);
     }
 }
 
-pub mod input {
-    use miniquad::{KeyCode, MouseButton};
-    use std::collections::HashSet;
+// input recording and replay
+impl Stage {
+    /// Start recording the input, or stop and save the recording. The camera controller starts
+    /// over and held keys are forgotten so a replay can start from the same state
+    pub fn toggle_input_recording(&mut self) {
+        if let Some(recording) = self.replay.stop() {
+            let message = match recording.save(&self.replay.file) {
+                Ok(()) => format!(
+                    "recorded {} frames of input to {}",
+                    recording.frames,
+                    self.replay.file.display()
+                ),
+                Err(e) => e,
+            };
+            self.notify(message);
+            return;
+        }
 
-    pub struct InputData {
-        pub pressed_keys: HashSet<KeyCode>,
-        pub pressed_mouse_buttons: HashSet<MouseButton>,
+        let start = StartState {
+            camera_pos: self.world.cam.camera_pos,
+            orientation: self.world.cam.orientation,
+            controller: self.active_camera_controller,
+            mouse: self.input.mouse_location,
+        };
+        self.reset_input(&start);
+        self.replay
+            .start_recording(start, self.time.simulation.step);
+
+        let message = format!(
+            "recording input, {} stops",
+            self.actions.label("record_input")
+        );
+        self.notify(message);
+    }
+
+    /// replay the recording in the replay file
+    pub fn replay_input(&mut self) {
+        match InputRecording::load(&self.replay.file) {
+            Ok(recording) => self.start_replay(recording),
+            Err(e) => self.notify(e),
+        }
+    }
+
+    /// Put the camera back to where the recording started and feed its events to the game
+    /// instead of live input until it ends
+    pub fn start_replay(&mut self, recording: InputRecording) {
+        self.world.cam.camera_pos = recording.start.camera_pos;
+        self.world.cam.orientation = recording.start.orientation;
+        self.reset_input(&recording.start);
+        self.time.simulation.step = recording.simulation_step;
+
+        let message = format!("replaying {} frames of input", recording.frames);
+        self.replay.start_replay(recording);
+        self.notify(message);
+    }
+
+    /// print where the replay left the camera, to compare runs with
+    fn finish_replay(&mut self) {
+        self.actions.release_all();
+
+        let cam = &self.world.cam;
+        let (yaw, pitch, roll) = cam.yaw_pitch_roll();
+        let message = format!(
+            "replay finished after {} frames, camera at {:.3} {:.3} {:.3} yaw {:.2} pitch {:.2} roll {:.2}",
+            self.replay.frame(),
+            cam.camera_pos.x,
+            cam.camera_pos.y,
+            cam.camera_pos.z,
+            yaw,
+            pitch,
+            roll
+        );
+        self.notify(message);
+    }
+
+    fn reset_input(&mut self, start: &StartState) {
+        self.actions.release_all();
+        self.time.reset_simulation();
+
+        self.input.mouse_location = start.mouse;
+        self.input.prev_mouse_location = glam::vec2(start.mouse.x, -start.mouse.y);
+        self.input.select_press_location = start.mouse;
+
+        self.active_camera_controller = start.controller.min(self.camera_controllers.len() - 1);
+        self.camera_controllers[self.active_camera_controller].activate(&self.world.cam);
+    }
+}
+
+// on screen information
+impl Stage {
+    /// print a message and show it on the hud for a few seconds
+    pub fn notify(&mut self, message: String) {
+        log::info!("{}", message);
+        self.meta.notice = Some((message, self.time.real_elapsed + 3.0));
+    }
+
+    /// queue the fps counter, frame statistics and the current notice
+    pub fn queue_hud(&mut self) {
+        let (width, height) = self.meta.logical_screen_size();
+        let margin = 8.0;
+
+        let fps = &self.meta.fps;
+        let mut frame_info = format!("{:.0} fps\n{:.2} ms", fps.fps, fps.frame_time_ms);
+        if self.time.paused {
+            frame_info += "\npaused";
+        } else if self.time.time_scale != 1.0 {
+            frame_info += &format!("\ntime x{}", self.time.time_scale);
+        }
+        self.text.draw_screen(
+            &frame_info,
+            glam::vec2(margin, margin),
+            &TextStyle {
+                align: TextAlign::Right,
+                max_width: Some(width - margin * 2.0),
+                ..Default::default()
+            },
+        );
+
+        let stats = self.meta.render_stats;
+        self.text.draw_screen(
+            &format!("drawn {}\nculled {}", stats.drawn, stats.culled),
+            glam::vec2(margin, margin),
+            &TextStyle {
+                color: glam::vec4(0.8, 0.8, 0.8, 1.0),
+                ..Default::default()
+            },
+        );
+
+        let replay_status = if self.replay.is_recording() {
+            Some("recording input")
+        } else if self.replay.is_replaying() {
+            Some("replaying input")
+        } else {
+            None
+        };
+        if let Some(status) = replay_status {
+            self.text.draw_screen(
+                &format!("{} {}", status, self.replay.frame()),
+                glam::vec2(margin, margin),
+                &TextStyle {
+                    color: glam::vec4(1.0, 0.3, 0.3, 1.0),
+                    align: TextAlign::Center,
+                    max_width: Some(width - margin * 2.0),
+                    ..Default::default()
+                },
+            );
+        }
+
+        if let Some((message, until)) = &self.meta.notice {
+            if self.time.real_elapsed < *until {
+                self.text.draw_screen(
+                    message,
+                    glam::vec2(margin, height * 0.8),
+                    &TextStyle {
+                        color: glam::vec4(1.0, 1.0, 0.6, 1.0),
+                        scale: 1.25,
+                        align: TextAlign::Center,
+                        max_width: Some(width - margin * 2.0),
+                    },
+                );
+            } else {
+                self.meta.notice = None;
+            }
+        }
+    }
+}
 
+pub mod input {
+    pub struct InputData {
         pub prev_mouse_location: glam::Vec2,
+        /// last known cursor position in pixels from the top left
+        pub mouse_location: glam::Vec2,
+        /// where the cursor was when select was pressed
+        pub select_press_location: glam::Vec2,
     }
 
     impl InputData {
         pub fn new() -> Self {
             Self {
-                pressed_keys: HashSet::new(),
-                pressed_mouse_buttons: HashSet::new(),
                 prev_mouse_location: glam::Vec2 { x: 0.0, y: 0.0 },
+                mouse_location: glam::Vec2 { x: 0.0, y: 0.0 },
+                select_press_location: glam::Vec2 { x: 0.0, y: 0.0 },
             }
         }
     }
@@ -0,0 +1,183 @@
+use glam::*;
+
+/// How the camera maps view space onto the screen. Every mode produces the 0..1 depth range of
+/// `Mat4::perspective_rh`, reversed for `InfinitePerspective`
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Projection {
+    Perspective {
+        fov_y_deg: f32,
+        z_near: f32,
+        z_far: f32,
+    },
+
+    /// parallel projection showing `height` world units vertically, the width follows the aspect
+    /// ratio of the window
+    Orthographic {
+        height: f32,
+        z_near: f32,
+        z_far: f32,
+    },
+
+    /// parallel projection of a fixed box in view space, ignores the aspect ratio of the window.
+    /// Meant for shadow cameras and editors that need exact bounds
+    OrthographicBounds {
+        left: f32,
+        right: f32,
+        bottom: f32,
+        top: f32,
+        z_near: f32,
+        z_far: f32,
+    },
+
+    /// Perspective without a far plane. Depth is reversed, 1.0 at the near plane and 0.0 at
+    /// infinity, so it has to be cleared to 0.0 and tested with Greater
+    InfinitePerspective { fov_y_deg: f32, z_near: f32 },
+}
+
+impl Projection {
+    pub fn matrix(&self, aspect_ratio: f32) -> Mat4 {
+        match *self {
+            Projection::Perspective {
+                fov_y_deg,
+                z_near,
+                z_far,
+            } => Mat4::perspective_rh(fov_y_deg.to_radians(), aspect_ratio, z_near, z_far),
+
+            Projection::Orthographic {
+                height,
+                z_near,
+                z_far,
+            } => {
+                let half_height = height * 0.5;
+                let half_width = half_height * aspect_ratio;
+
+                Mat4::orthographic_rh(
+                    -half_width,
+                    half_width,
+                    -half_height,
+                    half_height,
+                    z_near,
+                    z_far,
+                )
+            }
+
+            Projection::OrthographicBounds {
+                left,
+                right,
+                bottom,
+                top,
+                z_near,
+                z_far,
+            } => Mat4::orthographic_rh(left, right, bottom, top, z_near, z_far),
+
+            Projection::InfinitePerspective { fov_y_deg, z_near } => {
+                Mat4::perspective_infinite_reverse_rh(fov_y_deg.to_radians(), aspect_ratio, z_near)
+            }
+        }
+    }
+
+    /// true if nearer fragments have a larger depth value
+    pub fn reversed_z(&self) -> bool {
+        matches!(self, Projection::InfinitePerspective { .. })
+    }
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            Projection::Perspective { .. } => "perspective",
+            Projection::Orthographic { .. } => "orthographic",
+            Projection::OrthographicBounds { .. } => "orthographic bounds",
+            Projection::InfinitePerspective { .. } => "infinite perspective",
+        }
+    }
+
+    /// The next mode in the order the projection key cycles through them. Keeps the near plane
+    /// and, for the orthographic modes, shows what a perspective shows at `focus_distance`
+    pub fn next(&self, focus_distance: f32) -> Projection {
+        let (fov_y_deg, z_near, z_far) = self.view_volume();
+        let height = 2.0 * focus_distance * (fov_y_deg.to_radians() * 0.5).tan();
+
+        match self {
+            Projection::Perspective { .. } => Projection::Orthographic {
+                height,
+                z_near,
+                z_far,
+            },
+            Projection::Orthographic { height, .. } => Projection::OrthographicBounds {
+                left: -height * 0.5,
+                right: height * 0.5,
+                bottom: -height * 0.5,
+                top: height * 0.5,
+                z_near,
+                z_far,
+            },
+            Projection::OrthographicBounds { .. } => {
+                Projection::InfinitePerspective { fov_y_deg, z_near }
+            }
+            Projection::InfinitePerspective { .. } => Projection::Perspective {
+                fov_y_deg,
+                z_near,
+                z_far,
+            },
+        }
+    }
+
+    /// Replace the near and far plane where given, the far plane where the mode has one
+    pub fn set_clip_planes(&mut self, near: Option<f32>, far: Option<f32>) {
+        match self {
+            Projection::Perspective { z_near, z_far, .. }
+            | Projection::Orthographic { z_near, z_far, .. }
+            | Projection::OrthographicBounds { z_near, z_far, .. } => {
+                *z_near = near.unwrap_or(*z_near);
+                *z_far = far.unwrap_or(*z_far);
+            }
+            Projection::InfinitePerspective { z_near, .. } => {
+                *z_near = near.unwrap_or(*z_near);
+            }
+        }
+    }
+
+    /// fov, near and far of the mode, with the defaults for what it does not have
+    pub fn view_volume(&self) -> (f32, f32, f32) {
+        const DEFAULT_FOV_Y_DEG: f32 = 45.0;
+        const DEFAULT_Z_FAR: f32 = 100.0;
+
+        match *self {
+            Projection::Perspective {
+                fov_y_deg,
+                z_near,
+                z_far,
+            } => (fov_y_deg, z_near, z_far),
+            Projection::Orthographic { z_near, z_far, .. }
+            | Projection::OrthographicBounds { z_near, z_far, .. } => {
+                (DEFAULT_FOV_Y_DEG, z_near, z_far)
+            }
+            Projection::InfinitePerspective { fov_y_deg, z_near } => {
+                (fov_y_deg, z_near, DEFAULT_Z_FAR)
+            }
+        }
+    }
+}
+
+/// blend from the matrix that was shown when the projection changed to the new one
+#[derive(Debug, Clone, Copy)]
+pub struct ProjectionTransition {
+    pub from: Mat4,
+    pub elapsed: f32,
+    pub duration: f32,
+}
+
+impl ProjectionTransition {
+    /// Blend every matrix element, smoothstepped over the duration. Halfway between a perspective
+    /// and an orthographic matrix is not a real projection, but it looks like a dolly zoom
+    pub fn blend(&self, to: Mat4) -> Mat4 {
+        let t = (self.elapsed / self.duration).clamp(0.0, 1.0);
+        let t = t * t * (3.0 - 2.0 * t);
+
+        Mat4::from_cols(
+            self.from.x_axis.lerp(to.x_axis, t),
+            self.from.y_axis.lerp(to.y_axis, t),
+            self.from.z_axis.lerp(to.z_axis, t),
+            self.from.w_axis.lerp(to.w_axis, t),
+        )
+    }
+}
@@ -0,0 +1,82 @@
+use std::path::PathBuf;
+
+use super::Stage;
+use crate::scene::{CameraDescription, LightDescription, ObjectDescription, Scene};
+
+// loading and saving scenes
+impl Stage {
+    /// Replace every object, the camera position, the sun and the environment with the ones of
+    /// the scene in `file`. Missing assets are reported and left out, the number of them is
+    /// returned. A file that can not be read changes nothing
+    pub fn load_scene(&mut self, file: PathBuf) -> Result<usize, String> {
+        let scene = Scene::load(&file)?;
+
+        for mut object in self.renderable_objects.drain(..) {
+            object.drop_gl_resources(&mut self.ctx);
+        }
+        self.selection = None;
+        // the scripts put theirs back
+        self.scripts.request_reload();
+
+        let mut problems = vec![];
+        for (index, object) in scene.objects.iter().enumerate() {
+            for mut mesh_object in scene.build_object(object, &mut problems) {
+                mesh_object.scene_object = Some(index);
+                self.renderable_objects.push(Box::new(mesh_object));
+            }
+        }
+
+        scene.camera.apply(&mut self.world.cam);
+        self.previous_camera_pose = self.world.cam.pose();
+        let cam = self.world.cam.clone();
+        self.camera_controllers[self.active_camera_controller].activate(&cam);
+
+        self.world.light = scene.light.build();
+        self.world.environment.drop_gl_resources(&mut self.ctx);
+        self.world.environment = scene.environment.build(&mut problems);
+
+        for problem in problems.iter() {
+            log::warn!("{}: {}", file.display(), problem);
+        }
+
+        self.scene = scene;
+        self.scene_file = file;
+        return Ok(problems.len());
+    }
+
+    /// Write the scene with the current camera, sun, environment settings and object transforms
+    /// to `file`
+    pub fn save_scene(&mut self, file: PathBuf) -> Result<(), String> {
+        // the parts of a model all sit at the object, any of them will do
+        for object in self.renderable_objects.iter() {
+            if let Some(index) = object.get_scene_object() {
+                self.scene.objects[index].set_transform(object.get_model_matrix());
+            }
+        }
+        self.scene.camera = CameraDescription::from_camera(&self.world.cam);
+        self.scene.light = LightDescription::from_light(&self.world.light);
+        self.scene.environment.enabled = self.world.environment.enabled;
+        self.scene.environment.intensity = self.world.environment.intensity;
+
+        self.scene.save(&file)?;
+        self.scene_file = file;
+        return Ok(());
+    }
+
+    /// Add `object` to the scene and the world, saving the scene keeps it. Nothing is added when
+    /// one of its assets is missing
+    pub fn add_to_scene(&mut self, object: ObjectDescription) -> Result<(), String> {
+        let mut problems = vec![];
+        let mesh_objects = self.scene.build_object(&object, &mut problems);
+        if !problems.is_empty() {
+            return Err(problems.join(", "));
+        }
+
+        for mut mesh_object in mesh_objects {
+            mesh_object.scene_object = Some(self.scene.objects.len());
+            self.renderable_objects.push(Box::new(mesh_object));
+        }
+        self.scene.objects.push(object);
+        return Ok(());
+    }
+}
@@ -0,0 +1,85 @@
+use std::collections::HashMap;
+
+use super::Stage;
+use crate::{
+    profiler::profile_scope,
+    scene::{ObjectDescription, Shape},
+};
+
+// running scripts and applying what they did
+impl Stage {
+    /// Reload the scripts if they changed and move or spawn the objects they asked for. Their
+    /// callbacks run with the simulation steps, see simulate
+    pub fn update_scripts(&mut self) {
+        profile_scope!("scripts");
+
+        if self.scripts.reload_if_needed() {
+            self.remove_script_objects();
+        }
+
+        // built like the objects of the scene file, with its materials
+        let mut problems = vec![];
+        for spawn in self.scripts.take_spawns() {
+            let shape = match spawn.shape.as_str() {
+                "cube" => Shape::Cube { size: 1.0 },
+                "sphere" => Shape::Sphere { radius: 0.5 },
+                "plane" => Shape::Plane {
+                    size: 1.0,
+                    uv_scale: 1.0,
+                },
+                path => Shape::Model {
+                    path: path.to_owned(),
+                },
+            };
+            let description = ObjectDescription {
+                name: format!("script {} {}", spawn.shape, spawn.id),
+                shape,
+                material: spawn.material,
+                position: [0.0; 3],
+                rotation: [0.0; 3],
+                scale: [1.0; 3],
+                instances: vec![],
+            };
+
+            for mut object in self.scene.build_object(&description, &mut problems) {
+                object.transform = spawn.transform;
+                object.script_id = Some(spawn.id);
+                self.renderable_objects.push(Box::new(object));
+            }
+        }
+        for problem in problems {
+            log::warn!("{}: {}", self.scripts.file.display(), problem);
+        }
+
+        let moves: HashMap<i64, glam::Mat4> = self.scripts.take_moves().into_iter().collect();
+        if moves.is_empty() {
+            return;
+        }
+        for object in self.renderable_objects.iter_mut() {
+            if let Some(id) = object.get_script_id()
+                && let Some(transform) = moves.get(&id)
+            {
+                object.set_model_matrix(*transform);
+            }
+        }
+    }
+
+    /// Everything the previous run of the scripts spawned goes before the new run's objects come
+    fn remove_script_objects(&mut self) {
+        let count = self.renderable_objects.len();
+        let mut kept = Vec::with_capacity(count);
+        for mut object in self.renderable_objects.drain(..) {
+            if object.get_script_id().is_some() {
+                object.drop_gl_resources(&mut self.ctx);
+            } else {
+                kept.push(object);
+            }
+        }
+
+        // the selection is an index, the objects after a removed one moved
+        if kept.len() != count {
+            self.selection = None;
+        }
+        self.renderable_objects = kept;
+    }
+}
@@ -0,0 +1,141 @@
+use std::collections::HashMap;
+
+/// one `char` line of a BMFont description, in pixels of the page image
+pub struct BitmapChar {
+    pub x: f32,
+    pub y: f32,
+    pub width: f32,
+    pub height: f32,
+    /// from the top of the line to the top of the glyph
+    pub xoffset: f32,
+    pub yoffset: f32,
+    pub xadvance: f32,
+}
+
+/// Font description in the AngelCode BMFont text format, as written by BMFont, Hiero and most
+/// other bitmap font tools. The binary and xml variants are not supported
+pub struct BitmapFont {
+    pub line_height: f32,
+    /// distance from the top of a line to the baseline
+    pub base: f32,
+    /// image holding the glyphs, relative to the .fnt file. Only single page fonts are supported
+    pub page: String,
+    pub chars: HashMap<char, BitmapChar>,
+    pub kerning: HashMap<(char, char), f32>,
+}
+
+impl BitmapFont {
+    pub fn parse(source: &str) -> Result<Self, String> {
+        let mut font = Self {
+            line_height: 0.0,
+            base: 0.0,
+            page: String::new(),
+            chars: HashMap::new(),
+            kerning: HashMap::new(),
+        };
+
+        for (number, line) in source.lines().enumerate() {
+            let mut words = split_words(line).into_iter();
+            let Some(tag) = words.next() else {
+                continue;
+            };
+
+            let attributes: HashMap<String, String> = words
+                .filter_map(|word| {
+                    let (key, value) = word.split_once('=')?;
+                    Some((key.to_owned(), value.to_owned()))
+                })
+                .collect();
+
+            let number_of = |key: &str| -> Result<f32, String> {
+                attributes
+                    .get(key)
+                    .and_then(|v| v.parse::<f32>().ok())
+                    .ok_or(format!(
+                        "line {}: {} needs a numeric {}",
+                        number + 1,
+                        tag,
+                        key
+                    ))
+            };
+
+            let char_of = |key: &str| -> Result<char, String> {
+                char::from_u32(number_of(key)? as u32).ok_or(format!(
+                    "line {}: {} is not a character",
+                    number + 1,
+                    key
+                ))
+            };
+
+            match tag.as_str() {
+                "common" => {
+                    font.line_height = number_of("lineHeight")?;
+                    font.base = number_of("base")?;
+
+                    if number_of("pages")? != 1.0 {
+                        return Err("only bitmap fonts with a single page are supported".to_owned());
+                    }
+                }
+                "page" => {
+                    font.page = attributes
+                        .get("file")
+                        .cloned()
+                        .ok_or(format!("line {}: page without a file", number + 1))?;
+                }
+                "char" => {
+                    font.chars.insert(
+                        char_of("id")?,
+                        BitmapChar {
+                            x: number_of("x")?,
+                            y: number_of("y")?,
+                            width: number_of("width")?,
+                            height: number_of("height")?,
+                            xoffset: number_of("xoffset")?,
+                            yoffset: number_of("yoffset")?,
+                            xadvance: number_of("xadvance")?,
+                        },
+                    );
+                }
+                "kerning" => {
+                    font.kerning.insert(
+                        (char_of("first")?, char_of("second")?),
+                        number_of("amount")?,
+                    );
+                }
+                // info, chars and kernings only hold information that is not needed
+                _ => {}
+            }
+        }
+
+        if font.page.is_empty() {
+            return Err("bitmap font does not name a page image".to_owned());
+        }
+
+        return Ok(font);
+    }
+}
+
+/// split at spaces, except inside of quotes which are removed: `face="Dejavu Sans" size=16`
+fn split_words(line: &str) -> Vec<String> {
+    let mut words = vec![];
+    let mut word = String::new();
+    let mut quoted = false;
+
+    for c in line.chars() {
+        match c {
+            '"' => quoted = !quoted,
+            ' ' | '\t' if !quoted => {
+                if !word.is_empty() {
+                    words.push(std::mem::take(&mut word));
+                }
+            }
+            _ => word.push(c),
+        }
+    }
+
+    if !word.is_empty() {
+        words.push(word);
+    }
+
+    return words;
+}
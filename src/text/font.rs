@@ -0,0 +1,293 @@
+use std::{collections::HashMap, path::Path};
+
+use glam::{Vec2, vec2};
+use miniquad::{
+    FilterMode, MipmapFilterMode, RenderingBackend, TextureAccess, TextureFormat, TextureId,
+    TextureKind, TextureParams, TextureSource, TextureWrap,
+};
+
+use super::bmfont::BitmapFont;
+use crate::{profiler::profile_scope, textures};
+
+type BackendArg = Box<dyn RenderingBackend>;
+
+/// width of the glyph atlas, it grows in height when it runs out of space
+const ATLAS_WIDTH: u32 = 512;
+
+/// Where a glyph lives in the atlas and how it is placed relative to the pen, all in pixels
+#[derive(Clone, Copy)]
+pub struct Glyph {
+    pub atlas_min: Vec2,
+    pub size: Vec2,
+    /// from the pen position on the baseline to the top left corner, y points down
+    pub offset: Vec2,
+    pub advance: f32,
+}
+
+enum GlyphSource {
+    /// glyphs are rasterized at `size` pixels the first time they are used
+    Outline { font: fontdue::Font, size: f32 },
+    /// every glyph is already on the page which was copied into the atlas
+    Bitmap(BitmapFont),
+}
+
+/// A font with all of its glyphs packed into one texture.
+/// TTF and OTF fonts are rasterized on demand, BMFont fonts come pre rendered
+pub struct Font {
+    pub name: String,
+
+    /// distance between two baselines
+    pub line_height: f32,
+    /// distance from the top of a line to the baseline
+    pub ascent: f32,
+
+    source: GlyphSource,
+    /// None for characters the font can not show
+    glyphs: HashMap<char, Option<Glyph>>,
+    atlas: Atlas,
+}
+
+impl Font {
+    /// Load a .ttf, .otf or BMFont .fnt file. `size` is the pixel height outline fonts are
+    /// rasterized at, bitmap fonts always have the size they were rendered with
+    pub fn load(path: &str, size: f32) -> Result<Self, String> {
+        profile_scope!("load font");
+        let file = Path::new(path);
+        let extension = file
+            .extension()
+            .and_then(|e| e.to_str())
+            .unwrap_or("")
+            .to_lowercase();
+
+        match extension.as_str() {
+            "ttf" | "otf" => {
+                let bytes =
+                    std::fs::read(file).map_err(|e| format!("could not read {}: {}", path, e))?;
+                let font = fontdue::Font::from_bytes(bytes, fontdue::FontSettings::default())
+                    .map_err(|e| format!("could not parse {}: {}", path, e))?;
+
+                let metrics = font
+                    .horizontal_line_metrics(size)
+                    .ok_or(format!("{} has no horizontal metrics", path))?;
+
+                Ok(Self {
+                    name: path.to_owned(),
+                    line_height: metrics.new_line_size,
+                    ascent: metrics.ascent,
+                    source: GlyphSource::Outline { font, size },
+                    glyphs: HashMap::new(),
+                    atlas: Atlas::new(ATLAS_WIDTH, 128),
+                })
+            }
+            "fnt" => {
+                let source =
+                    std::fs::read_to_string(file).map_err(|e| format!("{}: {}", path, e))?;
+                let font = BitmapFont::parse(&source).map_err(|e| format!("{}: {}", path, e))?;
+
+                // textures are loaded relative to ./sprites unless the path is absolute
+                let page = file.parent().unwrap_or(Path::new(".")).join(&font.page);
+                let page = std::fs::canonicalize(&page)
+                    .map_err(|e| format!("bitmap font page {}: {}", page.display(), e))?;
+                let image = textures::Texture::load(page.to_string_lossy().into_owned(), false)?;
+
+                let mut atlas = Atlas::new(image.width as u32, image.height as u32);
+                atlas.pixels.copy_from_slice(image.rgba8_bytes());
+
+                Ok(Self {
+                    name: path.to_owned(),
+                    line_height: font.line_height,
+                    ascent: font.base,
+                    source: GlyphSource::Bitmap(font),
+                    glyphs: HashMap::new(),
+                    atlas,
+                })
+            }
+            _ => Err(format!("{} is not a .ttf, .otf or .fnt font", path)),
+        }
+    }
+
+    /// the glyph of `c`, missing characters are shown as '?'
+    pub fn glyph(&mut self, c: char) -> Option<Glyph> {
+        if let Some(glyph) = self.glyphs.get(&c) {
+            return *glyph;
+        }
+
+        let glyph = match &self.source {
+            GlyphSource::Outline { font, size } => {
+                if font.has_glyph(c) || c.is_whitespace() {
+                    let (metrics, coverage) = font.rasterize(c, *size);
+
+                    // white with the coverage as alpha so the text color can simply be multiplied
+                    let rgba: Vec<u8> = coverage.iter().flat_map(|a| [255, 255, 255, *a]).collect();
+                    let atlas_min =
+                        self.atlas
+                            .insert(metrics.width as u32, metrics.height as u32, &rgba);
+
+                    Some(Glyph {
+                        atlas_min,
+                        size: vec2(metrics.width as f32, metrics.height as f32),
+                        offset: vec2(
+                            metrics.xmin as f32,
+                            -(metrics.ymin as f32 + metrics.height as f32),
+                        ),
+                        advance: metrics.advance_width,
+                    })
+                } else {
+                    None
+                }
+            }
+            GlyphSource::Bitmap(font) => font.chars.get(&c).map(|ch| Glyph {
+                atlas_min: vec2(ch.x, ch.y),
+                size: vec2(ch.width, ch.height),
+                offset: vec2(ch.xoffset, ch.yoffset - font.base),
+                advance: ch.xadvance,
+            }),
+        };
+
+        let glyph = match glyph {
+            None if c != '?' => self.glyph('?'),
+            glyph => glyph,
+        };
+
+        self.glyphs.insert(c, glyph);
+        return glyph;
+    }
+
+    /// extra space between two characters, usually negative
+    pub fn kerning(&self, left: char, right: char) -> f32 {
+        match &self.source {
+            GlyphSource::Outline { font, size } => {
+                font.horizontal_kern(left, right, *size).unwrap_or(0.0)
+            }
+            GlyphSource::Bitmap(font) => *font.kerning.get(&(left, right)).unwrap_or(&0.0),
+        }
+    }
+
+    /// size of the atlas in pixels, glyph positions are relative to it
+    pub fn atlas_size(&self) -> Vec2 {
+        vec2(self.atlas.width as f32, self.atlas.height as f32)
+    }
+
+    /// Get the atlas texture, uploading it again if glyphs were added since the last call
+    pub fn get_texture(&mut self, ctx: &mut BackendArg) -> TextureId {
+        self.atlas.get_texture(ctx)
+    }
+
+    /// deallocate any resources that are allocated on opengl
+    pub fn drop_gl_resources(&mut self, ctx: &mut BackendArg) {
+        if let Some(val) = self.atlas.texture.take() {
+            ctx.delete_texture(val);
+        }
+    }
+}
+
+impl Drop for Font {
+    fn drop(&mut self) {
+        if self.atlas.texture.is_some() {
+            panic!("Font {} was not cleared properly", self.name);
+        }
+    }
+}
+
+/// rgba pixels glyphs are packed into row by row
+struct Atlas {
+    width: u32,
+    height: u32,
+    pixels: Vec<u8>,
+
+    /// where the next glyph goes and the height of the row it is put in
+    cursor: (u32, u32),
+    row_height: u32,
+
+    /// the pixels changed since the last upload
+    dirty: bool,
+
+    /// opengl vars
+    texture: Option<TextureId>,
+    texture_size: (u32, u32),
+}
+
+impl Atlas {
+    fn new(width: u32, height: u32) -> Self {
+        Self {
+            width,
+            height,
+            pixels: vec![0; (width * height * 4) as usize],
+            cursor: (0, 0),
+            row_height: 0,
+            dirty: true,
+            texture: None,
+            texture_size: (0, 0),
+        }
+    }
+
+    /// copy a `width` x `height` rgba image into a free spot and return its top left corner
+    fn insert(&mut self, width: u32, height: u32, rgba: &[u8]) -> Vec2 {
+        // one pixel of space around every glyph so linear filtering does not bleed
+        const PADDING: u32 = 1;
+
+        if self.cursor.0 + width + PADDING > self.width {
+            self.cursor = (0, self.cursor.1 + self.row_height);
+            self.row_height = 0;
+        }
+
+        while self.cursor.1 + height + PADDING > self.height {
+            // rows are stored top to bottom, so growing only appends pixels
+            self.height *= 2;
+            self.pixels
+                .resize((self.width * self.height * 4) as usize, 0);
+        }
+
+        let (x, y) = self.cursor;
+        for row in 0..height {
+            let source = (row * width * 4) as usize;
+            let target = (((y + row) * self.width + x) * 4) as usize;
+            self.pixels[target..target + (width * 4) as usize]
+                .copy_from_slice(&rgba[source..source + (width * 4) as usize]);
+        }
+
+        self.cursor.0 += width + PADDING;
+        self.row_height = self.row_height.max(height + PADDING);
+        self.dirty = true;
+
+        return vec2(x as f32, y as f32);
+    }
+
+    fn get_texture(&mut self, ctx: &mut BackendArg) -> TextureId {
+        if !self.dirty {
+            return self.texture.unwrap();
+        }
+
+        match self.texture {
+            Some(texture) if self.texture_size == (self.width, self.height) => {
+                ctx.texture_update(texture, &self.pixels);
+            }
+            _ => {
+                if let Some(val) = self.texture.take() {
+                    ctx.delete_texture(val);
+                }
+
+                self.texture = Some(ctx.new_texture(
+                    TextureAccess::Static,
+                    TextureSource::Bytes(&self.pixels),
+                    TextureParams {
+                        kind: TextureKind::Texture2D,
+                        format: TextureFormat::RGBA8,
+                        wrap: TextureWrap::Clamp,
+                        min_filter: FilterMode::Linear,
+                        mag_filter: FilterMode::Linear,
+                        mipmap_filter: MipmapFilterMode::None,
+                        width: self.width,
+                        height: self.height,
+                        allocate_mipmaps: false,
+                        sample_count: 1,
+                    },
+                ));
+                self.texture_size = (self.width, self.height);
+            }
+        }
+
+        self.dirty = false;
+        return self.texture.unwrap();
+    }
+}
@@ -0,0 +1,134 @@
+use glam::{Vec2, vec2};
+
+use super::{Font, Glyph, TextAlign};
+
+/// a glyph placed relative to the top left corner of the text block, in font pixels
+pub struct PositionedGlyph {
+    pub glyph: Glyph,
+    pub pos: Vec2,
+}
+
+pub struct TextLayout {
+    pub glyphs: Vec<PositionedGlyph>,
+    /// width and height of the whole block
+    pub size: Vec2,
+}
+
+/// Break `text` into lines at newlines and, if `max_width` is given, at the last space that keeps
+/// a line narrower than `max_width`. Words longer than a line are split where they overflow.
+/// Lines are aligned inside of `max_width` or, without one, inside of the widest line
+pub fn layout(font: &mut Font, text: &str, max_width: Option<f32>, align: TextAlign) -> TextLayout {
+    let mut lines: Vec<Vec<char>> = vec![];
+
+    for paragraph in text.split('\n') {
+        let chars: Vec<char> = paragraph.chars().collect();
+        let mut start = 0;
+
+        loop {
+            let end = match max_width {
+                Some(max_width) => line_end(font, &chars, start, max_width),
+                None => chars.len(),
+            };
+
+            lines.push(chars[start..end].to_vec());
+
+            // the space a line was broken at belongs to neither line
+            start = end;
+            if chars.get(start) == Some(&' ') {
+                start += 1;
+            }
+
+            if start >= chars.len() {
+                break;
+            }
+        }
+    }
+
+    let widths: Vec<f32> = lines.iter().map(|line| line_width(font, line)).collect();
+    let block_width = max_width.unwrap_or(widths.iter().copied().fold(0.0, f32::max));
+
+    let mut glyphs = vec![];
+    for (index, line) in lines.iter().enumerate() {
+        let mut pen = vec2(
+            match align {
+                TextAlign::Left => 0.0,
+                TextAlign::Center => (block_width - widths[index]) * 0.5,
+                TextAlign::Right => block_width - widths[index],
+            },
+            font.ascent + index as f32 * font.line_height,
+        );
+
+        let mut previous = None;
+        for c in line.iter().copied() {
+            let Some(glyph) = font.glyph(c) else {
+                continue;
+            };
+
+            if let Some(previous) = previous {
+                pen.x += font.kerning(previous, c);
+            }
+
+            if glyph.size.x > 0.0 && glyph.size.y > 0.0 {
+                glyphs.push(PositionedGlyph {
+                    glyph,
+                    pos: pen + glyph.offset,
+                });
+            }
+
+            pen.x += glyph.advance;
+            previous = Some(c);
+        }
+    }
+
+    return TextLayout {
+        glyphs,
+        size: vec2(block_width, lines.len() as f32 * font.line_height),
+    };
+}
+
+/// index after the last character of the line starting at `start`
+fn line_end(font: &mut Font, chars: &[char], start: usize, max_width: f32) -> usize {
+    let mut width = 0.0;
+    let mut previous = None;
+    let mut last_space = None;
+
+    for (i, c) in chars.iter().copied().enumerate().skip(start) {
+        let advance = advance(font, previous, c);
+
+        if width + advance > max_width && i > start && c != ' ' {
+            return match last_space {
+                Some(space) if space > start => space,
+                _ => i,
+            };
+        }
+
+        if c == ' ' {
+            last_space = Some(i);
+        }
+
+        width += advance;
+        previous = Some(c);
+    }
+
+    return chars.len();
+}
+
+/// width of a line without trailing spaces, so they do not shift aligned text
+fn line_width(font: &mut Font, line: &[char]) -> f32 {
+    let trimmed = line.len() - line.iter().rev().take_while(|c| **c == ' ').count();
+
+    let mut width = 0.0;
+    let mut previous = None;
+    for c in line[..trimmed].iter().copied() {
+        width += advance(font, previous, c);
+        previous = Some(c);
+    }
+
+    return width;
+}
+
+fn advance(font: &mut Font, previous: Option<char>, c: char) -> f32 {
+    let kerning = previous.map_or(0.0, |p| font.kerning(p, c));
+
+    return kerning + font.glyph(c).map_or(0.0, |g| g.advance);
+}
@@ -0,0 +1,40 @@
+mod bmfont;
+mod font;
+mod layout;
+mod renderer;
+pub use font::Font;
+pub use renderer::TextRenderer;
+
+use font::Glyph;
+use layout::layout;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum TextAlign {
+    Left,
+    Center,
+    Right,
+}
+
+pub struct TextStyle {
+    pub color: glam::Vec4,
+
+    /// screen space text is `scale` window pixels per font pixel, world space text `scale` world
+    /// units per font pixel
+    pub scale: f32,
+
+    pub align: TextAlign,
+
+    /// wrap lines that get wider than this, in the same unit as `scale`
+    pub max_width: Option<f32>,
+}
+
+impl Default for TextStyle {
+    fn default() -> Self {
+        Self {
+            color: glam::Vec4::ONE,
+            scale: 1.0,
+            align: TextAlign::Left,
+            max_width: None,
+        }
+    }
+}
@@ -0,0 +1,308 @@
+use glam::{Mat4, Vec2, Vec3, vec2};
+use miniquad::{
+    Bindings, BlendFactor, BlendState, BlendValue, BufferId, BufferLayout, BufferSource,
+    BufferType, BufferUsage, Equation, PassAction, Pipeline, PipelineParams, RenderPass,
+    RenderingBackend, ShaderId, UniformsSource, VertexAttribute, VertexFormat,
+};
+
+use super::{Font, TextStyle, layout};
+use crate::{profiler::profile_scope, shaders::ShaderFile};
+
+type BackendArg = Box<dyn RenderingBackend>;
+
+#[repr(C)]
+#[derive(Clone, Copy)]
+struct TextVertex {
+    pos: Vec3,
+    uv: Vec2,
+    color: [f32; 4],
+}
+
+/// one glyph waiting to be drawn, the uvs are kept in atlas pixels since the atlas can still grow
+/// before the end of the frame
+struct QueuedQuad {
+    /// billboard position for world space text, None for screen space text
+    anchor: Option<Vec3>,
+    min: Vec2,
+    max: Vec2,
+    atlas_min: Vec2,
+    atlas_max: Vec2,
+    color: [f32; 4],
+}
+
+/// Collects text during the frame and draws it on top of everything in one go.
+/// Screen space positions are window pixels with the origin in the top left corner, world space
+/// text always faces the camera
+pub struct TextRenderer {
+    /// without a font every draw call is silently ignored
+    pub font: Option<Font>,
+
+    shader_file: ShaderFile,
+    quads: Vec<QueuedQuad>,
+
+    /// in quads, the buffers grow when more text is queued
+    capacity: usize,
+
+    /// opengl vars
+    shader: Option<ShaderId>,
+    pipeline: Option<Pipeline>,
+    vertex_buffer: Option<BufferId>,
+    index_buffer: Option<BufferId>,
+}
+
+impl TextRenderer {
+    pub fn new(font: Option<Font>) -> Self {
+        Self {
+            font,
+            shader_file: ShaderFile::new("text".to_owned()),
+            quads: vec![],
+            capacity: 0,
+            shader: None,
+            pipeline: None,
+            vertex_buffer: None,
+            index_buffer: None,
+        }
+    }
+
+    /// queue text with its top left corner at `pos`
+    pub fn draw_screen(&mut self, text: &str, pos: Vec2, style: &TextStyle) {
+        self.queue(text, None, pos, style);
+    }
+
+    /// queue a label centered above `anchor`
+    pub fn draw_world(&mut self, text: &str, anchor: Vec3, style: &TextStyle) {
+        self.queue(text, Some(anchor), Vec2::ZERO, style);
+    }
+
+    fn queue(&mut self, text: &str, anchor: Option<Vec3>, pos: Vec2, style: &TextStyle) {
+        let Some(font) = self.font.as_mut() else {
+            return;
+        };
+
+        let laid_out = layout(
+            font,
+            text,
+            style.max_width.map(|w| w / style.scale),
+            style.align,
+        );
+
+        let origin = match anchor {
+            Some(_) => vec2(-laid_out.size.x * 0.5, -laid_out.size.y) * style.scale,
+            None => pos,
+        };
+
+        for positioned in laid_out.glyphs {
+            let min = origin + positioned.pos * style.scale;
+
+            self.quads.push(QueuedQuad {
+                anchor,
+                min,
+                max: min + positioned.glyph.size * style.scale,
+                atlas_min: positioned.glyph.atlas_min,
+                atlas_max: positioned.glyph.atlas_min + positioned.glyph.size,
+                color: style.color.to_array(),
+            });
+        }
+    }
+
+    /// Draw and forget everything queued this frame into `output`, None being the window.
+    /// Must be called outside of a render pass
+    pub fn draw(
+        &mut self,
+        ctx: &mut BackendArg,
+        view_projection: Mat4,
+        view: Mat4,
+        (width, height): (f32, f32),
+        output: Option<RenderPass>,
+    ) {
+        profile_scope!("text");
+        let quads = std::mem::take(&mut self.quads);
+        if quads.is_empty() {
+            return;
+        }
+
+        self.ensure_pipeline(ctx);
+        let (Some(pipeline), Some(font)) = (self.pipeline, self.font.as_mut()) else {
+            return;
+        };
+
+        let atlas = font.get_texture(ctx);
+        let atlas_size = font.atlas_size();
+
+        // world space quads first so both kinds can be drawn from one buffer
+        let (world, screen): (Vec<&QueuedQuad>, Vec<&QueuedQuad>) =
+            quads.iter().partition(|q| q.anchor.is_some());
+
+        // camera right and up in world space, the first two rows of the rotation
+        let right = view.row(0).truncate();
+        let up = view.row(1).truncate();
+
+        let mut vertices = Vec::with_capacity(quads.len() * 4);
+        for quad in world.iter().chain(screen.iter()) {
+            let uv_min = quad.atlas_min / atlas_size;
+            let uv_max = quad.atlas_max / atlas_size;
+
+            for (corner, uv) in [
+                (vec2(quad.min.x, quad.min.y), vec2(uv_min.x, uv_min.y)),
+                (vec2(quad.max.x, quad.min.y), vec2(uv_max.x, uv_min.y)),
+                (vec2(quad.max.x, quad.max.y), vec2(uv_max.x, uv_max.y)),
+                (vec2(quad.min.x, quad.max.y), vec2(uv_min.x, uv_max.y)),
+            ] {
+                let pos = match quad.anchor {
+                    // text space points down, the world points up
+                    Some(anchor) => anchor + right * corner.x - up * corner.y,
+                    None => corner.extend(0.0),
+                };
+
+                vertices.push(TextVertex {
+                    pos,
+                    uv,
+                    color: quad.color,
+                });
+            }
+        }
+
+        self.ensure_capacity(ctx, quads.len());
+        ctx.buffer_update(self.vertex_buffer.unwrap(), BufferSource::slice(&vertices));
+
+        let screen_projection = Mat4::orthographic_rh(0.0, width, height, 0.0, -1.0, 1.0);
+
+        ctx.begin_pass(output, PassAction::Nothing);
+        ctx.apply_pipeline(&pipeline);
+        ctx.apply_bindings(&Bindings {
+            vertex_buffers: vec![self.vertex_buffer.unwrap()],
+            index_buffer: self.index_buffer.unwrap(),
+            images: vec![atlas],
+        });
+
+        for (first, count, view_projection) in [
+            (0, world.len(), view_projection),
+            (world.len(), screen.len(), screen_projection),
+        ] {
+            if count == 0 {
+                continue;
+            }
+
+            ctx.apply_uniforms(UniformsSource::table(&shader::Uniforms { view_projection }));
+            ctx.draw((first * 6) as i32, (count * 6) as i32, 1);
+        }
+
+        ctx.end_render_pass();
+    }
+
+    fn ensure_pipeline(&mut self, ctx: &mut BackendArg) {
+        if !self.shader_file.reload_if_needed() && self.pipeline.is_some() {
+            return;
+        }
+
+        let compiled = ctx.new_shader(self.shader_file.get_shadersource(), shader::meta());
+        self.shader_file.report_compile(&compiled);
+
+        let Ok(shader) = compiled else {
+            return;
+        };
+
+        if let Some(val) = self.pipeline.take() {
+            ctx.delete_pipeline(val);
+        }
+        if let Some(val) = self.shader.take() {
+            ctx.delete_shader(val);
+        }
+
+        self.pipeline = Some(ctx.new_pipeline(
+            &[BufferLayout::default()],
+            &[
+                VertexAttribute::new("in_pos", VertexFormat::Float3),
+                VertexAttribute::new("in_uv", VertexFormat::Float2),
+                VertexAttribute::new("in_color", VertexFormat::Float4),
+            ],
+            shader,
+            PipelineParams {
+                color_blend: Some(BlendState::new(
+                    Equation::Add,
+                    BlendFactor::Value(BlendValue::SourceAlpha),
+                    BlendFactor::OneMinusValue(BlendValue::SourceAlpha),
+                )),
+                ..Default::default()
+            },
+        ));
+        self.shader = Some(shader);
+    }
+
+    /// make sure the buffers can hold `quad_count` quads
+    fn ensure_capacity(&mut self, ctx: &mut BackendArg, quad_count: usize) {
+        if self.vertex_buffer.is_some() && quad_count <= self.capacity {
+            return;
+        }
+
+        self.drop_buffers(ctx);
+
+        self.capacity = quad_count.next_power_of_two().max(256);
+
+        self.vertex_buffer = Some(ctx.new_buffer(
+            BufferType::VertexBuffer,
+            BufferUsage::Stream,
+            BufferSource::empty::<TextVertex>(self.capacity * 4),
+        ));
+
+        let indices: Vec<u32> = (0..self.capacity as u32)
+            .flat_map(|quad| [0, 1, 2, 0, 2, 3].map(|i| quad * 4 + i))
+            .collect();
+        self.index_buffer = Some(ctx.new_buffer(
+            BufferType::IndexBuffer,
+            BufferUsage::Immutable,
+            BufferSource::slice(&indices),
+        ));
+    }
+
+    fn drop_buffers(&mut self, ctx: &mut BackendArg) {
+        if let Some(val) = self.vertex_buffer.take() {
+            ctx.delete_buffer(val);
+        }
+
+        if let Some(val) = self.index_buffer.take() {
+            ctx.delete_buffer(val);
+        }
+    }
+
+    /// deallocate any resources that are allocated on opengl
+    pub fn drop_gl_resources(&mut self, ctx: &mut BackendArg) {
+        self.drop_buffers(ctx);
+
+        if let Some(font) = self.font.as_mut() {
+            font.drop_gl_resources(ctx);
+        }
+
+        if let Some(val) = self.pipeline.take() {
+            ctx.delete_pipeline(val);
+        }
+
+        if let Some(val) = self.shader.take() {
+            ctx.delete_shader(val);
+        }
+    }
+}
+
+impl Drop for TextRenderer {
+    fn drop(&mut self) {
+        if self.vertex_buffer.is_some() || self.pipeline.is_some() {
+            panic!("Text renderer was not cleared properly");
+        }
+    }
+}
+
+mod shader {
+    use miniquad::*;
+    pub fn meta() -> ShaderMeta {
+        ShaderMeta {
+            images: vec!["atlas".to_string()],
+            uniforms: UniformBlockLayout {
+                uniforms: vec![UniformDesc::new("view_projection", UniformType::Mat4)],
+            },
+        }
+    }
+    #[repr(C)]
+    pub struct Uniforms {
+        pub view_projection: glam::f32::Mat4,
+    }
+}
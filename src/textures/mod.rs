@@ -1,4 +1,16 @@
-use std::{fs::File, io::Read, panic};
+use std::{
+    fs::File,
+    io::Read,
+    path::{Path, PathBuf},
+};
+
+use crate::profiler::profile_scope;
+
+/// where a texture `basename` is loaded from, relative to ./sprites
+pub fn sprite_path(basename: &str) -> PathBuf {
+    // joining an absolute path replaces the sprites folder
+    return Path::new("./sprites").join(basename);
+}
 
 /// represents a file loaded with stb_image_rust
 pub struct Texture {
@@ -14,7 +26,14 @@ pub struct Texture {
 }
 
 impl Texture {
-    pub fn new(basename: String) -> Self {
+    pub fn new(basename: String) -> Result<Self, String> {
+        Self::load(basename, true)
+    }
+
+    /// opengl expects the first row to be the bottom one, which is why `new` flips on load.
+    /// Cubemap faces however are addressed top-left first and must not be flipped
+    pub fn load(basename: String, flip_vertically: bool) -> Result<Self, String> {
+        profile_scope!("load texture");
         let mut ret = Self {
             _name: basename.to_owned(),
             _file_contents: vec![],
@@ -24,22 +43,19 @@ impl Texture {
             height: 0,
 
             comp: 0,
-            img: 0 as *mut u8,
+            img: std::ptr::null_mut(),
         };
 
-        let path = format!("./sprites/{}", basename);
-        println!("trying to load texture {}", path);
-        let mut f = File::open(path.clone()).expect("Error loading texture");
+        let path = sprite_path(&basename);
+        log::debug!("trying to load texture {}", path.display());
 
         let mut contents = vec![];
-        let err = f.read_to_end(&mut contents);
-
-        if err.is_err() {
-            panic!("could not load {}", basename);
-        }
+        File::open(&path)
+            .and_then(|mut f| f.read_to_end(&mut contents))
+            .map_err(|e| format!("could not load texture {}: {}", path.display(), e))?;
 
         unsafe {
-            stb_image_rust::stbi_set_flip_vertically_on_load(1);
+            stb_image_rust::stbi_set_flip_vertically_on_load(flip_vertically as i32);
             ret.img = stb_image_rust::stbi_load_from_memory(
                 contents.as_mut_ptr(),
                 contents.len() as i32,
@@ -50,15 +66,27 @@ impl Texture {
             );
         }
 
+        if ret.img.is_null() {
+            return Err(format!("could not decode texture {}", path.display()));
+        }
+
         ret.length = contents.len() as i32;
-        return ret;
+        return Ok(ret);
+    }
+
+    pub fn rgba8_bytes(&self) -> &[u8] {
+        unsafe { std::slice::from_raw_parts(self.img, (self.width * self.height * 4) as usize) }
     }
 }
 
 // must be deallocated correctly when dropped
 impl Drop for Texture {
     fn drop(&mut self) {
-        println!("dropped texture");
+        log::debug!("dropped texture");
+        // nothing was decoded for textures that failed to load
+        if self.img.is_null() {
+            return;
+        }
         unsafe {
             stb_image_rust::c_runtime::free(self.img);
         }
@@ -0,0 +1,181 @@
+// One clock for everything that changes over time. Frames take however long they take, the
+// simulation advances in steps of a fixed length so movement behaves the same at any frame rate.
+// Rendering shows the state between the last two steps, `alpha` of the way from the previous one
+use glam::*;
+
+/// steps per second unless changed
+pub const DEFAULT_SIMULATION_RATE: f64 = 60.0;
+
+/// Frame times add up with rounding errors, 144 frames of 1/144 s are a hair less than a second.
+/// Time this close to a full step counts as one so they still make 60 steps of 1/60 s
+const ROUNDING: f64 = 1e-9;
+
+/// Accumulates frame time and hands it out as whole simulation steps
+#[derive(Debug, Clone)]
+pub struct FixedTimestep {
+    /// seconds per simulation step
+    pub step: f64,
+    /// Most steps a single frame may run. After a long hitch the simulation falls behind the
+    /// clock instead of every following frame taking even longer to catch up
+    pub max_steps: u32,
+
+    /// frame time that was not simulated yet, less than one step between frames
+    accumulator: f64,
+    /// steps the last frame ran
+    pub last_steps: u32,
+    /// seconds of frame time thrown away because of max_steps since the start
+    pub dropped: f64,
+}
+
+impl FixedTimestep {
+    pub fn new(rate: f64) -> Self {
+        Self {
+            step: 1.0 / rate,
+            max_steps: 5,
+            accumulator: 0.0,
+            last_steps: 0,
+            dropped: 0.0,
+        }
+    }
+
+    /// steps per second
+    pub fn rate(&self) -> f64 {
+        1.0 / self.step
+    }
+
+    pub fn set_rate(&mut self, rate: f64) {
+        self.step = 1.0 / rate.max(1.0);
+    }
+
+    /// forget the time that was not simulated yet, so runs starting here take the same steps
+    pub fn reset(&mut self) {
+        self.accumulator = 0.0;
+    }
+
+    /// Add the time the last frame took and return how many steps to simulate for it
+    pub fn advance(&mut self, delta: f64) -> u32 {
+        self.accumulator += delta.max(0.0);
+
+        let mut steps = 0;
+        while self.accumulator + ROUNDING >= self.step && steps < self.max_steps {
+            self.accumulator -= self.step;
+            steps += 1;
+        }
+
+        // whatever is still owed is lost, only the part of a step is kept for the interpolation
+        if self.accumulator + ROUNDING >= self.step {
+            let mut kept = self.accumulator % self.step;
+            if kept + ROUNDING >= self.step {
+                kept = 0.0;
+            }
+            self.dropped += self.accumulator - kept;
+            self.accumulator = kept;
+        }
+        self.accumulator = self.accumulator.max(0.0);
+
+        self.last_steps = steps;
+        return steps;
+    }
+
+    /// 0..1, how far the frame is between the previous and the current simulation step
+    pub fn alpha(&self) -> f32 {
+        (self.accumulator / self.step).clamp(0.0, 1.0) as f32
+    }
+}
+
+/// The clock all update code reads instead of asking the system for the time. Advanced once at
+/// the start of every frame
+#[derive(Debug, Clone)]
+pub struct Time {
+    /// seconds the last frame took on the wall clock, or the fixed delta of a capture or replay
+    pub real_delta: f64,
+    /// real_delta times the time scale, 0 while paused. What the simulation and animations
+    /// advance by
+    pub delta: f64,
+    /// sum of all deltas
+    pub elapsed: f64,
+    /// sum of all real deltas, for things that should not stop with the game like notices
+    pub real_elapsed: f64,
+    /// frames since the start, the first frame is 1
+    pub frame: u64,
+
+    pub paused: bool,
+    /// below 1.0 for slow motion
+    pub time_scale: f64,
+
+    /// the steps the simulation is advanced in
+    pub simulation: FixedTimestep,
+
+    /// run one simulation step next frame even though time is paused
+    step_requested: bool,
+    /// wall clock time in seconds at the start of the last frame
+    last_now: f64,
+}
+
+impl Time {
+    /// `now` is the wall clock in seconds, e.g. date::now()
+    pub fn new(now: f64) -> Self {
+        Self {
+            real_delta: 0.0,
+            delta: 0.0,
+            elapsed: 0.0,
+            real_elapsed: 0.0,
+            frame: 0,
+            paused: false,
+            time_scale: 1.0,
+            simulation: FixedTimestep::new(DEFAULT_SIMULATION_RATE),
+            step_requested: false,
+            last_now: now,
+        }
+    }
+
+    /// Start a frame at wall clock time `now`. A `fixed_delta` replaces the measured frame time,
+    /// captures and replays use it to run at a known rate. Returns the simulation steps to take
+    pub fn begin_frame(&mut self, now: f64, fixed_delta: Option<f64>) -> u32 {
+        self.real_delta = fixed_delta.unwrap_or(now - self.last_now);
+        self.last_now = now;
+
+        self.delta = if self.step_requested {
+            self.simulation.step
+        } else if self.paused {
+            0.0
+        } else {
+            self.real_delta * self.time_scale
+        };
+        self.step_requested = false;
+
+        self.elapsed += self.delta;
+        self.real_elapsed += self.real_delta;
+        self.frame += 1;
+
+        return self.simulation.advance(self.delta);
+    }
+
+    pub fn toggle_pause(&mut self) {
+        self.paused = !self.paused;
+    }
+
+    /// pause and run exactly one simulation step next frame
+    pub fn step_one_frame(&mut self) {
+        self.paused = true;
+        self.step_requested = true;
+    }
+
+    /// unpaused at normal speed without leftover step time, how recordings and replays start
+    pub fn reset_simulation(&mut self) {
+        self.paused = false;
+        self.time_scale = 1.0;
+        self.step_requested = false;
+        self.simulation.reset();
+    }
+
+    /// The `time` shader uniform: elapsed, delta, real elapsed and the frame number
+    pub fn uniform(&self) -> Vec4 {
+        vec4(
+            self.elapsed as f32,
+            self.delta as f32,
+            self.real_elapsed as f32,
+            self.frame as f32,
+        )
+    }
+}